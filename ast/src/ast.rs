@@ -7,7 +7,7 @@ use crate::id::*;
 
 #[derive(Debug,PartialEq,Clone)]
 pub enum Statment {
-    Assign(u32, Value),
+    Assign(u32, Span, Value),
     Call(FunctionCall),
     Match(MatchStatment), // New case for match statements
 }
@@ -18,6 +18,7 @@ pub enum Statment {
 pub struct FuncDec {
     pub sig: FuncSig,
     pub body: FuncBlock,
+    pub debug_span: Span, // covers the signature only
 }
 
 #[derive(Debug,PartialEq,Clone)]
@@ -30,6 +31,7 @@ pub struct FuncSig {
 pub struct FuncBlock{
     pub body: Vec<Statment>, 
     pub ret: Option<Ret>,
+    pub debug_span: Span, // includes the braces
 }
 
 #[derive(Debug,PartialEq,Clone)]
@@ -64,7 +66,7 @@ pub struct FunctionCall {
 #[derive(Debug,PartialEq,Clone)]
 pub enum FValue {
     SelfRef(Span),
-    Name(u32),
+    Name(u32, Span),
     FuncCall(Box<FunctionCall>),
     Lambda(Box<Lambda>),
     MatchLambda(Box<MatchLambda>),
@@ -78,7 +80,7 @@ pub enum Value {
     Bool(bool),
     Atom(u32),
     String(u32),
-    Variable(u32),
+    Variable(u32, Span),
     SelfRef(Span),
     FuncCall(FunctionCall),
     Lambda(Box<Lambda>),
@@ -91,7 +93,7 @@ pub enum Value {
 impl From<FValue> for Value {
     fn from(fval: FValue) -> Self {
         match fval {
-            FValue::Name(name) => Value::Variable(name),
+            FValue::Name(name, span) => Value::Variable(name, span),
             FValue::FuncCall(func_call) => Value::FuncCall(*func_call),
             FValue::Lambda(lam) => Value::Lambda(lam),
             FValue::MatchLambda(m) => Value::MatchLambda(m),
@@ -138,6 +140,7 @@ pub enum MatchPattern {
 pub struct MatchArm {
     pub pattern: MatchPattern, // The pattern to match
     pub result: MatchOut, // Result of the match arm (a Value or a block)
+    pub debug_span: Span,
 }
 
 
@@ -264,7 +267,7 @@ impl<'input> StringTable<'input> {
 
     // Returns the string corresponding to an ID, or an error if the ID is out of bounds.
     pub fn get_escaped_string(&self, id: u32) -> String {
-        self.vec.get(id as usize).map(|r|unescape(&r[1..r.len()-1]).unwrap()).unwrap()
+        self.vec.get(id as usize).map(|r|unescape(&unescape_braces(&r[1..r.len()-1])).unwrap()).unwrap()
     }
 
 }
@@ -293,6 +296,27 @@ fn test_string_table() {
     assert_eq!(id_hello_again, id_hello);
 }
 
+// \{ and \} are only used by interpolated strings so unescape does not know them
+fn unescape_braces(s: &str) -> String {
+    let mut ans = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('\\', Some(b @ ('{' | '}'))) => ans.push(b),
+            ('\\', Some(next)) => {
+                ans.push(c);
+                ans.push(next);
+            }
+            _ => {
+                ans.push(c);
+                continue;
+            }
+        }
+        chars.next();
+    }
+    ans
+}
+
 #[test]
 fn test_string_table_unescape() {
     let input = "\"hello world\\n\"";
@@ -304,4 +328,13 @@ fn test_string_table_unescape() {
     // Check that we can retrieve "hello" by its ID
     let retrieved_hello = table.get_escaped_string(id);
     assert_eq!(retrieved_hello, "hello world\n");
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_unescape_braces() {
+    let input = r#""\{a\} \\{b}\n""#;
+    let mut table = StringTable::new();
+
+    let id = table.get_id(input);
+    assert_eq!(table.get_escaped_string(id), "{a} \\{b}\n");
+}
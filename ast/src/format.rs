@@ -0,0 +1,607 @@
+// Pretty printer that turns a parsed program back into canonical source.
+// Comments are not part of the ast so they are taken from the lexer trivia
+// and put back by position, right before the first item that comes after them.
+
+use codespan::{ByteIndex, Span};
+use lalrpop_util::ParseError;
+
+use crate::ast::*;
+use crate::lexer::{lex_comments, Comment, LexTag, Lexer};
+use crate::parser::ProgramParser;
+
+const INDENT: &str = "    ";
+
+// loosest level (matching the grammar precedence levels) that needs no parens
+const ANY_LEVEL: u8 = 8;
+const NO_OPS: u8 = 2;
+
+pub fn format_source(input: &str) -> Result<String, ParseError<usize, LexTag, ()>> {
+    let mut table = StringTable::new();
+    let program = ProgramParser::new().parse(input, &mut table, Lexer::new(input))?;
+    Ok(format_program(input, &program, &table))
+}
+
+pub fn format_program(input: &str, program: &[OuterExp], table: &StringTable) -> String {
+    let comments = lex_comments(input);
+    let tokens: Vec<(usize, LexTag, usize)> = Lexer::new(input).map_while(|t| t.ok()).collect();
+    let interps: Vec<(usize, usize)> = tokens
+        .iter()
+        .filter(|(_, tag, _)| *tag == LexTag::InterpString)
+        .map(|(start, _, end)| (*start, *end))
+        .collect();
+    let tokens: Vec<(usize, LexTag)> = tokens.into_iter().map(|(start, tag, _)| (start, tag)).collect();
+    let mut f = Formatter {
+        input,
+        table,
+        comments: &comments,
+        next_comment: 0,
+        tokens: &tokens,
+        interps: &interps,
+        out: String::with_capacity(input.len()),
+        indent: 0,
+        block_start: false,
+        force_blank: false,
+    };
+
+    for exp in program {
+        f.outer(exp);
+    }
+    f.flush_comments(input.len());
+
+    f.out.push('\n');
+    f.out
+}
+
+struct Formatter<'a> {
+    input: &'a str,
+    table: &'a StringTable<'a>,
+    comments: &'a [Comment<'a>],
+    next_comment: usize,
+    tokens: &'a [(usize, LexTag)],
+    interps: &'a [(usize, usize)],
+
+    out: String,
+    indent: usize,
+    block_start: bool, // no blank line right after an opening brace
+    force_blank: bool, // top level items are always split by a blank line
+}
+
+impl Formatter<'_> {
+    fn newline(&mut self) {
+        self.out.push('\n');
+        for _ in 0..self.indent {
+            self.out.push_str(INDENT);
+        }
+    }
+
+    // starts the line for an item found at pos in the source, keeping a blank line if the source had one
+    fn item_line(&mut self, pos: usize) {
+        if self.out.is_empty() {
+            self.force_blank = false;
+            return;
+        }
+
+        if !self.block_start && (self.force_blank || self.blank_before(pos)) {
+            self.out.push('\n');
+        }
+        self.block_start = false;
+        self.force_blank = false;
+        self.newline();
+    }
+
+    fn close_line(&mut self) {
+        self.block_start = false;
+        self.newline();
+    }
+
+    fn blank_before(&self, pos: usize) -> bool {
+        let before = &self.input[..pos];
+        before[before.trim_end().len()..].matches('\n').count() > 1
+    }
+
+    fn has_comment_before(&self, pos: usize) -> bool {
+        self.comments
+            .get(self.next_comment)
+            .is_some_and(|c| c.span.start().to_usize() < pos)
+    }
+
+    fn flush_comments(&mut self, pos: usize) {
+        while let Some(comment) = self.comments.get(self.next_comment) {
+            let start = comment.span.start().to_usize();
+            if start >= pos {
+                break;
+            }
+            self.next_comment += 1;
+
+            let line_start = self.input[..start].rfind('\n').map_or(0, |i| i + 1);
+            let trailing = !self.input[line_start..start].trim().is_empty();
+
+            if trailing && !self.out.is_empty() {
+                self.out.push(' ');
+            } else {
+                self.item_line(start);
+            }
+            self.out.push_str(comment.text);
+        }
+    }
+
+    fn name(&mut self, id: u32) {
+        self.out.push_str(self.table.get_raw_str(id));
+    }
+
+    fn names(&mut self, ids: &[u32]) {
+        self.out.push('(');
+        for (i, id) in ids.iter().enumerate() {
+            if i > 0 {
+                self.out.push_str(", ");
+            }
+            self.name(*id);
+        }
+        self.out.push(')');
+    }
+
+    fn outer(&mut self, exp: &OuterExp) {
+        self.force_blank = true;
+        match exp {
+            OuterExp::FuncDec(func) => {
+                // the span starts at the name so step back over the def keyword
+                let name_start = func.debug_span.start().to_usize();
+                let pos = self.input[..name_start].rfind("def").unwrap_or(name_start);
+
+                self.flush_comments(pos);
+                self.item_line(pos);
+                self.out.push_str("def ");
+                self.name(func.sig.name);
+                self.names(&func.sig.args);
+                self.out.push(' ');
+                self.block(&func.body);
+            }
+            OuterExp::ImportFunc(imp) => {
+                // imports carry no span so they just go where they are
+                if !self.out.is_empty() {
+                    self.out.push('\n');
+                    self.newline();
+                }
+                self.force_blank = false;
+                self.out.push_str("from ");
+                self.name(imp.path);
+                self.out.push_str(" import ");
+                self.name(imp.name);
+                self.out.push(';');
+            }
+        }
+    }
+
+    fn block(&mut self, block: &FuncBlock) {
+        let end = block.debug_span.end().to_usize() - 1; // the closing brace
+        if block.body.is_empty() && block.ret.is_none() && !self.has_comment_before(end) {
+            self.out.push_str("{}");
+            return;
+        }
+
+        self.out.push('{');
+        self.indent += 1;
+        self.block_start = true;
+
+        for stmt in block.body.iter() {
+            self.statment(stmt);
+        }
+        if let Some(ret) = &block.ret {
+            self.ret(ret, end);
+        }
+
+        self.flush_comments(end);
+        self.indent -= 1;
+        self.close_line();
+        self.out.push('}');
+    }
+
+    fn statment(&mut self, stmt: &Statment) {
+        let pos = match stmt {
+            Statment::Assign(_, span, _) => span.start(),
+            Statment::Call(call) => call.debug_span.start(),
+            Statment::Match(m) => m.debug_span.start(),
+        }
+        .to_usize();
+
+        self.flush_comments(pos);
+        self.item_line(pos);
+
+        match stmt {
+            Statment::Assign(name, _, val) => {
+                self.name(*name);
+                self.out.push_str(" = ");
+                self.value(val, ANY_LEVEL);
+            }
+            Statment::Call(call) => self.call(call),
+            Statment::Match(m) => self.match_statment(m),
+        }
+        self.out.push(';');
+    }
+
+    fn ret(&mut self, ret: &Ret, end: usize) {
+        let pos = match (ret, value_start(ret.get_value())) {
+            (Ret::Imp(_), Some(pos)) => pos,
+            (Ret::Exp(_), Some(pos)) => self.input[..pos].trim_end().len().saturating_sub("return".len()),
+            (_, None) => self.literal_ret_start(ret, end),
+        };
+        self.flush_comments(pos);
+        self.item_line(pos);
+
+        match ret {
+            Ret::Exp(val) => {
+                self.out.push_str("return ");
+                self.value(val, ANY_LEVEL);
+                self.out.push(';');
+            }
+            Ret::Imp(val) => self.value(val, ANY_LEVEL),
+        }
+    }
+
+    // literals have no span so walk back over the tokens from the closing brace
+    fn literal_ret_start(&self, ret: &Ret, end: usize) -> usize {
+        let last = self.tokens.partition_point(|(start, _)| *start < end);
+        let before = &self.tokens[..last];
+        let found = match ret {
+            Ret::Exp(_) => before.iter().rev().take(4).find(|(_, tag)| *tag == LexTag::Return),
+            Ret::Imp(_) => {
+                let lit = before.len().saturating_sub(1);
+                match before.get(lit.wrapping_sub(1)) {
+                    Some(neg @ (_, LexTag::Minus)) => Some(neg),
+                    _ => before.get(lit),
+                }
+            }
+        };
+        found.map_or(end, |(start, _)| *start)
+    }
+
+    fn value(&mut self, val: &Value, level: u8) {
+        match val {
+            Value::Int(i) => self.out.push_str(&i.to_string()),
+            Value::Float(f) => self.out.push_str(&format_float(*f)),
+            Value::Bool(b) => self.out.push_str(if *b { "true" } else { "false" }),
+            Value::Atom(id) | Value::String(id) | Value::Variable(id, _) => self.name(*id),
+            Value::Nil => self.out.push_str("nil"),
+            Value::SelfRef(_) => self.out.push_str("self"),
+
+            Value::FuncCall(call) => match call.name {
+                // interpolated strings are desugared into + so they are printed as written
+                FValue::BuildIn(_) if self.is_interp(call.debug_span) => {
+                    let span = call.debug_span;
+                    self.out.push_str(&self.input[span.start().to_usize()..span.end().to_usize()]);
+                }
+                FValue::BuildIn(op) => self.binary(op, &call.args, level),
+                _ => self.call(call),
+            },
+            Value::Lambda(l) => self.lambda(l),
+            Value::MatchLambda(ml) => self.match_lambda(ml),
+            Value::Match(m) => self.match_statment(m),
+            Value::BuildIn(_) => unreachable!("build in op should never be made as a value in the ast"),
+        }
+    }
+
+    fn is_interp(&self, span: Span) -> bool {
+        self.interps.binary_search(&(span.start().to_usize(), span.end().to_usize())).is_ok()
+    }
+
+    fn binary(&mut self, op: BuildIn, args: &[Value], level: u8) {
+        let (op_level, op_str) = build_in_info(op);
+        let parens = op_level > level;
+
+        // pow is the only right associative operator
+        let (left, right) = match op {
+            BuildIn::Pow => (op_level - 1, op_level),
+            _ => (op_level, op_level - 1),
+        };
+
+        if parens {
+            self.out.push('(');
+        }
+        self.value(&args[0], left);
+        self.out.push(' ');
+        self.out.push_str(op_str);
+        self.out.push(' ');
+        self.value(&args[1], right);
+        if parens {
+            self.out.push(')');
+        }
+    }
+
+    fn call(&mut self, call: &FunctionCall) {
+        let args = match is_pipe(call) {
+            true => {
+                self.value(&call.args[0], NO_OPS);
+                self.out.push_str(" |> ");
+                &call.args[1..]
+            }
+            false => &call.args[..],
+        };
+
+        match &call.name {
+            FValue::Name(id, _) => self.name(*id),
+            FValue::SelfRef(_) => self.out.push_str("self"),
+            FValue::FuncCall(inner) => self.call(inner),
+            FValue::Lambda(l) => self.lambda(l),
+            FValue::MatchLambda(ml) => self.match_lambda(ml),
+            FValue::BuildIn(op) => {
+                self.binary(*op, args, ANY_LEVEL);
+                return;
+            }
+        }
+
+        self.out.push('(');
+        for (i, arg) in args.iter().enumerate() {
+            if i > 0 {
+                self.out.push_str(", ");
+            }
+            self.value(arg, ANY_LEVEL);
+        }
+        self.out.push(')');
+    }
+
+    fn lambda(&mut self, l: &Lambda) {
+        self.out.push_str("fn");
+        self.names(&l.sig);
+        self.out.push_str(" -> ");
+        self.block(&l.body);
+    }
+
+    fn match_lambda(&mut self, ml: &MatchLambda) {
+        self.out.push_str("match fn ");
+        self.arms(&ml.arms, ml.debug_span.end().to_usize() - 1);
+    }
+
+    fn match_statment(&mut self, m: &MatchStatment) {
+        self.out.push_str("match ");
+        self.value(&m.val, ANY_LEVEL);
+        self.out.push(' ');
+        self.arms(&m.arms, m.debug_span.end().to_usize() - 1);
+    }
+
+    fn arms(&mut self, arms: &[MatchArm], end: usize) {
+        self.out.push('{');
+        self.indent += 1;
+        self.block_start = true;
+
+        for arm in arms {
+            let pos = arm.debug_span.start().to_usize();
+            self.flush_comments(pos);
+            self.item_line(pos);
+
+            match &arm.pattern {
+                MatchPattern::Literal(l) => self.value(&l.clone().into(), NO_OPS),
+                MatchPattern::Variable(id) => self.name(*id),
+                MatchPattern::Wildcard => self.out.push('_'),
+            }
+            self.out.push_str(" => ");
+            match &arm.result {
+                MatchOut::Value(val) => self.value(val, ANY_LEVEL),
+                MatchOut::Block(block) => self.block(block),
+            }
+            self.out.push(',');
+        }
+
+        self.flush_comments(end);
+        self.indent -= 1;
+        self.close_line();
+        self.out.push('}');
+    }
+}
+
+fn build_in_info(op: BuildIn) -> (u8, &'static str) {
+    match op {
+        BuildIn::Pow => (3, "**"),
+
+        BuildIn::Mul => (4, "*"),
+        BuildIn::Div => (4, "/"),
+        BuildIn::IntDiv => (4, "//"),
+        BuildIn::Modulo => (4, "%"),
+
+        BuildIn::Add => (5, "+"),
+        BuildIn::Sub => (5, "-"),
+
+        BuildIn::Xor => (6, "^"),
+        BuildIn::Or => (6, "|"),
+        BuildIn::And => (6, "&"),
+
+        BuildIn::Equal => (7, "=="),
+        BuildIn::NotEqual => (7, "!="),
+        BuildIn::Smaller => (7, "<"),
+        BuildIn::Bigger => (7, ">"),
+        BuildIn::SmallerEq => (7, "<="),
+        BuildIn::BiggerEq => (7, ">="),
+
+        BuildIn::DoubleAnd => (8, "&&"),
+        BuildIn::DoubleOr => (8, "||"),
+        BuildIn::DoubleXor => (8, "^^"),
+    }
+}
+
+// the lexer has no exponents so always write out every digit and a dot
+fn format_float(f: f64) -> String {
+    let s = f.to_string();
+    match s.contains('.') {
+        true => s,
+        false => s + ".0",
+    }
+}
+
+fn fvalue_start(name: &FValue) -> Option<ByteIndex> {
+    match name {
+        FValue::Name(_, span) | FValue::SelfRef(span) => Some(span.start()),
+        FValue::FuncCall(call) => Some(call.debug_span.start()),
+        FValue::Lambda(l) => Some(l.debug_span.start()),
+        FValue::MatchLambda(ml) => Some(ml.debug_span.start()),
+        FValue::BuildIn(_) => None,
+    }
+}
+
+// pipes are turned into regular calls, but their span starts at the piped value (before the function)
+fn is_pipe(call: &FunctionCall) -> bool {
+    fvalue_start(&call.name).is_some_and(|start| start > call.debug_span.start())
+}
+
+fn value_start(val: &Value) -> Option<usize> {
+    let start = match val {
+        Value::Variable(_, span) | Value::SelfRef(span) => span.start(),
+        Value::FuncCall(call) => call.debug_span.start(),
+        Value::Lambda(l) => l.debug_span.start(),
+        Value::MatchLambda(ml) => ml.debug_span.start(),
+        Value::Match(m) => m.debug_span.start(),
+        _ => return None,
+    };
+    Some(start.to_usize())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clear_block(block: &mut FuncBlock) {
+        block.debug_span = Span::default();
+        for stmt in block.body.iter_mut() {
+            match stmt {
+                Statment::Assign(_, span, val) => {
+                    *span = Span::default();
+                    clear_value(val);
+                }
+                Statment::Call(call) => clear_call(call),
+                Statment::Match(m) => clear_match(m),
+            }
+        }
+        match &mut block.ret {
+            Some(Ret::Imp(val)) | Some(Ret::Exp(val)) => clear_value(val),
+            None => {}
+        }
+    }
+
+    fn clear_call(call: &mut FunctionCall) {
+        call.debug_span = Span::default();
+        match &mut call.name {
+            FValue::Name(_, span) | FValue::SelfRef(span) => *span = Span::default(),
+            FValue::FuncCall(inner) => clear_call(inner),
+            FValue::Lambda(l) => clear_lambda(l),
+            FValue::MatchLambda(ml) => clear_arms(&mut ml.arms, &mut ml.debug_span),
+            FValue::BuildIn(_) => {}
+        }
+        call.args.iter_mut().for_each(clear_value);
+    }
+
+    fn clear_lambda(l: &mut Lambda) {
+        l.debug_span = Span::default();
+        clear_block(&mut l.body);
+    }
+
+    fn clear_match(m: &mut MatchStatment) {
+        clear_value(&mut m.val);
+        clear_arms(&mut m.arms, &mut m.debug_span);
+    }
+
+    fn clear_arms(arms: &mut [MatchArm], span: &mut Span) {
+        *span = Span::default();
+        for arm in arms {
+            arm.debug_span = Span::default();
+            match &mut arm.result {
+                MatchOut::Value(val) => clear_value(val),
+                MatchOut::Block(block) => clear_block(block),
+            }
+        }
+    }
+
+    fn clear_value(val: &mut Value) {
+        match val {
+            Value::Variable(_, span) | Value::SelfRef(span) => *span = Span::default(),
+            Value::FuncCall(call) => clear_call(call),
+            Value::Lambda(l) => clear_lambda(l),
+            Value::MatchLambda(ml) => clear_arms(&mut ml.arms, &mut ml.debug_span),
+            Value::Match(m) => clear_match(m),
+            _ => {}
+        }
+    }
+
+    fn parse_cleared<'a>(input: &'a str, table: &mut StringTable<'a>) -> Vec<OuterExp> {
+        let mut program = ProgramParser::new().parse(input, table, Lexer::new(input)).unwrap();
+        for exp in program.iter_mut() {
+            if let OuterExp::FuncDec(func) = exp {
+                func.debug_span = Span::default();
+                clear_block(&mut func.body);
+            }
+        }
+        program
+    }
+
+    fn check_round_trip(input: &str) -> String {
+        let output = format_source(input).unwrap();
+
+        // same table so both sides get the same ids
+        let mut table = StringTable::new();
+        let original = parse_cleared(input, &mut table);
+        let formatted = parse_cleared(&output, &mut table);
+        assert_eq!(original, formatted, "formatting changed the program:\n{}", output);
+
+        assert_eq!(format_source(&output).unwrap(), output, "formatting is not idempotent");
+        output
+    }
+
+    #[test]
+    fn round_trip_sample_files() {
+        check_round_trip(include_str!("../../sample.fay"));
+        check_round_trip(include_str!("../../matrix.fay"));
+    }
+
+    #[test]
+    fn round_trip_operators() {
+        check_round_trip(
+            "def f(a,b){x=(a+b)*2;y=a-(b-1);z=2**3**a;w=(2**3)**a;v=-1.5+a/b//2%3;\
+             k=(a<b)==(b>a)&&a!=b||true;j=a|b^(a|b);q= a ^^ b;1.0}",
+        );
+    }
+
+    #[test]
+    fn round_trip_pipes_and_calls() {
+        check_round_trip(
+            "def f(a){ a |> g() |> h(1,2); x = a(1)(2) |> fn(y) -> {y}(); \
+             match fn {_ => 1}(a); fn(){self()}(); b = a|> g()(1);}",
+        );
+    }
+
+    #[test]
+    fn keeps_interpolated_strings() {
+        let output = check_round_trip(r#"def f(a){ x = "a = {a +1}!"+ a; "{ "{a}" }" }"#);
+        assert_eq!(output, "def f(a) {\n    x = \"a = {a +1}!\" + a;\n    \"{ \"{a}\" }\"\n}\n");
+    }
+
+    #[test]
+    fn keeps_comments_and_blank_lines() {
+        let input = "# header\n\ndef f(a) { # trailing\n    x = a;\n\n\n    # own line\n    x # last\n    # end\n}\n# eof\n";
+        let output = check_round_trip(input);
+        assert_eq!(
+            output,
+            "# header\n\ndef f(a) { # trailing\n    x = a;\n\n    # own line\n    x # last\n    # end\n}\n# eof\n"
+        );
+    }
+
+    #[test]
+    fn canonical_layout() {
+        let output = check_round_trip(
+            "def g(){}\ndef f(a,b){x=1.0;match a{0=>{},_=>{return b;}};fn(x)->{x}(2)}",
+        );
+        assert_eq!(
+            output,
+            "def g() {}\n\
+             \n\
+             def f(a, b) {\n\
+             \x20   x = 1.0;\n\
+             \x20   match a {\n\
+             \x20       0 => {},\n\
+             \x20       _ => {\n\
+             \x20           return b;\n\
+             \x20       },\n\
+             \x20   };\n\
+             \x20   fn(x) -> {\n\
+             \x20       x\n\
+             \x20   }(2)\n\
+             }\n"
+        );
+    }
+}
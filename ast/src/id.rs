@@ -41,6 +41,165 @@ pub const DELETE_DIR_ID: u32 = 20;
 pub const STRING_OUT_OF_BOUNDS: u32 = 21;
 pub const SELF_ID: u32 = 22;
 
+// String module IDs
+pub const SPLIT_ID: u32 = 23;
+pub const JOIN_ID: u32 = 24;
+pub const SLICE_ID: u32 = 25;
+pub const FIND_ID: u32 = 26;
+pub const REPLACE_ID: u32 = 27;
+pub const TRIM_ID: u32 = 28;
+pub const UPPER_ID: u32 = 29;
+pub const LOWER_ID: u32 = 30;
+pub const STARTS_WITH_ID: u32 = 31;
+pub const ENDS_WITH_ID: u32 = 32;
+pub const REPEAT_ID: u32 = 33;
+pub const CHAR_CODE_ID: u32 = 34;
+pub const FROM_CHAR_CODE_ID: u32 = 35;
+pub const BYTE_LEN_ID: u32 = 36;
+pub const PARSE_INT_ID: u32 = 37;
+pub const PARSE_FLOAT_ID: u32 = 38;
+pub const PARSE_RADIX_ID: u32 = 39;
+pub const TO_RADIX_ID: u32 = 40;
+pub const FORMAT_ID: u32 = 41;
+
+// Math module IDs
+pub const MATH_ID: u32 = 42;
+pub const SQRT_ID: u32 = 43;
+pub const EXP_ID: u32 = 44;
+pub const LN_ID: u32 = 45;
+pub const LOG_ID: u32 = 46;
+pub const SIN_ID: u32 = 47;
+pub const COS_ID: u32 = 48;
+pub const TAN_ID: u32 = 49;
+pub const ASIN_ID: u32 = 50;
+pub const ACOS_ID: u32 = 51;
+pub const ATAN_ID: u32 = 52;
+pub const ATAN2_ID: u32 = 53;
+pub const FLOOR_ID: u32 = 54;
+pub const CEIL_ID: u32 = 55;
+pub const ROUND_ID: u32 = 56;
+pub const TRUNC_ID: u32 = 57;
+pub const ABS_ID: u32 = 58;
+pub const MIN_ID: u32 = 59;
+pub const MAX_ID: u32 = 60;
+pub const GCD_ID: u32 = 61;
+pub const PI_ID: u32 = 62;
+pub const E_ID: u32 = 63;
+
+// Random module IDs
+pub const RANDOM_ID: u32 = 64;
+pub const NEXT_INT_ID: u32 = 65;
+pub const NEXT_FLOAT_ID: u32 = 66;
+pub const RANGE_ID: u32 = 67;
+
+// Time IDs
+pub const NOW_ID: u32 = 68;
+pub const UNIX_TIME_ID: u32 = 69;
+pub const SLEEP_ID: u32 = 70;
+pub const FORMAT_TIME_ID: u32 = 71;
+pub const PARSE_TIME_ID: u32 = 72;
+pub const DATE_ID: u32 = 73;
+pub const YEAR_ID: u32 = 74;
+pub const MONTH_ID: u32 = 75;
+pub const DAY_ID: u32 = 76;
+pub const HOUR_ID: u32 = 77;
+pub const MINUTE_ID: u32 = 78;
+pub const SECOND_ID: u32 = 79;
+pub const UNIX_ID: u32 = 80;
+pub const KEYS_ID: u32 = 81;
+
+// Environment IDs
+pub const ENV_ID: u32 = 82;
+pub const ENV_VARS_ID: u32 = 83;
+pub const CWD_ID: u32 = 84;
+pub const SET_CWD_ID: u32 = 85;
+
+// Filesystem IDs
+pub const APPEND_FILE_ID: u32 = 86;
+pub const METADATA_ID: u32 = 87;
+pub const EXISTS_ID: u32 = 88;
+pub const RENAME_ID: u32 = 89;
+pub const COPY_FILE_ID: u32 = 90;
+pub const MAKE_DIR_ALL_ID: u32 = 91;
+pub const DELETE_DIR_ALL_ID: u32 = 92;
+pub const SIZE_ID: u32 = 93;
+pub const IS_FILE_ID: u32 = 94;
+pub const IS_DIR_ID: u32 = 95;
+pub const READONLY_ID: u32 = 96;
+pub const MODIFIED_ID: u32 = 97;
+pub const NOT_FOUND_ID: u32 = 98;
+pub const PERMISSION_DENIED_ID: u32 = 99;
+pub const ALREADY_EXISTS_ID: u32 = 100;
+pub const NOT_A_DIRECTORY_ID: u32 = 101;
+pub const IS_A_DIRECTORY_ID: u32 = 102;
+pub const DIRECTORY_NOT_EMPTY_ID: u32 = 103;
+pub const INVALID_DATA_ID: u32 = 104;
+pub const INVALID_INPUT_ID: u32 = 105;
+pub const IO_ERROR_ID: u32 = 106;
+
+// Bytes IDs
+pub const BYTES_ID: u32 = 107;
+pub const READ_BYTES_ID: u32 = 108;
+pub const WRITE_BYTES_ID: u32 = 109;
+pub const BYTES_OUT_OF_BOUNDS_ID: u32 = 110;
+pub const FROM_UTF8_ID: u32 = 111;
+pub const TO_UTF8_ID: u32 = 112;
+pub const FROM_HEX_ID: u32 = 113;
+pub const TO_HEX_ID: u32 = 114;
+pub const FROM_BASE64_ID: u32 = 115;
+pub const TO_BASE64_ID: u32 = 116;
+pub const FROM_ARRAY_ID: u32 = 117;
+pub const TO_ARRAY_ID: u32 = 118;
+
+// JSON IDs
+pub const JSON_PARSE_ID: u32 = 119;
+pub const JSON_STRINGIFY_ID: u32 = 120;
+
+// Printing IDs
+pub const PRINT_ID: u32 = 121;
+pub const EPRINTLN_ID: u32 = 122;
+
+// Process IDs
+pub const RUN_ID: u32 = 123;
+pub const START_PROCESS_ID: u32 = 124;
+pub const STATUS_ID: u32 = 125;
+pub const STDOUT_ID: u32 = 126;
+pub const STDERR_ID: u32 = 127;
+pub const WRITE_ID: u32 = 128;
+pub const READ_LINE_ID: u32 = 129;
+pub const WAIT_ID: u32 = 130;
+pub const EXIT_STATUS_ID: u32 = 131;
+
+// Process IDs
+pub const SPAWN_ID: u32 = 132;
+pub const SEND_ID: u32 = 133;
+pub const RECEIVE_ID: u32 = 134;
+pub const SELF_PID_ID: u32 = 135;
+pub const TIMEOUT_ID: u32 = 136;
+
+// Supervision IDs
+pub const LINK_ID: u32 = 137;
+pub const MONITOR_ID: u32 = 138;
+pub const SUPERVISE_ID: u32 = 139;
+pub const EXIT_ID: u32 = 140;
+pub const DOWN_ID: u32 = 141;
+pub const NORMAL_ID: u32 = 142;
+pub const NOPROC_ID: u32 = 143;
+pub const KILLED_ID: u32 = 144;
+pub const SHUTDOWN_ID: u32 = 145;
+pub const ONE_FOR_ONE_ID: u32 = 146;
+pub const ONE_FOR_ALL_ID: u32 = 147;
+
+// Stream IDs
+pub const STREAM_ID: u32 = 148;
+pub const EMIT_ID: u32 = 149;
+pub const READ_LINES_ID: u32 = 150;
+pub const NEXT_ID: u32 = 151;
+pub const MAP_ID: u32 = 152;
+pub const FILTER_ID: u32 = 153;
+pub const TAKE_ID: u32 = 154;
+pub const FOLD_ID: u32 = 155;
+
 
 pub fn preload_table(table: &mut StringTable) {
     assert_eq!(table.get_id(":nil"), NIL_ID);
@@ -72,6 +231,140 @@ pub fn preload_table(table: &mut StringTable) {
 
     assert_eq!(table.get_id(":string_out_of_bounds"), STRING_OUT_OF_BOUNDS);
     assert_eq!(table.get_id("self"), SELF_ID);
+
+    assert_eq!(table.get_id(":split"), SPLIT_ID);
+    assert_eq!(table.get_id(":join"), JOIN_ID);
+    assert_eq!(table.get_id(":slice"), SLICE_ID);
+    assert_eq!(table.get_id(":find"), FIND_ID);
+    assert_eq!(table.get_id(":replace"), REPLACE_ID);
+    assert_eq!(table.get_id(":trim"), TRIM_ID);
+    assert_eq!(table.get_id(":upper"), UPPER_ID);
+    assert_eq!(table.get_id(":lower"), LOWER_ID);
+    assert_eq!(table.get_id(":starts_with"), STARTS_WITH_ID);
+    assert_eq!(table.get_id(":ends_with"), ENDS_WITH_ID);
+    assert_eq!(table.get_id(":repeat"), REPEAT_ID);
+    assert_eq!(table.get_id(":char_code"), CHAR_CODE_ID);
+    assert_eq!(table.get_id(":from_char_code"), FROM_CHAR_CODE_ID);
+    assert_eq!(table.get_id(":byte_len"), BYTE_LEN_ID);
+    assert_eq!(table.get_id(":parse_int"), PARSE_INT_ID);
+    assert_eq!(table.get_id(":parse_float"), PARSE_FLOAT_ID);
+    assert_eq!(table.get_id(":parse_radix"), PARSE_RADIX_ID);
+    assert_eq!(table.get_id(":to_radix"), TO_RADIX_ID);
+    assert_eq!(table.get_id(":format"), FORMAT_ID);
+    assert_eq!(table.get_id(":math"), MATH_ID);
+    assert_eq!(table.get_id(":sqrt"), SQRT_ID);
+    assert_eq!(table.get_id(":exp"), EXP_ID);
+    assert_eq!(table.get_id(":ln"), LN_ID);
+    assert_eq!(table.get_id(":log"), LOG_ID);
+    assert_eq!(table.get_id(":sin"), SIN_ID);
+    assert_eq!(table.get_id(":cos"), COS_ID);
+    assert_eq!(table.get_id(":tan"), TAN_ID);
+    assert_eq!(table.get_id(":asin"), ASIN_ID);
+    assert_eq!(table.get_id(":acos"), ACOS_ID);
+    assert_eq!(table.get_id(":atan"), ATAN_ID);
+    assert_eq!(table.get_id(":atan2"), ATAN2_ID);
+    assert_eq!(table.get_id(":floor"), FLOOR_ID);
+    assert_eq!(table.get_id(":ceil"), CEIL_ID);
+    assert_eq!(table.get_id(":round"), ROUND_ID);
+    assert_eq!(table.get_id(":trunc"), TRUNC_ID);
+    assert_eq!(table.get_id(":abs"), ABS_ID);
+    assert_eq!(table.get_id(":min"), MIN_ID);
+    assert_eq!(table.get_id(":max"), MAX_ID);
+    assert_eq!(table.get_id(":gcd"), GCD_ID);
+    assert_eq!(table.get_id(":pi"), PI_ID);
+    assert_eq!(table.get_id(":e"), E_ID);
+    assert_eq!(table.get_id(":random"), RANDOM_ID);
+    assert_eq!(table.get_id(":next_int"), NEXT_INT_ID);
+    assert_eq!(table.get_id(":next_float"), NEXT_FLOAT_ID);
+    assert_eq!(table.get_id(":range"), RANGE_ID);
+    assert_eq!(table.get_id(":now"), NOW_ID);
+    assert_eq!(table.get_id(":unix_time"), UNIX_TIME_ID);
+    assert_eq!(table.get_id(":sleep"), SLEEP_ID);
+    assert_eq!(table.get_id(":format_time"), FORMAT_TIME_ID);
+    assert_eq!(table.get_id(":parse_time"), PARSE_TIME_ID);
+    assert_eq!(table.get_id(":date"), DATE_ID);
+    assert_eq!(table.get_id(":year"), YEAR_ID);
+    assert_eq!(table.get_id(":month"), MONTH_ID);
+    assert_eq!(table.get_id(":day"), DAY_ID);
+    assert_eq!(table.get_id(":hour"), HOUR_ID);
+    assert_eq!(table.get_id(":minute"), MINUTE_ID);
+    assert_eq!(table.get_id(":second"), SECOND_ID);
+    assert_eq!(table.get_id(":unix"), UNIX_ID);
+    assert_eq!(table.get_id(":keys"), KEYS_ID);
+    assert_eq!(table.get_id(":env"), ENV_ID);
+    assert_eq!(table.get_id(":env_vars"), ENV_VARS_ID);
+    assert_eq!(table.get_id(":cwd"), CWD_ID);
+    assert_eq!(table.get_id(":set_cwd"), SET_CWD_ID);
+    assert_eq!(table.get_id(":append_file"), APPEND_FILE_ID);
+    assert_eq!(table.get_id(":metadata"), METADATA_ID);
+    assert_eq!(table.get_id(":exists"), EXISTS_ID);
+    assert_eq!(table.get_id(":rename"), RENAME_ID);
+    assert_eq!(table.get_id(":copy_file"), COPY_FILE_ID);
+    assert_eq!(table.get_id(":make_dir_all"), MAKE_DIR_ALL_ID);
+    assert_eq!(table.get_id(":delete_dir_all"), DELETE_DIR_ALL_ID);
+    assert_eq!(table.get_id(":size"), SIZE_ID);
+    assert_eq!(table.get_id(":is_file"), IS_FILE_ID);
+    assert_eq!(table.get_id(":is_dir"), IS_DIR_ID);
+    assert_eq!(table.get_id(":readonly"), READONLY_ID);
+    assert_eq!(table.get_id(":modified"), MODIFIED_ID);
+    assert_eq!(table.get_id(":not_found"), NOT_FOUND_ID);
+    assert_eq!(table.get_id(":permission_denied"), PERMISSION_DENIED_ID);
+    assert_eq!(table.get_id(":already_exists"), ALREADY_EXISTS_ID);
+    assert_eq!(table.get_id(":not_a_directory"), NOT_A_DIRECTORY_ID);
+    assert_eq!(table.get_id(":is_a_directory"), IS_A_DIRECTORY_ID);
+    assert_eq!(table.get_id(":directory_not_empty"), DIRECTORY_NOT_EMPTY_ID);
+    assert_eq!(table.get_id(":invalid_data"), INVALID_DATA_ID);
+    assert_eq!(table.get_id(":invalid_input"), INVALID_INPUT_ID);
+    assert_eq!(table.get_id(":io_error"), IO_ERROR_ID);
+    assert_eq!(table.get_id(":bytes"), BYTES_ID);
+    assert_eq!(table.get_id(":read_bytes"), READ_BYTES_ID);
+    assert_eq!(table.get_id(":write_bytes"), WRITE_BYTES_ID);
+    assert_eq!(table.get_id(":bytes_out_of_bounds"), BYTES_OUT_OF_BOUNDS_ID);
+    assert_eq!(table.get_id(":from_utf8"), FROM_UTF8_ID);
+    assert_eq!(table.get_id(":to_utf8"), TO_UTF8_ID);
+    assert_eq!(table.get_id(":from_hex"), FROM_HEX_ID);
+    assert_eq!(table.get_id(":to_hex"), TO_HEX_ID);
+    assert_eq!(table.get_id(":from_base64"), FROM_BASE64_ID);
+    assert_eq!(table.get_id(":to_base64"), TO_BASE64_ID);
+    assert_eq!(table.get_id(":from_array"), FROM_ARRAY_ID);
+    assert_eq!(table.get_id(":to_array"), TO_ARRAY_ID);
+    assert_eq!(table.get_id(":json_parse"), JSON_PARSE_ID);
+    assert_eq!(table.get_id(":json_stringify"), JSON_STRINGIFY_ID);
+    assert_eq!(table.get_id(":print"), PRINT_ID);
+    assert_eq!(table.get_id(":eprintln"), EPRINTLN_ID);
+    assert_eq!(table.get_id(":run"), RUN_ID);
+    assert_eq!(table.get_id(":start_process"), START_PROCESS_ID);
+    assert_eq!(table.get_id(":status"), STATUS_ID);
+    assert_eq!(table.get_id(":stdout"), STDOUT_ID);
+    assert_eq!(table.get_id(":stderr"), STDERR_ID);
+    assert_eq!(table.get_id(":write"), WRITE_ID);
+    assert_eq!(table.get_id(":read_line"), READ_LINE_ID);
+    assert_eq!(table.get_id(":wait"), WAIT_ID);
+    assert_eq!(table.get_id(":exit_status"), EXIT_STATUS_ID);
+    assert_eq!(table.get_id(":spawn"), SPAWN_ID);
+    assert_eq!(table.get_id(":send"), SEND_ID);
+    assert_eq!(table.get_id(":receive"), RECEIVE_ID);
+    assert_eq!(table.get_id(":self_pid"), SELF_PID_ID);
+    assert_eq!(table.get_id(":timeout"), TIMEOUT_ID);
+    assert_eq!(table.get_id(":link"), LINK_ID);
+    assert_eq!(table.get_id(":monitor"), MONITOR_ID);
+    assert_eq!(table.get_id(":supervise"), SUPERVISE_ID);
+    assert_eq!(table.get_id(":exit"), EXIT_ID);
+    assert_eq!(table.get_id(":down"), DOWN_ID);
+    assert_eq!(table.get_id(":normal"), NORMAL_ID);
+    assert_eq!(table.get_id(":noproc"), NOPROC_ID);
+    assert_eq!(table.get_id(":killed"), KILLED_ID);
+    assert_eq!(table.get_id(":shutdown"), SHUTDOWN_ID);
+    assert_eq!(table.get_id(":one_for_one"), ONE_FOR_ONE_ID);
+    assert_eq!(table.get_id(":one_for_all"), ONE_FOR_ALL_ID);
+    assert_eq!(table.get_id(":stream"), STREAM_ID);
+    assert_eq!(table.get_id(":emit"), EMIT_ID);
+    assert_eq!(table.get_id(":read_lines"), READ_LINES_ID);
+    assert_eq!(table.get_id(":next"), NEXT_ID);
+    assert_eq!(table.get_id(":map"), MAP_ID);
+    assert_eq!(table.get_id(":filter"), FILTER_ID);
+    assert_eq!(table.get_id(":take"), TAKE_ID);
+    assert_eq!(table.get_id(":fold"), FOLD_ID);
 }
 
 #[macro_export]
@@ -106,6 +399,140 @@ macro_rules! get_id {
     (":string_out_of_bounds") => { STRING_OUT_OF_BOUNDS };
     ("self") => { SELF_ID };
 
+    (":split") => { SPLIT_ID };
+    (":join") => { JOIN_ID };
+    (":slice") => { SLICE_ID };
+    (":find") => { FIND_ID };
+    (":replace") => { REPLACE_ID };
+    (":trim") => { TRIM_ID };
+    (":upper") => { UPPER_ID };
+    (":lower") => { LOWER_ID };
+    (":starts_with") => { STARTS_WITH_ID };
+    (":ends_with") => { ENDS_WITH_ID };
+    (":repeat") => { REPEAT_ID };
+    (":char_code") => { CHAR_CODE_ID };
+    (":from_char_code") => { FROM_CHAR_CODE_ID };
+    (":byte_len") => { BYTE_LEN_ID };
+    (":parse_int") => { PARSE_INT_ID };
+    (":parse_float") => { PARSE_FLOAT_ID };
+    (":parse_radix") => { PARSE_RADIX_ID };
+    (":to_radix") => { TO_RADIX_ID };
+    (":format") => { FORMAT_ID };
+    (":math") => { MATH_ID };
+    (":sqrt") => { SQRT_ID };
+    (":exp") => { EXP_ID };
+    (":ln") => { LN_ID };
+    (":log") => { LOG_ID };
+    (":sin") => { SIN_ID };
+    (":cos") => { COS_ID };
+    (":tan") => { TAN_ID };
+    (":asin") => { ASIN_ID };
+    (":acos") => { ACOS_ID };
+    (":atan") => { ATAN_ID };
+    (":atan2") => { ATAN2_ID };
+    (":floor") => { FLOOR_ID };
+    (":ceil") => { CEIL_ID };
+    (":round") => { ROUND_ID };
+    (":trunc") => { TRUNC_ID };
+    (":abs") => { ABS_ID };
+    (":min") => { MIN_ID };
+    (":max") => { MAX_ID };
+    (":gcd") => { GCD_ID };
+    (":pi") => { PI_ID };
+    (":e") => { E_ID };
+    (":random") => { RANDOM_ID };
+    (":next_int") => { NEXT_INT_ID };
+    (":next_float") => { NEXT_FLOAT_ID };
+    (":range") => { RANGE_ID };
+    (":now") => { NOW_ID };
+    (":unix_time") => { UNIX_TIME_ID };
+    (":sleep") => { SLEEP_ID };
+    (":format_time") => { FORMAT_TIME_ID };
+    (":parse_time") => { PARSE_TIME_ID };
+    (":date") => { DATE_ID };
+    (":year") => { YEAR_ID };
+    (":month") => { MONTH_ID };
+    (":day") => { DAY_ID };
+    (":hour") => { HOUR_ID };
+    (":minute") => { MINUTE_ID };
+    (":second") => { SECOND_ID };
+    (":unix") => { UNIX_ID };
+    (":keys") => { KEYS_ID };
+    (":env") => { ENV_ID };
+    (":env_vars") => { ENV_VARS_ID };
+    (":cwd") => { CWD_ID };
+    (":set_cwd") => { SET_CWD_ID };
+    (":append_file") => { APPEND_FILE_ID };
+    (":metadata") => { METADATA_ID };
+    (":exists") => { EXISTS_ID };
+    (":rename") => { RENAME_ID };
+    (":copy_file") => { COPY_FILE_ID };
+    (":make_dir_all") => { MAKE_DIR_ALL_ID };
+    (":delete_dir_all") => { DELETE_DIR_ALL_ID };
+    (":size") => { SIZE_ID };
+    (":is_file") => { IS_FILE_ID };
+    (":is_dir") => { IS_DIR_ID };
+    (":readonly") => { READONLY_ID };
+    (":modified") => { MODIFIED_ID };
+    (":not_found") => { NOT_FOUND_ID };
+    (":permission_denied") => { PERMISSION_DENIED_ID };
+    (":already_exists") => { ALREADY_EXISTS_ID };
+    (":not_a_directory") => { NOT_A_DIRECTORY_ID };
+    (":is_a_directory") => { IS_A_DIRECTORY_ID };
+    (":directory_not_empty") => { DIRECTORY_NOT_EMPTY_ID };
+    (":invalid_data") => { INVALID_DATA_ID };
+    (":invalid_input") => { INVALID_INPUT_ID };
+    (":io_error") => { IO_ERROR_ID };
+    (":bytes") => { BYTES_ID };
+    (":read_bytes") => { READ_BYTES_ID };
+    (":write_bytes") => { WRITE_BYTES_ID };
+    (":bytes_out_of_bounds") => { BYTES_OUT_OF_BOUNDS_ID };
+    (":from_utf8") => { FROM_UTF8_ID };
+    (":to_utf8") => { TO_UTF8_ID };
+    (":from_hex") => { FROM_HEX_ID };
+    (":to_hex") => { TO_HEX_ID };
+    (":from_base64") => { FROM_BASE64_ID };
+    (":to_base64") => { TO_BASE64_ID };
+    (":from_array") => { FROM_ARRAY_ID };
+    (":to_array") => { TO_ARRAY_ID };
+    (":json_parse") => { JSON_PARSE_ID };
+    (":json_stringify") => { JSON_STRINGIFY_ID };
+    (":print") => { PRINT_ID };
+    (":eprintln") => { EPRINTLN_ID };
+    (":run") => { RUN_ID };
+    (":start_process") => { START_PROCESS_ID };
+    (":status") => { STATUS_ID };
+    (":stdout") => { STDOUT_ID };
+    (":stderr") => { STDERR_ID };
+    (":write") => { WRITE_ID };
+    (":read_line") => { READ_LINE_ID };
+    (":wait") => { WAIT_ID };
+    (":exit_status") => { EXIT_STATUS_ID };
+    (":spawn") => { SPAWN_ID };
+    (":send") => { SEND_ID };
+    (":receive") => { RECEIVE_ID };
+    (":self_pid") => { SELF_PID_ID };
+    (":timeout") => { TIMEOUT_ID };
+    (":link") => { LINK_ID };
+    (":monitor") => { MONITOR_ID };
+    (":supervise") => { SUPERVISE_ID };
+    (":exit") => { EXIT_ID };
+    (":down") => { DOWN_ID };
+    (":normal") => { NORMAL_ID };
+    (":noproc") => { NOPROC_ID };
+    (":killed") => { KILLED_ID };
+    (":shutdown") => { SHUTDOWN_ID };
+    (":one_for_one") => { ONE_FOR_ONE_ID };
+    (":one_for_all") => { ONE_FOR_ALL_ID };
+    (":stream") => { STREAM_ID };
+    (":emit") => { EMIT_ID };
+    (":read_lines") => { READ_LINES_ID };
+    (":next") => { NEXT_ID };
+    (":map") => { MAP_ID };
+    (":filter") => { FILTER_ID };
+    (":take") => { TAKE_ID };
+    (":fold") => { FOLD_ID };
+
     ($other:expr) => { // Fallback to the runtime version if it's not predefined
         $other
     };
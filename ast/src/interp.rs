@@ -0,0 +1,133 @@
+// Interpolated strings like "x = {x}, sum = {a + b}".
+// The lexer hands us the whole literal, here it is cut into text and expression parts
+// and desugared into a chain of + starting from a string, so every value goes through to_string.
+// Expressions are parsed in place so their spans point inside the literal.
+
+use std::ops::Range;
+
+use codespan::Span;
+use lalrpop_util::ParseError;
+
+use crate::ast::*;
+use crate::lexer::{lex_string, LexTag, Lexer};
+use crate::parser::ValueParser;
+
+// alternating text and expression ranges, starting and ending with text.
+// text ranges keep one delimiter on each side (a quote or brace) so the string table can unescape them.
+fn split_interp(input: &str, range: Range<usize>) -> Vec<Range<usize>> {
+    let mut parts = Vec::new();
+    let mut part_start = range.start;
+    let mut depth = 0;
+
+    let mut i = range.start + 1;
+    while i < range.end {
+        let rest = &input[i..range.end];
+        let c = rest.chars().next().unwrap();
+        match c {
+            '\\' if depth == 0 => i += rest.chars().nth(1).map_or(0, char::len_utf8),
+            '{' if depth == 0 => {
+                parts.push(part_start..i + 1);
+                part_start = i + 1;
+                depth = 1;
+            }
+            '{' => depth += 1,
+            '}' if depth == 1 => {
+                parts.push(part_start..i);
+                part_start = i;
+                depth = 0;
+            }
+            '}' if depth > 1 => depth -= 1,
+            '"' | '\'' if depth > 0 => {
+                let (next, _) = lex_string(rest).expect("the lexer already matched this string");
+                i = range.end - next.len();
+                continue;
+            }
+            _ => {}
+        }
+        i += c.len_utf8();
+    }
+    parts.push(part_start..range.end);
+    parts
+}
+
+pub fn desugar_interp<'input>(
+    input: &'input str,
+    range: Range<usize>,
+    table: &mut StringTable<'input>,
+) -> Result<Value, ParseError<usize, LexTag, ()>> {
+    let parts = split_interp(input, range.clone());
+
+    let mut ans = Value::String(table.get_id(&input[parts[0].clone()]));
+    for pair in parts[1..].chunks(2) {
+        let [expr, text] = pair else {
+            unreachable!("interpolated strings always end with text")
+        };
+        if input[expr.clone()].trim().is_empty() {
+            return Err(ParseError::UnrecognizedToken {
+                token: (expr.end, LexTag::CloseCurly, expr.end + 1),
+                expected: vec!["an expression".to_string()],
+            });
+        }
+
+        let value = ValueParser::new().parse(input, table, Lexer::with_range(input, expr.clone()))?;
+        let span = Span::new(range.start as u32, text.end as u32);
+        ans = concat(ans, value, span);
+        if text.len() > 2 {
+            ans = concat(ans, Value::String(table.get_id(&input[text.clone()])), span);
+        }
+    }
+    Ok(ans)
+}
+
+fn concat(a: Value, b: Value, span: Span) -> Value {
+    Value::FuncCall(FunctionCall {
+        name: FValue::BuildIn(BuildIn::Add),
+        args: vec![a, b],
+        debug_span: span,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parts(input: &str) -> Vec<&str> {
+        split_interp(input, 0..input.len()).into_iter().map(|r| &input[r]).collect()
+    }
+
+    #[test]
+    fn splits_text_and_expressions() {
+        assert_eq!(parts(r#""x = {x}, sum = {a + b}""#), [r#""x = {"#, "x", "}, sum = {", "a + b", r#"}""#]);
+        assert_eq!(parts(r#""{f("}", {a})}!""#), [r#""{"#, r#"f("}", {a})"#, r#"}!""#]);
+        assert_eq!(parts(r#""\{{a}\}""#), [r#""\{{"#, "a", r#"}\}""#]);
+    }
+
+    #[test]
+    fn desugars_to_concatenation() {
+        let input = r#""a{x}b""#;
+        let mut table = StringTable::new();
+        let value = desugar_interp(input, 0..input.len(), &mut table).unwrap();
+
+        let Value::FuncCall(outer) = value else { panic!("expected a call") };
+        assert_eq!(outer.name, FValue::BuildIn(BuildIn::Add));
+        assert_eq!(outer.debug_span, Span::new(0, 7));
+        assert_eq!(outer.args[1], Value::String(table.get_id(r#"}b""#)));
+
+        let start = table.get_id(r#""a{"#);
+        let Value::FuncCall(inner) = &outer.args[0] else { panic!("expected a call") };
+        assert_eq!(inner.args, [Value::String(start), Value::Variable(table.get_id("x"), Span::new(3, 4))]);
+        assert_eq!(table.get_escaped_string(start), "a");
+    }
+
+    #[test]
+    fn errors_point_inside_the_literal() {
+        let input = r#"  "ok {a +} {}""#;
+        let mut table = StringTable::new();
+        let err = desugar_interp(input, 2..input.len(), &mut table).unwrap_err();
+        assert!(matches!(err, ParseError::UnrecognizedEof { location: 10, .. }), "{:?}", err);
+
+        let input = r#""{}""#;
+        let err = desugar_interp(input, 0..input.len(), &mut table).unwrap_err();
+        assert!(matches!(err, ParseError::UnrecognizedToken { token: (2, LexTag::CloseCurly, 3), .. }), "{:?}", err);
+    }
+}
@@ -1,6 +1,7 @@
 #![allow(clippy::needless_lifetimes)]
 use codespan::ByteIndex;
 use codespan::Span;
+use std::ops::Range;
 use nom::bytes::complete::{tag,is_not,take_till,take_while,take_while1};
 use nom::combinator::{opt, recognize};
 use nom::branch::alt;
@@ -32,6 +33,7 @@ pub enum LexTag {
     Name,
     Atom,
     String,
+    InterpString,
     PoisonString,
     Unknowen,
     Nil,
@@ -141,9 +143,13 @@ fn lex_unknowen<'a>(input: &'a str) -> LexResult<'a>{
     Ok((input,LexTag::Unknowen))
 }
 
-fn lex_string<'a>(input: &'a str) -> LexResult<'a> {
-    // Match the string delimiters (either ' or ")
-    let (input, d) = recognize(one_of("'\""))(input)?;
+pub(crate) fn lex_string<'a>(input: &'a str) -> LexResult<'a> {
+    if input.starts_with('"') {
+        return lex_double_string(input);
+    }
+
+    // Match the string delimiters
+    let (input, d) = recognize(one_of("'"))(input)?;
     
     // Process the content inside the delimiters
     let (input, last) = preceded(
@@ -161,6 +167,44 @@ fn lex_string<'a>(input: &'a str) -> LexResult<'a> {
     }
 }
 
+// Double quoted strings may hold {expr} parts (\{ is a plain brace).
+// The expressions can have strings and braces of their own so we track the nesting.
+fn lex_double_string<'a>(input: &'a str) -> LexResult<'a> {
+    let (mut rest, _) = tag("\"")(input)?;
+    let mut depth = 0;
+    let mut interp = false;
+
+    loop {
+        let mut chars = rest.chars();
+        let Some(c) = chars.next() else {
+            return Ok((rest, LexTag::PoisonString));
+        };
+        match c {
+            '\\' if depth == 0 => {
+                chars.next();
+            }
+            '"' if depth == 0 => {
+                let tag = if interp { LexTag::InterpString } else { LexTag::String };
+                return Ok((chars.as_str(), tag));
+            }
+            '{' => {
+                depth += 1;
+                interp = true;
+            }
+            '}' if depth > 0 => depth -= 1,
+            '"' | '\'' if depth > 0 => match lex_string(rest)? {
+                (rest, LexTag::PoisonString) => return Ok((rest, LexTag::PoisonString)),
+                (next, _) => {
+                    rest = next;
+                    continue;
+                }
+            },
+            _ => {}
+        }
+        rest = chars.as_str();
+    }
+}
+
 fn lex_atom<'a>(input: &'a str) -> LexResult<'a> {
     let (input, _ans) = recognize(preceded(
         tag(":"),
@@ -308,6 +352,7 @@ fn lex_number<'a>(input: &'a str) -> LexResult<'a> {
         tag(".")(input)
     }
 
+    let start = input;
     let (input, n) = lex_digits(input)?;
 
     let input = match dot(input) {
@@ -326,12 +371,12 @@ fn lex_number<'a>(input: &'a str) -> LexResult<'a> {
 
     match lex_digits(input) {
         Err(_) => Ok((input, LexTag::Float(f))),
-        Ok((input, n2)) => {
-            // Use log10 to calculate the scaling factor for the fractional part
-            let fractional_part = to_float(n2);
-            let scale_factor = 10f64.powi(fractional_part.log10().ceil() as i32);
+        Ok((input, _)) => {
+            // Parse the literal as text, rebuilding the fraction from its digit value loses leading zeros (0.05)
+            let text = &start[..start.len() - input.len()];
+            let value = text.replace('_', "").parse::<f64>().unwrap();
 
-            Ok((input, LexTag::Float(f + fractional_part / scale_factor)))
+            Ok((input, LexTag::Float(value)))
         }
     }
 }
@@ -358,7 +403,12 @@ pub struct Lexer<'input> {
 
 impl<'input> Lexer<'input> {
     pub fn new(input: &'input str) -> Self {
-        let (rem, dump) = skip(input);
+        Self::with_range(input, 0..input.len())
+    }
+
+    // lexes only part of the input while keeping positions relative to all of it
+    pub fn with_range(input: &'input str, range: Range<usize>) -> Self {
+        let (rem, dump) = skip(&input[range]);
         let span = get_subslice_span(input, dump);
         let (next, line) = get_line(rem);
 
@@ -421,6 +471,36 @@ impl<'input> Iterator for Lexer<'input> {
 
 
 
+#[derive(Debug, PartialEq, Clone)]
+pub struct Comment<'input> {
+    pub text: &'input str, // includes the leading #
+    pub span: Span,
+}
+
+// The parser never sees comments so they are recovered as trivia from the gaps between tokens.
+// A gap only holds whitespace and comments so every # in it starts a comment.
+pub fn lex_comments<'input>(input: &'input str) -> Vec<Comment<'input>> {
+    fn scan_gap<'input>(input: &'input str, mut gap: &'input str, comments: &mut Vec<Comment<'input>>) {
+        while let Some(start) = gap.find('#') {
+            let rest = &gap[start..];
+            let end = rest.find('\n').unwrap_or(rest.len());
+            let text = rest[..end].trim_end();
+            comments.push(Comment { text, span: get_subslice_span(input, text) });
+            gap = &rest[end..];
+        }
+    }
+
+    let mut comments = Vec::new();
+    let mut last = 0;
+    for token in Lexer::new(input) {
+        let Ok((start, _, end)) = token else { break };
+        scan_gap(input, &input[last..start], &mut comments);
+        last = end;
+    }
+    scan_gap(input, &input[last..], &mut comments);
+    comments
+}
+
 #[test]
 #[allow(unused_variables)]
 fn test_lexer_end_to_end() {
@@ -779,6 +859,15 @@ fn test_lex_number_valid() {
     assert!(matches!(tag, LexTag::Float(_)));
 }
 
+#[test]
+fn test_lex_float_values() {
+    for (input, expected) in [("1.0", 1.0), ("0.05", 0.05), ("1.10", 1.1), ("3.000001", 3.000001), ("12_3.4_5", 123.45)] {
+        let (remaining, tag) = lex_number(input).unwrap();
+        assert_eq!(remaining, "");
+        assert_eq!(tag, LexTag::Float(expected), "{}", input);
+    }
+}
+
 #[test]
 fn test_lex_number_overflow() {
     // Integer overflow
@@ -792,4 +881,15 @@ fn test_lex_number_overflow() {
     let (remaining, tag) = lex_number(input).unwrap();
     assert_eq!(remaining, "");
     assert!(matches!(tag, LexTag::Float(_))); // Should be parsed as f64 due to overflow
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_lex_comments() {
+    let source = "#top\ndef f() { # after brace\n    x = '# not a comment'; #end\n}\n# last";
+    let comments: Vec<&str> = lex_comments(source).iter().map(|c| c.text).collect();
+    assert_eq!(comments, vec!["#top", "# after brace", "#end", "# last"]);
+
+    for c in lex_comments(source) {
+        assert_eq!(&source[c.span.start().to_usize()..c.span.end().to_usize()], c.text);
+    }
+}
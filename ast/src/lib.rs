@@ -1,6 +1,8 @@
 pub mod lexer;
 pub mod ast;
 pub mod id;
+pub mod format;
+pub mod interp;
 mod test_parsing;
 
 use lalrpop_util::lalrpop_mod;
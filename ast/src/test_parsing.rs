@@ -100,7 +100,7 @@ fn parse_hello_world_function() {
         // Check the outer function call is `system(:println)`
         if let FValue::FuncCall(outer_call) = &func_call.name {
             // Ensure the outer function is `system`
-            if let FValue::Name(system_name) = outer_call.name {
+            if let FValue::Name(system_name, _) = outer_call.name {
                 assert_eq!(table.get_display_str(system_name), Some("system"));
             } else {
                 panic!("Expected 'system' as the outer function name");
@@ -207,7 +207,7 @@ fn function_calls_and_expressions() {
     let value = result.unwrap();
     if let Value::FuncCall (FunctionCall {name, args,.. }) = value {
         let name = match name {
-            FValue::Name(n) => n,
+            FValue::Name(n, _) => n,
             _ => unreachable!()
         };
         assert_eq!(table.get_display_str(name).unwrap(), "foo");
@@ -222,7 +222,7 @@ fn function_calls_and_expressions() {
             _ => panic!("Expected second argument to be Float(2.5)"),
         }
         match args[2] {
-            Value::Variable(id) => assert_eq!(table.get_display_str(id).unwrap(), "x"),
+            Value::Variable(id, _) => assert_eq!(table.get_display_str(id).unwrap(), "x"),
             _ => panic!("Expected third argument to be Variable 'x'"),
         }
     } else {
@@ -256,7 +256,7 @@ fn function_call_no_args() {
     let value = result.unwrap();
     if let Value::FuncCall(FunctionCall { name, args,.. }) = value {
         let name = match name {
-            FValue::Name(n) => n,
+            FValue::Name(n, _) => n,
             _ => unreachable!()
         };
         assert_eq!(table.get_display_str(name).unwrap(), "foo");
@@ -285,14 +285,14 @@ fn func_block_with_statements_and_return() {
     match &func_block.body[1] {
         Statment::Call(fc) => {
             let name = match fc.name {
-                FValue::Name(n) => n,
+                FValue::Name(n, _) => n,
                 _ => unreachable!()
             };
             assert_eq!("s",table.get_display_str(name).unwrap());
         },
         _ => unreachable!()
     };
-    assert!(matches!(func_block.ret, Some(Ret::Imp(Value::Variable(_)))));
+    assert!(matches!(func_block.ret, Some(Ret::Imp(Value::Variable(..)))));
 }
 
 #[test]
@@ -326,7 +326,7 @@ fn func_block_only_return() {
 
     // Check empty body and return value
     assert_eq!(func_block.body.len(), 0);
-    assert!(matches!(func_block.ret, Some(Ret::Exp(Value::Variable(_)))));
+    assert!(matches!(func_block.ret, Some(Ret::Exp(Value::Variable(..)))));
 }
 
 #[test]
@@ -492,7 +492,7 @@ fn test_comparison_with_logical_in_parentheses() {
             let x_var_index = table.get_id("x");
 
             // Check that the left operand is the variable `x` (index in string table)
-            assert_eq!(gt_call.args[0], Value::Variable(x_var_index));
+            assert!(matches!(gt_call.args[0], Value::Variable(id, _) if id == x_var_index));
 
             // Check that the right operand is the `&&` logical operation inside parentheses
             if let Value::FuncCall(and_call) = &gt_call.args[1] {
@@ -501,13 +501,13 @@ fn test_comparison_with_logical_in_parentheses() {
                     let a_var_index = table.get_id("a");
 
                     // Check that the left operand of `&&` is the variable `a` (index in string table)
-                    assert_eq!(and_call.args[0], Value::Variable(a_var_index));
+                    assert!(matches!(and_call.args[0], Value::Variable(id, _) if id == a_var_index));
 
                     // Fetch the index for variable "b" from the string table
                     let b_var_index = table.get_id("b");
 
                     // Check that the right operand of `&&` is the variable `b` (index in string table)
-                    assert_eq!(and_call.args[1], Value::Variable(b_var_index));
+                    assert!(matches!(and_call.args[1], Value::Variable(id, _) if id == b_var_index));
                 } else {
                     panic!("Expected logical AND function inside parentheses");
                 }
@@ -545,10 +545,10 @@ fn test_complex_expression_with_nested_parentheses() {
             if let Value::FuncCall(add_call) = &mul_call.args[0] {
                 if let FValue::BuildIn(BuildIn::Add) = add_call.name {
                     // Check that the left operand of `+` is `x`
-                    assert_eq!(add_call.args[0], Value::Variable(x_var_index));
+                    assert!(matches!(add_call.args[0], Value::Variable(id, _) if id == x_var_index));
 
                     // Check that the right operand of `+` is `y`
-                    assert_eq!(add_call.args[1], Value::Variable(y_var_index));
+                    assert!(matches!(add_call.args[1], Value::Variable(id, _) if id == y_var_index));
                 } else {
                     panic!("Expected addition (+) function for `(x + y)`");
                 }
@@ -562,7 +562,7 @@ fn test_complex_expression_with_nested_parentheses() {
                     let a_var_index = table.get_id("a");
 
                     // Check that the left operand of `&&` is `a`
-                    assert_eq!(and_call.args[0], Value::Variable(a_var_index));
+                    assert!(matches!(and_call.args[0], Value::Variable(id, _) if id == a_var_index));
 
                     // Check that the right operand of `&&` is `(b > c || d)`
                     if let Value::FuncCall(or_call) = &and_call.args[1] {
@@ -574,8 +574,8 @@ fn test_complex_expression_with_nested_parentheses() {
                             // Check that the left operand of `||` is `(b > c)`
                             if let Value::FuncCall(gt_call) = &or_call.args[0] {
                                 if let FValue::BuildIn(BuildIn::Bigger) = gt_call.name {
-                                    assert_eq!(gt_call.args[0], Value::Variable(b_var_index));
-                                    assert_eq!(gt_call.args[1], Value::Variable(c_var_index));
+                                    assert!(matches!(gt_call.args[0], Value::Variable(id, _) if id == b_var_index));
+                                    assert!(matches!(gt_call.args[1], Value::Variable(id, _) if id == c_var_index));
                                 } else {
                                     panic!("Expected greater-than (>) function for `(b > c)`");
                                 }
@@ -584,7 +584,7 @@ fn test_complex_expression_with_nested_parentheses() {
                             }
 
                             // Check that the right operand of `||` is `d`
-                            assert_eq!(or_call.args[1], Value::Variable(d_var_index));
+                            assert!(matches!(or_call.args[1], Value::Variable(id, _) if id == d_var_index));
                         } else {
                             panic!("Expected logical OR (||) function for `(b > c || d)`");
                         }
@@ -618,17 +618,17 @@ fn test_basic_piping() {
     let pipe_call = result.unwrap();
 
     // Check the last function called in the chain is `c()`
-    if let FValue::Name(c_name) = pipe_call.name {
+    if let FValue::Name(c_name, _) = pipe_call.name {
         assert_eq!(table.get_display_str(c_name).unwrap(), "c");
 
         // Check the argument to `c()` is a `FuncCall` for `b()`
         if let Value::FuncCall(b_call) = &pipe_call.args[0] {
-            if let FValue::Name(b_name) = b_call.name {
+            if let FValue::Name(b_name, _) = b_call.name {
                 assert_eq!(table.get_display_str(b_name).unwrap(), "b");
 
                 // Check the argument to `b()` is a `FuncCall` for `a()`
                 if let Value::FuncCall(a_call) = &b_call.args[0] {
-                    if let FValue::Name(a_name) = a_call.name {
+                    if let FValue::Name(a_name, _) = a_call.name {
                         assert_eq!(table.get_display_str(a_name).unwrap(), "a");
                     } else {
                         panic!("Expected function name 'a'");
@@ -657,14 +657,14 @@ fn test_piping_with_third_order_nesting() {
     let pipe_call = parser.parse(input, &mut table, lexer).unwrap();  // Unwrap to get full error report on failure
 
     // Check the last function called in the chain is `c()`
-    if let FValue::Name(c_name) = pipe_call.name {
+    if let FValue::Name(c_name, _) = pipe_call.name {
         assert_eq!(table.get_display_str(c_name).unwrap(), "c");
 
         // Check the arguments to `c()` (total 3 arguments, reverse order)
         assert_eq!(pipe_call.args.len(), 3);
 
         // Third argument is `z` (as a variable)
-        assert_eq!(pipe_call.args[2], Value::Variable(table.get_id("z")));
+        assert!(matches!(pipe_call.args[2], Value::Variable(id, _) if id == table.get_id("z")));
 
         // Second argument is `3 * 4`
         if let Value::FuncCall(mul_call) = &pipe_call.args[1] {
@@ -680,7 +680,7 @@ fn test_piping_with_third_order_nesting() {
 
         // First argument is `b(a(1 + 2), y, n(m(x)))` (as a function call)
         if let Value::FuncCall(b_call) = &pipe_call.args[0] {
-            if let FValue::Name(b_name) = b_call.name {
+            if let FValue::Name(b_name, _) = b_call.name {
                 assert_eq!(table.get_display_str(b_name).unwrap(), "b");
             } else {
                 panic!("Expected function name 'b'");
@@ -691,7 +691,7 @@ fn test_piping_with_third_order_nesting() {
 
             // First argument to `b()` is `a(1 + 2)` (as a function call)
             if let Value::FuncCall(a_call) = &b_call.args[0] {
-                if let FValue::Name(a_name) = a_call.name {
+                if let FValue::Name(a_name, _) = a_call.name {
                     assert_eq!(table.get_display_str(a_name).unwrap(), "a");
                 } else {
                     panic!("Expected function name 'a'");
@@ -713,11 +713,11 @@ fn test_piping_with_third_order_nesting() {
             }
 
             // Second argument to `b()` is `y` (as a variable)
-            assert_eq!(b_call.args[1], Value::Variable(table.get_id("y")));
+            assert!(matches!(b_call.args[1], Value::Variable(id, _) if id == table.get_id("y")));
 
             // Third argument to `b()` is `n(m(x))`
             if let Value::FuncCall(n_call) = &b_call.args[2] {
-                if let FValue::Name(n_name) = n_call.name {
+                if let FValue::Name(n_name, _) = n_call.name {
                     assert_eq!(table.get_display_str(n_name).unwrap(), "n");
                 } else {
                     panic!("Expected function name 'n'");
@@ -725,14 +725,14 @@ fn test_piping_with_third_order_nesting() {
 
                 // Check `n()` has one argument: `m(x)`
                 if let Value::FuncCall(m_call) = &n_call.args[0] {
-                    if let FValue::Name(m_name) = m_call.name {
+                    if let FValue::Name(m_name, _) = m_call.name {
                         assert_eq!(table.get_display_str(m_name).unwrap(), "m");
                     } else {
                         panic!("Expected function name 'm'");
                     }
 
                     // Check `m()` has one argument: `x`
-                    assert_eq!(m_call.args[0], Value::Variable(table.get_id("x")));
+                    assert!(matches!(m_call.args[0], Value::Variable(id, _) if id == table.get_id("x")));
                 } else {
                     panic!("Expected `m(x)` as the argument to `n()`");
                 }
@@ -761,7 +761,7 @@ fn test_piping_with_function_call_as_function() {
     // Check that the outermost call is a `FuncCall` representing the invocation of `c()()`
     if let FValue::FuncCall(c_call) = pipe_call.name {
         // Verify the name of the inner function call is `c`
-        if let FValue::Name(c_name) = c_call.name {
+        if let FValue::Name(c_name, _) = c_call.name {
             assert_eq!(table.get_display_str(c_name).unwrap(), "c");
 
             // Check that `c()` has no arguments in its first invocation
@@ -769,7 +769,7 @@ fn test_piping_with_function_call_as_function() {
 
             // Check the argument to the result of `c()` is a function call for `a()`
             if let Value::FuncCall(a_call) = &pipe_call.args[0] {
-                if let FValue::Name(a_name) = a_call.name {
+                if let FValue::Name(a_name, _) = a_call.name {
                     assert_eq!(table.get_display_str(a_name).unwrap(), "a");
 
                     // Verify `a()` has no arguments
@@ -801,7 +801,7 @@ fn test_complex_piping_with_double_function_call() {
     // Check that the outermost call is to `c(d(e()), f())(...)`
     if let FValue::FuncCall(c_call) = pipe_call.name {
         // Verify the name of the function is `c`
-        if let FValue::Name(c_name) = c_call.name {
+        if let FValue::Name(c_name, _) = c_call.name {
             assert_eq!(table.get_display_str(c_name).unwrap(), "c");
 
             // Check that `c()` has two arguments: `d(e())` and `f()`
@@ -809,12 +809,12 @@ fn test_complex_piping_with_double_function_call() {
 
             // First argument is `d(e())`
             if let Value::FuncCall(d_call) = &c_call.args[0] {
-                if let FValue::Name(d_name) = d_call.name {
+                if let FValue::Name(d_name, _) = d_call.name {
                     assert_eq!(table.get_display_str(d_name).unwrap(), "d");
 
                     // Check `d()` has one argument: `e()`
                     if let Value::FuncCall(e_call) = &d_call.args[0] {
-                        if let FValue::Name(e_name) = e_call.name {
+                        if let FValue::Name(e_name, _) = e_call.name {
                             assert_eq!(table.get_display_str(e_name).unwrap(), "e");
                         } else {
                             panic!("Expected function name 'e'");
@@ -831,7 +831,7 @@ fn test_complex_piping_with_double_function_call() {
 
             // Second argument is `f()`
             if let Value::FuncCall(f_call) = &c_call.args[1] {
-                if let FValue::Name(f_name) = f_call.name {
+                if let FValue::Name(f_name, _) = f_call.name {
                     assert_eq!(table.get_display_str(f_name).unwrap(), "f");
 
                     // Verify `f()` has no arguments
@@ -848,12 +848,12 @@ fn test_complex_piping_with_double_function_call() {
 
         // Check the argument passed to `c()` is `b(a(), x)`
         if let Value::FuncCall(b_call) = &pipe_call.args[0] {
-            if let FValue::Name(b_name) = b_call.name {
+            if let FValue::Name(b_name, _) = b_call.name {
                 assert_eq!(table.get_display_str(b_name).unwrap(), "b");
 
                 // First argument to `b()` is `a()`
                 if let Value::FuncCall(a_call) = &b_call.args[0] {
-                    if let FValue::Name(a_name) = a_call.name {
+                    if let FValue::Name(a_name, _) = a_call.name {
                         assert_eq!(table.get_display_str(a_name).unwrap(), "a");
 
                         // Verify `a()` has no arguments
@@ -866,7 +866,7 @@ fn test_complex_piping_with_double_function_call() {
                 }
 
                 // Second argument to `b()` is `x`
-                assert_eq!(b_call.args[1], Value::Variable(table.get_id("x")));
+                assert!(matches!(b_call.args[1], Value::Variable(id, _) if id == table.get_id("x")));
             } else {
                 panic!("Expected function name 'b'");
             }
@@ -903,7 +903,7 @@ fn parse_pipe_hello_world_function() {
         // Check the outer function call is `system(:println)`
         if let FValue::FuncCall(outer_call) = &func_call.name {
             // Ensure the outer function is `system`
-            if let FValue::Name(system_name) = outer_call.name {
+            if let FValue::Name(system_name, _) = outer_call.name {
                 assert_eq!(table.get_display_str(system_name), Some("system"));
             } else {
                 panic!("Expected 'system' as the outer function name");
@@ -956,7 +956,7 @@ fn parse_pipe_nil_bool() {
         // Check the outer function call is `system(:println)`
         if let FValue::FuncCall(outer_call) = &func_call.name {
             // Ensure the outer function is `system`
-            if let FValue::Name(system_name) = outer_call.name {
+            if let FValue::Name(system_name, _) = outer_call.name {
                 assert_eq!(table.get_display_str(system_name), Some("system"));
             } else {
                 panic!("Expected 'system' as the outer function name");
@@ -1013,7 +1013,7 @@ fn test_simple_match_statement() {
     // Unwrap the match statement
     if let Statment::Match(match_stmt) = &func_dec.body.body[0] {
         // Validate the expression being matched (should be variable 'x')
-        if let Value::Variable(var_id) = *match_stmt.val {
+        if let Value::Variable(var_id, _) = *match_stmt.val {
             assert_eq!(table.get_display_str(var_id).unwrap(), "x");
         } else {
             panic!("Expected variable 'x' as the value being matched");
@@ -1101,7 +1101,7 @@ fn test_match_with_blocks() {
     // Unwrap the match statement
     if let Statment::Match(match_stmt) = &func_dec.body.body[0] {
         // Validate the expression being matched (should be variable 'event')
-        if let Value::Variable(var_id) = *match_stmt.val {
+        if let Value::Variable(var_id, _) = *match_stmt.val {
             assert_eq!(table.get_display_str(var_id).unwrap(), "event");
         } else {
             panic!("Expected variable 'event' as the value being matched");
@@ -1154,3 +1154,31 @@ fn test_match_with_blocks() {
         panic!("Expected a match statement in function body");
     }
 }
+
+#[test]
+fn name_spans_point_at_source() {
+    let input = "def main(system) { system(:println)(value) }";
+
+    let lexer = Lexer::new(input);
+    let mut table = StringTable::new();
+
+    let parser = parser::FuncDecParser::new();
+    let func_dec = parser.parse(input, &mut table, lexer).unwrap();
+
+    let Some(Ret::Imp(Value::FuncCall(outer))) = func_dec.body.ret else {
+        panic!("Expected an implicit return of a call");
+    };
+
+    let Value::Variable(_, span) = outer.args[0] else {
+        panic!("Expected a variable argument");
+    };
+    assert_eq!(&input[span.start().to_usize()..span.end().to_usize()], "value");
+
+    let FValue::FuncCall(inner) = outer.name else {
+        panic!("Expected a curried call");
+    };
+    let FValue::Name(_, span) = inner.name else {
+        panic!("Expected a named call");
+    };
+    assert_eq!(&input[span.start().to_usize()..span.end().to_usize()], "system");
+}
@@ -5,12 +5,14 @@
 use ast::id::*;
 use ast::id::STRING_OUT_OF_BOUNDS;
 use ast::get_id;
+use crate::fstring::FString;
 use crate::reporting::zero_div_error;
 use crate::reporting::sig_error;
 use crate::reporting::stacked_error;
 use crate::reporting::NoneCallble;
 use crate::reporting::overflow_error;
 use crate::reporting::bug_error;
+use crate::reporting::arg_error;
 use codespan::Span;
 use std::sync::Arc;
 use crate::reporting::{ErrList,Error};
@@ -32,6 +34,52 @@ pub fn get_arg_vec<'code>(stack:&mut ValueStack<'code>)-> Vec<Value<'code>>{
     ans
 }
 
+//pops the args of a native call (and its terminator), in the order they were passed
+pub fn pop_args<'code,const N:usize>(stack:&mut ValueStack<'code>) -> Result<[Value<'code>;N],ErrList> {
+    let mut args = std::array::from_fn(|_| Value::Nil);
+    for (got,arg) in args.iter_mut().rev().enumerate() {
+        *arg = stack.pop_value().ok_or_else(|| arg_error(N,got))?;
+    }
+    stack.pop_terminator().ok_or_else(sig_error)?;
+    Ok(args)
+}
+
+//pushes what a native call gives back
+pub(crate) fn push<'code>(stack:&mut ValueStack<'code>,value:Value<'code>) -> Result<(),ErrList> {
+    stack.push_value(value).map_err(|_| overflow_error())
+}
+
+//runs a native the way the VM calls it, for unit tests of the modules
+#[cfg(test)]
+pub(crate) fn call_native(f:crate::vm::StaticFunc,args:impl IntoIterator<Item=Value<'static>>) -> Result<Value<'static>,ErrList> {
+    let table = StringTable::new();
+    let mut stack = ValueStack::new();
+    stack.push_terminator().map_err(|_| overflow_error())?;
+    for arg in args {
+        push(&mut stack,arg)?;
+    }
+    f(&mut stack,&table)?;
+    stack.pop_value().ok_or_else(|| bug_error("native left nothing on the stack"))
+}
+
+//calls a host function value like generators and handles, for unit tests of the modules
+#[cfg(test)]
+pub(crate) fn call_data(f:&crate::vm::DataFunc,args:impl IntoIterator<Item=Value<'static>>) -> Result<Value<'static>,ErrList> {
+    let table = StringTable::new();
+    let mut stack = ValueStack::new();
+    stack.push_terminator().map_err(|_| overflow_error())?;
+    for arg in args {
+        push(&mut stack,arg)?;
+    }
+    f.call(&mut stack,&table)?;
+    stack.pop_value().ok_or_else(|| bug_error("host function left nothing on the stack"))
+}
+
+#[cfg(test)]
+pub(crate) fn text(s:&str) -> Value<'static> {
+    Value::String(Arc::new(s.into()))
+}
+
 #[inline(always)]
 fn _is_equal<'code>(stack:&mut ValueStack<'code>,_table:&StringTable<'code>) -> Result<bool, ErrList> {
     let a = stack.pop_value().ok_or_else(|| bug_error("over popping"))?;
@@ -84,10 +132,12 @@ pub fn to_string_debug<'code>(value: &Value<'code>, table: &StringTable<'code>)
         Value::Float(f) => format!("float({})", f),
         Value::Atom(atom_id) => format!("atom({})", table.get_raw_str(*atom_id)),
         Value::String(s) => format!("string(\"{}\")", s),
+        Value::Bytes(b) => format!("bytes({:?})", &b[..]),
         Value::Func(func) => format!("func({:p})", Arc::as_ptr(func)),
         Value::WeakFunc(weak_func) => format!("weak_func({:p})", weak_func.as_ptr()),
         Value::StaticFunc(static_func) => format!("static_func({:p})", static_func as *const _),
         Value::DataFunc(d) => format!("data_func({:?})", d),
+        Value::Stream(s) => format!("stream({:p})", Arc::as_ptr(s)),
 
     }
 }
@@ -101,10 +151,12 @@ pub fn to_string_runtime<'code>(value: &Value<'code>, table: &StringTable<'code>
         Value::Float(f) => format!("{}", f),
         Value::Atom(atom_id) => table.get_raw_str(*atom_id).to_string(),
         Value::String(s) => s.to_string(),
+        Value::Bytes(b) => format!("bytes({:?})", &b[..]),
         Value::Func(func) => format!("func({:p})", Arc::as_ptr(func)),
         Value::WeakFunc(weak_func) => format!("weak_func({:p})", weak_func.as_ptr()),
         Value::StaticFunc(static_func) => format!("static_func({:p})", static_func as *const _),
         Value::DataFunc(d) => format!("data_func({:?})", d),
+        Value::Stream(s) => format!("stream({:p})", Arc::as_ptr(s)),
 
     }
 }
@@ -198,14 +250,15 @@ fn test_is_equal() {
 
 
 #[inline]
-fn to_bool(value: &Value<'_>) -> bool {
+pub(crate) fn to_bool(value: &Value<'_>) -> bool {
     match value {
         Value::Nil => false,
         Value::Bool(b) => *b,
         Value::Int(i) => *i > 0,
         Value::Float(f) => *f > 0.0,
         Value::String(s) => !s.is_empty(),
-        Value::Atom(_) | Value::Func(_) | Value::WeakFunc(_) | Value::StaticFunc(_) | Value::DataFunc(_)=> true,
+        Value::Bytes(b) => !b.is_empty(),
+        Value::Atom(_) | Value::Func(_) | Value::WeakFunc(_) | Value::StaticFunc(_) | Value::DataFunc(_) | Value::Stream(_)=> true,
     }
 }
 
@@ -256,8 +309,8 @@ fn test_logical_operations() {
     assert!(!to_bool(&Value::Int(0)));
     assert!(to_bool(&Value::Float(1.0)));
     assert!(!to_bool(&Value::Float(0.0)));
-    assert!(to_bool(&Value::String("non-empty".to_string().into())));
-    assert!(!to_bool(&Value::String("".to_string().into())));
+    assert!(to_bool(&Value::String(Arc::new("non-empty".into()))));
+    assert!(!to_bool(&Value::String(Arc::new("".into()))));
     assert!(to_bool(&Value::Atom(1)));
 
     // Test logical AND (&&)
@@ -748,6 +801,8 @@ pub fn add<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>, sp
         (Value::Int(a), Value::Float(b)) => Value::Float((a as f64) + b),
         (Value::Float(a), Value::Int(b)) => Value::Float(a + (b as f64)),
 
+        (Value::Bytes(b1), Value::Bytes(b2)) => Value::Bytes([&b1[..], &b2[..]].concat().into()),
+
         // String concatenation
         (Value::String(mut s1), Value::String(s2)) => {
             if let Some(s1_mut) = Arc::get_mut(&mut s1) {
@@ -757,7 +812,7 @@ pub fn add<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>, sp
                 let mut ans = String::with_capacity(s1.len() + s2.len());
                 ans.push_str(&s1);
                 ans.push_str(&s2);
-                Value::String(ans.into())
+                Value::String(Arc::new(ans.into()))
             }
         }
         (Value::String(mut s1), b) => {
@@ -769,7 +824,7 @@ pub fn add<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>, sp
                 let mut ans = String::with_capacity(s1.len() + s2.len());
                 ans.push_str(&s1);
                 ans.push_str(&s2);
-                Value::String(ans.into())
+                Value::String(Arc::new(ans.into()))
             }
         }
         (a, Value::String(mut s2)) => {
@@ -781,7 +836,7 @@ pub fn add<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>, sp
                 let mut ans = String::with_capacity(s1.len() + s2.len());
                 ans.push_str(&s1);
                 ans.push_str(&s2);
-                Value::String(ans.into())
+                Value::String(Arc::new(ans.into()))
             }
         }
         _ => return Err(stacked_error("while calling +", sig_error(), span)),
@@ -818,17 +873,17 @@ fn test_add_operation() {
     assert_eq!(result, Value::Float(5.5));
 
     // Test string concatenation
-    value_stack.push_value(Value::String("Hello".to_string().into())).unwrap();
-    value_stack.push_value(Value::String(" World".to_string().into())).unwrap();
+    value_stack.push_value(Value::String(Arc::new("Hello".into()))).unwrap();
+    value_stack.push_value(Value::String(Arc::new(" World".into()))).unwrap();
     add(&mut value_stack, &string_table, mock_span).unwrap();
     let result = value_stack.pop_value().unwrap();
-    assert_eq!(result, Value::String("Hello World".to_string().into()));
+    assert_eq!(result, Value::String(Arc::new("Hello World".into())));
 
-    value_stack.push_value(Value::String("Hello".to_string().into())).unwrap();
+    value_stack.push_value(Value::String(Arc::new("Hello".into()))).unwrap();
     value_stack.push_value(Value::Int(123)).unwrap();
     add(&mut value_stack, &string_table, mock_span).unwrap();
     let result = value_stack.pop_value().unwrap();
-    assert_eq!(result, Value::String("Hello123".to_string().into()));
+    assert_eq!(result, Value::String(Arc::new("Hello123".into())));
 }
 
 
@@ -865,19 +920,19 @@ fn test_chained_operations() {
     assert_eq!(result, Value::Int(8));
 
     // Chain operations with strings: "Hello" + " " + "World" + 123
-    value_stack.push_value(Value::String("Hello".to_string().into())).unwrap();
-    value_stack.push_value(Value::String(" ".to_string().into())).unwrap();
+    value_stack.push_value(Value::String(Arc::new("Hello".into()))).unwrap();
+    value_stack.push_value(Value::String(Arc::new(" ".into()))).unwrap();
     add(&mut value_stack, &string_table, mock_span).unwrap(); // Stack: ["Hello "]
 
 
-    value_stack.push_value(Value::String("World".to_string().into())).unwrap();
+    value_stack.push_value(Value::String(Arc::new("World".into()))).unwrap();
     add(&mut value_stack, &string_table, mock_span).unwrap(); // Stack: ["Hello World"]
 
 
     value_stack.push_value(Value::Int(123)).unwrap();
     add(&mut value_stack, &string_table, mock_span).unwrap(); // Stack: ["Hello World123"]
     let result = value_stack.pop_value().unwrap();
-    assert_eq!(result, Value::String("Hello World123".to_string().into()));
+    assert_eq!(result, Value::String(Arc::new("Hello World123".into())));
 }
 
 #[test]
@@ -905,18 +960,22 @@ fn test_division_by_zero() {
     }
 }
 
-pub fn call_string<'code>(string:Arc<String>,stack: &mut ValueStack<'code>, _table: &StringTable<'code>, span: Span) -> Result<(), ErrList> {
+pub fn call_string<'code>(string:Arc<FString>,stack: &mut ValueStack<'code>, _table: &StringTable<'code>, span: Span) -> Result<(), ErrList> {
     const ERR_MESSAGE: &str = "while calling a string"; 
     let arg = stack.pop_value().ok_or_else(|| stacked_error(ERR_MESSAGE, sig_error(), span))?;
     stack.pop_terminator().ok_or_else(|| stacked_error(ERR_MESSAGE, sig_error(), span))?;
 
     match arg {
+        //lengths and indecies count chars, negative indecies count from the end
         Value::Atom( get_id!(":len")) => {
+            stack.push_int(string.char_len() as i64)
+        },
+        Value::Atom( get_id!(":byte_len")) => {
             stack.push_int(string.len() as i64)
         },
         Value::Int(i) => {
-           match string.chars().nth(i as usize) {
-                Some(c) =>stack.push_string(Arc::new(c.to_string())), 
+           match string.resolve_index(i).and_then(|i| string.char_at(i)) {
+                Some(c) =>stack.push_string(Arc::new(c.to_string().into())), 
                 None => stack.push_atom(get_id!(":string_out_of_bounds"))
             }
         },
@@ -926,6 +985,28 @@ pub fn call_string<'code>(string:Arc<String>,stack: &mut ValueStack<'code>, _tab
     
 }
 
+//same as strings except indexing gives the byte as an int
+pub fn call_bytes<'code>(bytes:Arc<[u8]>,stack: &mut ValueStack<'code>, _table: &StringTable<'code>, span: Span) -> Result<(), ErrList> {
+    const ERR_MESSAGE: &str = "while calling bytes"; 
+    let arg = stack.pop_value().ok_or_else(|| stacked_error(ERR_MESSAGE, sig_error(), span))?;
+    stack.pop_terminator().ok_or_else(|| stacked_error(ERR_MESSAGE, sig_error(), span))?;
+
+    match arg {
+        Value::Atom( get_id!(":len")) => {
+            stack.push_int(bytes.len() as i64)
+        },
+        Value::Int(i) => {
+            let index = if i < 0 { i + bytes.len() as i64 } else { i };
+            match usize::try_from(index).ok().and_then(|i| bytes.get(i)) {
+                Some(b) => stack.push_int(*b as i64),
+                None => stack.push_atom(get_id!(":bytes_out_of_bounds"))
+            }
+        },
+        _ => {return Err(stacked_error(ERR_MESSAGE, sig_error(), span));},
+    }
+    .map_err(|_| stacked_error(ERR_MESSAGE, overflow_error(), span))
+}
+
 #[inline]
 pub fn get_type_atom_id(v:&Value) -> u32 {
     match v {
@@ -935,6 +1016,8 @@ pub fn get_type_atom_id(v:&Value) -> u32 {
         Value::Float(_)=>get_id!(":float"),
         Value::Atom(_)=>get_id!(":atom"),
         Value::String(_)=>get_id!(":string"),
+        Value::Bytes(_)=>get_id!(":bytes"),
+        Value::Stream(_)=>get_id!(":stream"),
         
         Value::Func(_) | Value::WeakFunc(_) | Value::StaticFunc(_) | Value::DataFunc(_) => get_id!(":func"),
     }
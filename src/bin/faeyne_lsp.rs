@@ -0,0 +1,6 @@
+use std::io;
+
+// editors start this and talk to it over stdin/stdout
+fn main() -> io::Result<()> {
+    faeyne_lang::lsp::run(io::stdin().lock(), io::stdout().lock())
+}
@@ -0,0 +1,266 @@
+// The bytes module, reached with system(:bytes)(:name).
+// Indexes work like the string module (negative ones count from the end, clamped to the bytes)
+// and decoding something malformed gives :err.
+
+use std::sync::Arc;
+
+use ast::ast::StringTable;
+use ast::get_id;
+use ast::id::*;
+
+use crate::basic_ops::{pop_args, push};
+use crate::reporting::*;
+use crate::stack::ValueStack;
+use crate::system::{array_items, make_array};
+use crate::value::Value;
+
+//every atom the module answers to (used for editor completion)
+pub const BYTES_FUNCS: &[&str] = &[
+    ":from_utf8",
+    ":to_utf8",
+    ":from_hex",
+    ":to_hex",
+    ":from_base64",
+    ":to_base64",
+    ":from_array",
+    ":to_array",
+    ":slice",
+];
+
+pub fn bytes_module<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+    let atom = stack.pop_atom().ok_or_else(|| arg_error(1, 0))?;
+    stack.pop_terminator().ok_or_else(sig_error)?;
+
+    let func = match atom {
+        get_id!(":from_utf8") => from_utf8_fn,
+        get_id!(":to_utf8") => to_utf8_fn,
+        get_id!(":from_hex") => from_hex_fn,
+        get_id!(":to_hex") => to_hex_fn,
+        get_id!(":from_base64") => from_base64_fn,
+        get_id!(":to_base64") => to_base64_fn,
+        get_id!(":from_array") => from_array_fn,
+        get_id!(":to_array") => to_array_fn,
+        get_id!(":slice") => slice_fn,
+        _ => return Err(sig_error()),
+    };
+
+    stack.push_value(Value::StaticFunc(func)).map_err(|_| overflow_error())
+}
+
+fn push_str(stack: &mut ValueStack, s: String) -> Result<(), ErrList> {
+    stack.push_string(Arc::new(s.into())).map_err(|_| overflow_error())
+}
+
+fn push_decoded(stack: &mut ValueStack, decoded: Option<Vec<u8>>) -> Result<(), ErrList> {
+    match decoded {
+        Some(b) => push(stack, Value::Bytes(b.into())),
+        None => push(stack, Value::Atom(get_id!(":err"))),
+    }
+}
+
+fn pop_bytes(stack: &mut ValueStack) -> Result<Arc<[u8]>, ErrList> {
+    let [Value::Bytes(b)] = pop_args(stack)? else {
+        return Err(sig_error());
+    };
+    Ok(b)
+}
+
+fn pop_string(stack: &mut ValueStack) -> Result<String, ErrList> {
+    let [Value::String(s)] = pop_args(stack)? else {
+        return Err(sig_error());
+    };
+    Ok(s.to_string())
+}
+
+const HEX_DIGITS: &[u8; 16] = b"0123456789abcdef";
+const BASE64_DIGITS: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .flat_map(|b| [HEX_DIGITS[(b >> 4) as usize], HEX_DIGITS[(b & 0xf) as usize]])
+        .map(char::from)
+        .collect()
+}
+
+// either case is accepted
+pub fn from_hex(s: &str) -> Option<Vec<u8>> {
+    if !s.len().is_multiple_of(2) {
+        return None;
+    }
+    let digit = |c: u8| (c as char).to_digit(16).map(|d| d as u8);
+    s.as_bytes().chunks(2).map(|pair| Some(digit(pair[0])? << 4 | digit(pair[1])?)).collect()
+}
+
+// standard alphabet with = padding
+pub fn to_base64(bytes: &[u8]) -> String {
+    let mut ans = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let mut group = [0u8; 3];
+        group[..chunk.len()].copy_from_slice(chunk);
+        let n = u32::from_be_bytes([0, group[0], group[1], group[2]]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                ans.push(BASE64_DIGITS[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                ans.push('=');
+            }
+        }
+    }
+    ans
+}
+
+// padding is required so every valid input has exactly one encoding length
+pub fn from_base64(s: &str) -> Option<Vec<u8>> {
+    let s = s.as_bytes();
+    if !s.len().is_multiple_of(4) {
+        return None;
+    }
+    let digit = |c: u8| BASE64_DIGITS.iter().position(|d| *d == c).map(|d| d as u32);
+
+    let mut ans = Vec::with_capacity(s.len() / 4 * 3);
+    for (i, chunk) in s.chunks(4).enumerate() {
+        let last = i == s.len() / 4 - 1;
+        let padding = chunk.iter().rev().take_while(|c| **c == b'=').count();
+        if padding > 2 || (padding > 0 && !last) {
+            return None;
+        }
+
+        let mut n = 0;
+        for c in &chunk[..4 - padding] {
+            n = n << 6 | digit(*c)?;
+        }
+        n <<= 6 * padding;
+        ans.extend_from_slice(&n.to_be_bytes()[1..4 - padding]);
+    }
+    Some(ans)
+}
+
+// byte offset of an index, clamped to the bytes
+fn clamp_index(len: usize, index: i64) -> usize {
+    let index = if index < 0 { index + len as i64 } else { index };
+    index.clamp(0, len as i64) as usize
+}
+
+pub fn from_utf8_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+    let s = pop_string(stack)?;
+    push(stack, Value::Bytes(s.into_bytes().into()))
+}
+
+// to_utf8(b) gives a string or :err when the bytes are not valid utf8
+pub fn to_utf8_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+    let b = pop_bytes(stack)?;
+    match std::str::from_utf8(&b) {
+        Ok(s) => push_str(stack, s.to_string()),
+        Err(_) => push(stack, Value::Atom(get_id!(":err"))),
+    }
+}
+
+pub fn from_hex_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+    let s = pop_string(stack)?;
+    push_decoded(stack, from_hex(&s))
+}
+
+pub fn to_hex_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+    let b = pop_bytes(stack)?;
+    push_str(stack, to_hex(&b))
+}
+
+pub fn from_base64_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+    let s = pop_string(stack)?;
+    push_decoded(stack, from_base64(&s))
+}
+
+pub fn to_base64_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+    let b = pop_bytes(stack)?;
+    push_str(stack, to_base64(&b))
+}
+
+// from_array(a) takes an array of ints from 0 to 255
+pub fn from_array_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+    let [array] = pop_args(stack)?;
+    let bytes = array_items(&array)?
+        .iter()
+        .map(|v| match v {
+            Value::Int(i) => u8::try_from(*i).map_err(|_| sig_error()),
+            _ => Err(sig_error()),
+        })
+        .collect::<Result<Vec<u8>, ErrList>>()?;
+    push(stack, Value::Bytes(bytes.into()))
+}
+
+pub fn to_array_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+    let b = pop_bytes(stack)?;
+    let items = b.iter().map(|b| Value::Int(*b as i64)).collect();
+    push(stack, Value::DataFunc(make_array(items)))
+}
+
+// slice(b, start, end) from start up to (not including) end
+pub fn slice_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+    let [Value::Bytes(b), Value::Int(start), Value::Int(end)] = pop_args(stack)? else {
+        return Err(sig_error());
+    };
+
+    let start = clamp_index(b.len(), start);
+    let end = clamp_index(b.len(), end).max(start);
+    push(stack, Value::Bytes(b[start..end].into()))
+}
+
+#[test]
+fn test_encodings() {
+    assert_eq!(to_hex(&[0, 15, 16, 255]), "000f10ff");
+    assert_eq!(from_hex("000F10ff"), Some(vec![0, 15, 16, 255]));
+    assert_eq!(from_hex("abc"), None);
+    assert_eq!(from_hex("zz"), None);
+
+    // the examples from rfc 4648
+    let cases = ["", "Zg==", "Zm8=", "Zm9v", "Zm9vYg==", "Zm9vYmE=", "Zm9vYmFy"];
+    for (len, encoded) in cases.iter().enumerate() {
+        let raw = &b"foobar"[..len];
+        assert_eq!(to_base64(raw), *encoded);
+        assert_eq!(from_base64(encoded).as_deref(), Some(raw));
+    }
+    assert_eq!(to_base64(&[0xfb, 0xff]), "+/8=");
+    for bad in ["Zg=", "Z===", "Zg==Zm9v", "Zm9*"] {
+        assert_eq!(from_base64(bad), None, "{}", bad);
+    }
+}
+
+#[cfg(test)]
+use crate::basic_ops::{call_native, text};
+
+#[cfg(test)]
+fn bytes(b: &[u8]) -> Value<'static> {
+    Value::Bytes(b.into())
+}
+
+#[test]
+fn test_conversions() {
+    let err = Value::Atom(get_id!(":err"));
+    assert_eq!(call_native(from_utf8_fn, [text("hé")]).unwrap(), bytes(b"h\xc3\xa9"));
+    assert_eq!(call_native(to_utf8_fn, [bytes(b"h\xc3\xa9")]).unwrap(), text("hé"));
+    assert_eq!(call_native(to_utf8_fn, [bytes(b"h\xc3")]).unwrap(), err);
+    assert_eq!(call_native(from_hex_fn, [text("00ff7f")]).unwrap(), bytes(&[0, 255, 127]));
+    assert_eq!(call_native(from_hex_fn, [text("0g")]).unwrap(), err);
+    assert_eq!(call_native(to_hex_fn, [bytes(&[0xc3, 0xa9])]).unwrap(), text("c3a9"));
+    assert_eq!(call_native(to_base64_fn, [bytes("héllo".as_bytes())]).unwrap(), text("aMOpbGxv"));
+    assert_eq!(call_native(from_base64_fn, [text("aMOpbGxv")]).unwrap(), bytes("héllo".as_bytes()));
+    assert!(call_native(to_hex_fn, [text("00")]).is_err());
+}
+
+#[test]
+fn test_arrays() {
+    let array = call_native(to_array_fn, [bytes(&[1, 33])]).unwrap();
+    assert_eq!(array_items(&array).unwrap(), [Value::Int(1), Value::Int(33)]);
+    assert_eq!(call_native(from_array_fn, [array]).unwrap(), bytes(&[1, 33]));
+    let too_big = Value::DataFunc(make_array(vec![Value::Int(256)]));
+    assert!(call_native(from_array_fn, [too_big]).is_err());
+}
+
+#[test]
+fn test_slice() {
+    let b = || bytes(&[1, 2, 3, 4]);
+    assert_eq!(call_native(slice_fn, [b(), Value::Int(1), Value::Int(3)]).unwrap(), bytes(&[2, 3]));
+    assert_eq!(call_native(slice_fn, [b(), Value::Int(-2), Value::Int(100)]).unwrap(), bytes(&[3, 4]));
+    assert_eq!(call_native(slice_fn, [b(), Value::Int(3), Value::Int(1)]).unwrap(), bytes(&[]));
+}
@@ -0,0 +1,435 @@
+// Where the file functions in system get their files from.
+// RealFs is the disk (what system::system uses), MemoryFs is a tree kept in memory
+// so tests can run scripts without touching the disk and look at what they left behind.
+
+use std::collections::BTreeMap;
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::{self, BufRead, BufReader, Cursor, ErrorKind, Write};
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use std::time::UNIX_EPOCH;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Metadata {
+    pub size: u64,
+    pub is_file: bool,
+    pub is_dir: bool,
+    pub readonly: bool,
+    //unix seconds, when the backend knows it
+    pub modified: Option<i64>,
+}
+
+pub trait FileSystem: Send + Sync {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>>;
+    //for reading a bit at a time (like line by line)
+    fn open(&self, path: &Path) -> io::Result<Box<dyn BufRead + Send>>;
+    //creates the file or replaces what was in it
+    fn write(&self, path: &Path, data: &[u8]) -> io::Result<()>;
+    //creates the file if needed
+    fn append(&self, path: &Path, data: &[u8]) -> io::Result<()>;
+    fn remove_file(&self, path: &Path) -> io::Result<()>;
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()>;
+    fn copy(&self, from: &Path, to: &Path) -> io::Result<()>;
+    fn exists(&self, path: &Path) -> io::Result<bool>;
+    fn metadata(&self, path: &Path) -> io::Result<Metadata>;
+    fn create_dir(&self, path: &Path) -> io::Result<()>;
+    fn create_dir_all(&self, path: &Path) -> io::Result<()>;
+    fn remove_dir(&self, path: &Path) -> io::Result<()>;
+    fn remove_dir_all(&self, path: &Path) -> io::Result<()>;
+    //the full paths of everything directly inside the directory
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>>;
+
+    //relative paths are relative to this
+    fn current_dir(&self) -> io::Result<PathBuf>;
+    //the absolute path of something that exists, with links and dots resolved
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf>;
+    //true for anything at the path, including a link to nothing
+    fn entry_exists(&self, path: &Path) -> bool;
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RealFs;
+
+impl FileSystem for RealFs {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        fs::read(path)
+    }
+
+    fn open(&self, path: &Path) -> io::Result<Box<dyn BufRead + Send>> {
+        Ok(Box::new(BufReader::new(fs::File::open(path)?)))
+    }
+
+    fn write(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+        fs::write(path, data)
+    }
+
+    fn append(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+        OpenOptions::new().create(true).append(true).open(path)?.write_all(data)
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        fs::remove_file(path)
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        fs::rename(from, to)
+    }
+
+    fn copy(&self, from: &Path, to: &Path) -> io::Result<()> {
+        fs::copy(from, to).map(|_| ())
+    }
+
+    fn exists(&self, path: &Path) -> io::Result<bool> {
+        fs::exists(path)
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+        let meta = fs::metadata(path)?;
+        let modified = meta.modified().ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_secs() as i64);
+        Ok(Metadata {
+            size: meta.len(),
+            is_file: meta.is_file(),
+            is_dir: meta.is_dir(),
+            readonly: meta.permissions().readonly(),
+            modified,
+        })
+    }
+
+    fn create_dir(&self, path: &Path) -> io::Result<()> {
+        fs::create_dir(path)
+    }
+
+    fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        fs::create_dir_all(path)
+    }
+
+    fn remove_dir(&self, path: &Path) -> io::Result<()> {
+        fs::remove_dir(path)
+    }
+
+    fn remove_dir_all(&self, path: &Path) -> io::Result<()> {
+        fs::remove_dir_all(path)
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        Ok(fs::read_dir(path)?.flatten().map(|entry| entry.path()).collect())
+    }
+
+    fn current_dir(&self) -> io::Result<PathBuf> {
+        env::current_dir()
+    }
+
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        path.canonicalize()
+    }
+
+    fn entry_exists(&self, path: &Path) -> bool {
+        fs::symlink_metadata(path).is_ok()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    File(Vec<u8>),
+    Dir,
+}
+
+// a tree of files rooted at / (which is also the current directory).
+// there are no links so paths are resolved by just looking at their parts.
+#[derive(Debug)]
+pub struct MemoryFs {
+    nodes: Mutex<BTreeMap<PathBuf, Node>>,
+}
+
+impl Default for MemoryFs {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn err<T>(kind: ErrorKind) -> io::Result<T> {
+    Err(io::Error::from(kind))
+}
+
+// the absolute form of a path, ".." at the root stays at the root like it does on disk
+fn normalize(path: &Path) -> PathBuf {
+    let mut ans = PathBuf::from("/");
+    for part in path.components() {
+        match part {
+            Component::Normal(name) => ans.push(name),
+            Component::ParentDir => {
+                ans.pop();
+            }
+            Component::RootDir | Component::CurDir | Component::Prefix(_) => {}
+        }
+    }
+    ans
+}
+
+type Nodes = BTreeMap<PathBuf, Node>;
+
+fn descendants<'a>(nodes: &'a Nodes, dir: &'a Path) -> impl Iterator<Item = &'a PathBuf> + 'a {
+    nodes.range(dir.to_path_buf()..).map(|(p, _)| p).skip(1).take_while(move |p| p.starts_with(dir))
+}
+
+// the parent has to be a directory for anything to be made in it
+fn check_parent(nodes: &Nodes, path: &Path) -> io::Result<()> {
+    match path.parent().map(|p| nodes.get(p)) {
+        Some(Some(Node::Dir)) => Ok(()),
+        Some(Some(Node::File(_))) => err(ErrorKind::NotADirectory),
+        Some(None) => err(ErrorKind::NotFound),
+        //the root itself
+        None => err(ErrorKind::AlreadyExists),
+    }
+}
+
+impl MemoryFs {
+    pub fn new() -> Self {
+        let nodes = BTreeMap::from([(PathBuf::from("/"), Node::Dir)]);
+        MemoryFs { nodes: Mutex::new(nodes) }
+    }
+
+    fn nodes(&self) -> MutexGuard<'_, Nodes> {
+        self.nodes.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    // adds a file along with any missing directories above it
+    pub fn insert_file(&self, path: impl AsRef<Path>, contents: impl Into<Vec<u8>>) {
+        let path = normalize(path.as_ref());
+        let mut nodes = self.nodes();
+        for dir in path.ancestors().skip(1) {
+            nodes.insert(dir.to_path_buf(), Node::Dir);
+        }
+        nodes.insert(path, Node::File(contents.into()));
+    }
+
+    pub fn file(&self, path: impl AsRef<Path>) -> Option<Vec<u8>> {
+        match self.nodes().get(&normalize(path.as_ref())) {
+            Some(Node::File(data)) => Some(data.clone()),
+            _ => None,
+        }
+    }
+
+    // every file and directory in order (directories end with a /), handy for comparing whole trees
+    pub fn paths(&self) -> Vec<String> {
+        self.nodes()
+            .iter()
+            .skip(1)
+            .map(|(p, node)| match node {
+                Node::Dir => format!("{}/", p.display()),
+                Node::File(_) => p.display().to_string(),
+            })
+            .collect()
+    }
+
+    fn update_file(&self, path: &Path, f: impl FnOnce(&mut Vec<u8>)) -> io::Result<()> {
+        let path = normalize(path);
+        let mut nodes = self.nodes();
+        match nodes.get_mut(&path) {
+            Some(Node::File(data)) => {
+                f(data);
+                Ok(())
+            }
+            Some(Node::Dir) => err(ErrorKind::IsADirectory),
+            None => {
+                check_parent(&nodes, &path)?;
+                let mut data = Vec::new();
+                f(&mut data);
+                nodes.insert(path, Node::File(data));
+                Ok(())
+            }
+        }
+    }
+
+    fn remove(&self, path: &Path, dir: bool, recursive: bool) -> io::Result<()> {
+        let path = normalize(path);
+        let mut nodes = self.nodes();
+        match (nodes.get(&path), dir) {
+            (None, _) => return err(ErrorKind::NotFound),
+            (Some(Node::File(_)), true) => return err(ErrorKind::NotADirectory),
+            (Some(Node::Dir), false) => return err(ErrorKind::IsADirectory),
+            _ if path.parent().is_none() => return err(ErrorKind::PermissionDenied),
+            _ => {}
+        }
+        let inside: Vec<PathBuf> = descendants(&nodes, &path).cloned().collect();
+        if !inside.is_empty() && !recursive {
+            return err(ErrorKind::DirectoryNotEmpty);
+        }
+        for p in inside {
+            nodes.remove(&p);
+        }
+        nodes.remove(&path);
+        Ok(())
+    }
+}
+
+impl FileSystem for MemoryFs {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        match self.nodes().get(&normalize(path)) {
+            Some(Node::File(data)) => Ok(data.clone()),
+            Some(Node::Dir) => err(ErrorKind::IsADirectory),
+            None => err(ErrorKind::NotFound),
+        }
+    }
+
+    //works on a copy, later writes do not show up in an open file
+    fn open(&self, path: &Path) -> io::Result<Box<dyn BufRead + Send>> {
+        Ok(Box::new(Cursor::new(self.read(path)?)))
+    }
+
+    fn write(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+        self.update_file(path, |file| *file = data.to_vec())
+    }
+
+    fn append(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+        self.update_file(path, |file| file.extend_from_slice(data))
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        self.remove(path, false, false)
+    }
+
+    // moves directories with everything in them, an empty directory can be replaced like on unix
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        let (from, to) = (normalize(from), normalize(to));
+        let mut nodes = self.nodes();
+        let Some(node) = nodes.get(&from).cloned() else {
+            return err(ErrorKind::NotFound);
+        };
+        if from == to {
+            return Ok(());
+        }
+        if from.parent().is_none() || to.starts_with(&from) {
+            return err(ErrorKind::InvalidInput);
+        }
+        match (&node, nodes.get(&to)) {
+            (Node::File(_), Some(Node::Dir)) => return err(ErrorKind::IsADirectory),
+            (Node::Dir, Some(Node::File(_))) => return err(ErrorKind::NotADirectory),
+            (Node::Dir, Some(Node::Dir)) if descendants(&nodes, &to).next().is_some() => {
+                return err(ErrorKind::DirectoryNotEmpty)
+            }
+            (_, Some(_)) => {}
+            (_, None) => check_parent(&nodes, &to)?,
+        }
+
+        let moved: Vec<PathBuf> = descendants(&nodes, &from).cloned().collect();
+        for p in moved {
+            let node = nodes.remove(&p).unwrap();
+            nodes.insert(to.join(p.strip_prefix(&from).unwrap()), node);
+        }
+        nodes.remove(&from);
+        nodes.insert(to, node);
+        Ok(())
+    }
+
+    fn copy(&self, from: &Path, to: &Path) -> io::Result<()> {
+        match self.read(from) {
+            Err(e) if e.kind() == ErrorKind::IsADirectory => err(ErrorKind::InvalidInput),
+            data => self.write(to, &data?),
+        }
+    }
+
+    fn exists(&self, path: &Path) -> io::Result<bool> {
+        Ok(self.nodes().contains_key(&normalize(path)))
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+        let (size, is_file) = match self.nodes().get(&normalize(path)) {
+            Some(Node::File(data)) => (data.len() as u64, true),
+            Some(Node::Dir) => (0, false),
+            None => return err(ErrorKind::NotFound),
+        };
+        Ok(Metadata { size, is_file, is_dir: !is_file, readonly: false, modified: None })
+    }
+
+    fn create_dir(&self, path: &Path) -> io::Result<()> {
+        let path = normalize(path);
+        let mut nodes = self.nodes();
+        if nodes.contains_key(&path) {
+            return err(ErrorKind::AlreadyExists);
+        }
+        check_parent(&nodes, &path)?;
+        nodes.insert(path, Node::Dir);
+        Ok(())
+    }
+
+    fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        let path = normalize(path);
+        let mut nodes = self.nodes();
+        let ancestors: Vec<&Path> = path.ancestors().collect();
+        for dir in ancestors.into_iter().rev() {
+            match nodes.get(dir) {
+                Some(Node::Dir) => {}
+                Some(Node::File(_)) if dir == path => return err(ErrorKind::AlreadyExists),
+                Some(Node::File(_)) => return err(ErrorKind::NotADirectory),
+                None => {
+                    nodes.insert(dir.to_path_buf(), Node::Dir);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn remove_dir(&self, path: &Path) -> io::Result<()> {
+        self.remove(path, true, false)
+    }
+
+    fn remove_dir_all(&self, path: &Path) -> io::Result<()> {
+        self.remove(path, true, true)
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        let path = normalize(path);
+        let nodes = self.nodes();
+        match nodes.get(&path) {
+            Some(Node::Dir) => Ok(descendants(&nodes, &path).filter(|p| p.parent() == Some(&path)).cloned().collect()),
+            Some(Node::File(_)) => err(ErrorKind::NotADirectory),
+            None => err(ErrorKind::NotFound),
+        }
+    }
+
+    fn current_dir(&self) -> io::Result<PathBuf> {
+        Ok(PathBuf::from("/"))
+    }
+
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        let path = normalize(path);
+        match self.nodes().contains_key(&path) {
+            true => Ok(path),
+            false => err(ErrorKind::NotFound),
+        }
+    }
+
+    fn entry_exists(&self, path: &Path) -> bool {
+        self.nodes().contains_key(&normalize(path))
+    }
+}
+
+#[test]
+fn test_memory_fs() {
+    let fs = MemoryFs::new();
+    fs.insert_file("/a/b.txt", "hi");
+    assert_eq!(fs.read(Path::new("a/./b.txt")).unwrap(), b"hi");
+    assert_eq!(fs.read(Path::new("/a")).unwrap_err().kind(), ErrorKind::IsADirectory);
+    assert_eq!(fs.write(Path::new("/missing/c.txt"), b"").unwrap_err().kind(), ErrorKind::NotFound);
+    assert_eq!(fs.write(Path::new("/a/b.txt/c.txt"), b"").unwrap_err().kind(), ErrorKind::NotADirectory);
+
+    fs.append(Path::new("/a/b.txt"), b"!").unwrap();
+    fs.create_dir_all(Path::new("/a/x/y")).unwrap();
+    fs.insert_file("/a/x/y/z.txt", "deep");
+    assert_eq!(fs.remove_dir(Path::new("/a/x")).unwrap_err().kind(), ErrorKind::DirectoryNotEmpty);
+    assert_eq!(fs.rename(Path::new("/a"), Path::new("/a/x/a")).unwrap_err().kind(), ErrorKind::InvalidInput);
+
+    fs.rename(Path::new("/a/x"), Path::new("/moved")).unwrap();
+    assert_eq!(fs.paths(), ["/a/", "/a/b.txt", "/moved/", "/moved/y/", "/moved/y/z.txt"]);
+    assert_eq!(fs.read_dir(Path::new("/")).unwrap(), [PathBuf::from("/a"), PathBuf::from("/moved")]);
+    assert_eq!(fs.file("/a/b.txt").unwrap(), b"hi!");
+
+    fs.remove_dir_all(Path::new("/moved")).unwrap();
+    assert_eq!(fs.remove_dir_all(Path::new("/")).unwrap_err().kind(), ErrorKind::PermissionDenied);
+    assert_eq!(fs.canonicalize(Path::new("/a/../a/b.txt")).unwrap(), PathBuf::from("/a/b.txt"));
+    assert_eq!(fs.paths(), ["/a/", "/a/b.txt"]);
+}
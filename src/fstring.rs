@@ -0,0 +1,166 @@
+// The string behind Value::String.
+// Indexing by char would be O(n) on utf8, so the first time a string is indexed
+// we record the byte offset of every SAMPLE'th char. After that finding a char is O(1).
+// Ascii strings (the common case) skip the table since chars and bytes line up.
+
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+use std::sync::OnceLock;
+
+const SAMPLE: usize = 32;
+
+pub struct FString {
+    text: String,
+    index: OnceLock<CharIndex>,
+}
+
+struct CharIndex {
+    len: usize,
+    samples: Vec<usize>, // empty for ascii
+}
+
+impl FString {
+    pub fn new(text: String) -> Self {
+        FString { text, index: OnceLock::new() }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    pub fn push_str(&mut self, s: &str) {
+        self.index.take();
+        self.text.push_str(s);
+    }
+
+    pub fn insert_str(&mut self, at: usize, s: &str) {
+        self.index.take();
+        self.text.insert_str(at, s);
+    }
+
+    fn index(&self) -> &CharIndex {
+        self.index.get_or_init(|| {
+            if self.text.is_ascii() {
+                return CharIndex { len: self.text.len(), samples: Vec::new() };
+            }
+
+            let mut len = 0;
+            let mut samples = Vec::with_capacity(self.text.len() / SAMPLE + 1);
+            for (i, (byte, _)) in self.text.char_indices().enumerate() {
+                if i % SAMPLE == 0 {
+                    samples.push(byte);
+                }
+                len += 1;
+            }
+            CharIndex { len, samples }
+        })
+    }
+
+    pub fn char_len(&self) -> usize {
+        self.index().len
+    }
+
+    // byte offset of the char at index (the end of the string counts as a valid index)
+    pub fn byte_offset(&self, index: usize) -> Option<usize> {
+        let char_index = self.index();
+        if index > char_index.len {
+            return None;
+        }
+        if char_index.samples.is_empty() {
+            return Some(index);
+        }
+
+        let Some(&start) = char_index.samples.get(index / SAMPLE) else {
+            return Some(self.text.len());
+        };
+        let offset = self.text[start..]
+            .char_indices()
+            .nth(index % SAMPLE)
+            .map_or(self.text.len(), |(i, _)| start + i);
+        Some(offset)
+    }
+
+    pub fn char_at(&self, index: usize) -> Option<char> {
+        let offset = self.byte_offset(index)?;
+        self.text[offset..].chars().next()
+    }
+
+    // turns a possibly negative index (counting from the end) into a plain one
+    pub fn resolve_index(&self, index: i64) -> Option<usize> {
+        match index {
+            0.. => usize::try_from(index).ok(),
+            _ => self.char_len().checked_sub(index.unsigned_abs() as usize),
+        }
+    }
+}
+
+impl Deref for FString {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.text
+    }
+}
+
+impl From<String> for FString {
+    fn from(text: String) -> Self {
+        FString::new(text)
+    }
+}
+
+impl From<&str> for FString {
+    fn from(text: &str) -> Self {
+        FString::new(text.to_string())
+    }
+}
+
+impl PartialEq for FString {
+    fn eq(&self, other: &Self) -> bool {
+        self.text == other.text
+    }
+}
+
+impl Eq for FString {}
+
+impl Hash for FString {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.text.hash(state)
+    }
+}
+
+impl fmt::Display for FString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.text.fmt(f)
+    }
+}
+
+impl fmt::Debug for FString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.text.fmt(f)
+    }
+}
+
+#[test]
+fn test_char_index() {
+    let ascii = FString::from("hello");
+    assert_eq!(ascii.char_len(), 5);
+    assert_eq!(ascii.char_at(1), Some('e'));
+    assert_eq!(ascii.byte_offset(5), Some(5));
+    assert_eq!(ascii.char_at(5), None);
+
+    let text: String = (0..100).map(|i| if i % 3 == 0 { 'é' } else { 'a' }).collect();
+    let s = FString::from(text.clone());
+    assert_eq!(s.char_len(), 100);
+    for (i, c) in text.chars().enumerate() {
+        assert_eq!(s.char_at(i), Some(c));
+        assert_eq!(s.byte_offset(i), text.char_indices().nth(i).map(|(b, _)| b));
+    }
+    assert_eq!(s.byte_offset(100), Some(text.len()));
+    assert_eq!(s.byte_offset(101), None);
+
+    assert_eq!(s.resolve_index(-1), Some(99));
+    assert_eq!(s.resolve_index(-100), Some(0));
+    assert_eq!(s.resolve_index(-101), None);
+    assert_eq!(s.resolve_index(7), Some(7));
+}
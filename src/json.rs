@@ -0,0 +1,165 @@
+// JSON in and out, reached with system(:json_parse) and system(:json_stringify).
+// Objects become maps (string keys, :keys gives them sorted), arrays become arrays
+// and numbers are ints whenever they hold a whole number that fits.
+// Going out only arrays and maps made natively are walked, atoms and int keys are written as strings.
+
+use std::sync::Arc;
+
+use ast::ast::StringTable;
+use ast::get_id;
+use ast::id::*;
+use serde_json::{Map, Number, Value as Json};
+
+use crate::basic_ops::{pop_args, push};
+use crate::reporting::*;
+use crate::stack::ValueStack;
+use crate::system::{make_array, make_map};
+use crate::value::Value;
+use crate::vm::DataKind;
+
+fn number_value(n: &Number) -> Value<'static> {
+    if let Some(i) = n.as_i64() {
+        return Value::Int(i);
+    }
+    let f = n.as_f64().unwrap_or(f64::NAN);
+    if f.fract() == 0.0 && (i64::MIN as f64..i64::MAX as f64).contains(&f) {
+        Value::Int(f as i64)
+    } else {
+        Value::Float(f)
+    }
+}
+
+pub fn from_json(json: Json) -> Value<'static> {
+    match json {
+        Json::Null => Value::Nil,
+        Json::Bool(b) => Value::Bool(b),
+        Json::Number(n) => number_value(&n),
+        Json::String(s) => Value::String(Arc::new(s.into())),
+        Json::Array(items) => Value::DataFunc(make_array(items.into_iter().map(from_json).collect())),
+        Json::Object(fields) => {
+            let entries = fields
+                .into_iter()
+                .map(|(k, v)| (Value::String(Arc::new(k.into())), from_json(v)))
+                .collect();
+            Value::DataFunc(make_map(entries))
+        }
+    }
+}
+
+fn atom_name<'a>(id: u32, table: &'a StringTable) -> &'a str {
+    let name = table.get_raw_str(id);
+    name.strip_prefix(':').unwrap_or(name)
+}
+
+fn key_string(key: &Value, table: &StringTable) -> Result<String, ErrList> {
+    match key {
+        Value::String(s) => Ok(s.to_string()),
+        Value::Atom(id) => Ok(atom_name(*id, table).to_string()),
+        Value::Int(i) => Ok(i.to_string()),
+        _ => Err(sig_error()),
+    }
+}
+
+pub fn to_json(value: &Value, table: &StringTable) -> Result<Json, ErrList> {
+    let json = match value {
+        Value::Nil => Json::Null,
+        Value::Bool(b) => Json::Bool(*b),
+        Value::Int(i) => Json::from(*i),
+        Value::Float(f) => Json::Number(Number::from_f64(*f).ok_or_else(sig_error)?),
+        Value::String(s) => Json::String(s.to_string()),
+        Value::Atom(id) => Json::String(atom_name(*id, table).to_string()),
+        Value::DataFunc(f) => match f.kind() {
+            DataKind::Map(entries) => {
+                let mut fields = Map::new();
+                for (key, item) in entries.iter() {
+                    fields.insert(key_string(key, table)?, to_json(item, table)?);
+                }
+                Json::Object(fields)
+            }
+            DataKind::Array(items) => Json::Array(items.iter().map(|v| to_json(v, table)).collect::<Result<_, _>>()?),
+            //a host function could do anything when called so it is never asked what it holds
+            DataKind::Host | DataKind::Pending(_) => return Err(sig_error()),
+        },
+        Value::Bytes(_) | Value::Func(_) | Value::WeakFunc(_) | Value::StaticFunc(_) | Value::Stream(_) => return Err(sig_error()),
+    };
+    Ok(json)
+}
+
+// json_parse(s) gives the value or :err when s is not valid JSON
+pub fn json_parse_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+    let [Value::String(s)] = pop_args(stack)? else {
+        return Err(sig_error());
+    };
+    match serde_json::from_str(&s) {
+        Ok(json) => push(stack, from_json(json)),
+        Err(_) => push(stack, Value::Atom(get_id!(":err"))),
+    }
+}
+
+// json_stringify(value) writes compact JSON, functions, bytes, streams and nan can not be written
+pub fn json_stringify_fn<'code>(stack: &mut ValueStack<'code>, table: &StringTable<'code>) -> Result<(), ErrList> {
+    let [value] = pop_args(stack)?;
+    let json = to_json(&value, table)?;
+    stack.push_string(Arc::new(json.to_string().into())).map_err(|_| overflow_error())
+}
+
+#[test]
+fn test_numbers() {
+    let parse = |s: &str| from_json(serde_json::from_str(s).unwrap());
+    assert_eq!(parse("3"), Value::Int(3));
+    assert_eq!(parse("-3.0"), Value::Int(-3));
+    assert_eq!(parse("1e3"), Value::Int(1000));
+    assert_eq!(parse("2.5"), Value::Float(2.5));
+    assert_eq!(parse("18446744073709551615"), Value::Float(18446744073709551615.0));
+}
+
+#[test]
+fn test_host_funcs() {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use crate::vm::DataFunc;
+
+    static CALLED: AtomicBool = AtomicBool::new(false);
+    let table = StringTable::new();
+    let host = DataFunc::new(|_stack, _table| {
+        CALLED.store(true, Ordering::Relaxed);
+        Err(sig_error())
+    });
+    let nested = Value::DataFunc(make_array(vec![Value::Int(1), Value::DataFunc(host.clone())]));
+    assert!(to_json(&Value::DataFunc(host), &table).is_err());
+    assert!(to_json(&nested, &table).is_err());
+    assert!(!CALLED.load(Ordering::Relaxed), "to_json called a host function");
+
+    let map = make_map(vec![(Value::Int(2), Value::Nil), (Value::String(Arc::new(String::from("a").into())), Value::Bool(true))]);
+    assert_eq!(to_json(&Value::DataFunc(map), &table).unwrap(), serde_json::json!({"2": null, "a": true}));
+}
+
+#[cfg(test)]
+use crate::basic_ops::{call_native, text};
+
+#[test]
+fn test_parse() {
+    let parsed = call_native(json_parse_fn, [text(r#"{"name": "faeyne", "tags": ["a", "b"], "n": 2.0, "none": null, "ok": true}"#)]).unwrap();
+    let Value::DataFunc(map) = &parsed else { panic!("expected a map got {:?}", parsed) };
+    let DataKind::Map(entries) = map.kind() else { panic!("expected a map") };
+    let keys: Vec<_> = entries.iter().map(|(k, _)| k.clone()).collect();
+    assert_eq!(keys, ["n", "name", "none", "ok", "tags"].map(text), "keys come out sorted");
+    assert_eq!(entries[0].1, Value::Int(2));
+    assert_eq!(entries[1].1, text("faeyne"));
+    assert_eq!(entries[2].1, Value::Nil);
+    assert_eq!(entries[3].1, Value::Bool(true));
+    assert_eq!(crate::system::array_items(&entries[4].1).unwrap(), [text("a"), text("b")]);
+    assert_eq!(call_native(json_parse_fn, [text(r#"{"a": }"#)]).unwrap(), Value::Atom(get_id!(":err")));
+}
+
+#[test]
+fn test_stringify() {
+    let stringify = |v: Value<'static>| call_native(json_stringify_fn, [v]);
+    let round_trip = r#"{"a":[1,2.5,"x",null],"b":{"c":false}}"#;
+    assert_eq!(stringify(from_json(serde_json::from_str(round_trip).unwrap())).unwrap(), text(round_trip));
+    assert_eq!(stringify(Value::Atom(get_id!(":ok"))).unwrap(), text(r#""ok""#));
+    assert_eq!(stringify(text(r#"say "hi""#)).unwrap(), text(r#""say \"hi\"""#));
+    let atom_keys = make_map(vec![(Value::Atom(get_id!(":ok")), Value::Int(1))]);
+    assert_eq!(stringify(Value::DataFunc(atom_keys)).unwrap(), text(r#"{"ok":1}"#));
+    assert!(stringify(Value::Float(f64::NAN)).is_err());
+    assert!(stringify(Value::Bytes([1].into())).is_err());
+}
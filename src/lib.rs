@@ -8,6 +8,22 @@ pub mod translate;
 pub mod runtime;
 
 pub mod system;
+pub mod filesystem;
+pub mod sandbox;
+pub mod process;
+pub mod scheduler;
+pub mod stream;
+pub mod transfer;
+pub mod fstring;
+pub mod strings;
+pub mod bytes;
+pub mod numbers;
+pub mod json;
+pub mod math;
+pub mod random;
+pub mod time;
+pub mod lsp;
+pub mod testing;
 
 mod test_code_easy;
 mod test_code;
\ No newline at end of file
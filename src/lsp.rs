@@ -0,0 +1,607 @@
+// Language server over stdio (JSON-RPC with Content-Length framing).
+// Documents are small so every request just re-analyzes the current text.
+
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::{Arc, RwLock};
+
+use codespan::Span;
+use codespan_reporting::diagnostic::{Diagnostic, LabelStyle, Severity};
+use serde_json::{json, Value as Json};
+
+use ast::ast::{FuncDec, OuterExp, StringTable};
+use ast::lexer::{LexTag, Lexer};
+use ast::parser::ProgramParser;
+
+use crate::reporting::{error_diagnostics, parse_error_diagnostic, warning_diagnostic};
+use crate::strings::STRING_FUNCS;
+use crate::bytes::BYTES_FUNCS;
+use crate::math::MATH_FUNCS;
+use crate::random::RANDOM_FUNCS;
+use crate::process::PROCESS_FUNCS;
+use crate::stream::STREAM_FUNCS;
+use crate::system::SYSTEM_FUNCS;
+use crate::translate::{translate_program_with_refs, NameRef};
+
+const METHOD_NOT_FOUND: i64 = -32601;
+
+pub fn read_message(input: &mut impl BufRead) -> io::Result<Option<Json>> {
+    let mut length = None;
+    loop {
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some(value) = line.strip_prefix("Content-Length:") {
+            length = value.trim().parse::<usize>().ok();
+        }
+    }
+
+    let length = length.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "missing Content-Length"))?;
+    let mut body = vec![0; length];
+    input.read_exact(&mut body)?;
+    serde_json::from_slice(&body).map(Some).map_err(io::Error::from)
+}
+
+pub fn write_message(output: &mut impl Write, message: &Json) -> io::Result<()> {
+    let body = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    output.flush()
+}
+
+// runs until the client sends exit (or closes the stream)
+pub fn run(mut input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+    let mut server = Server::default();
+    while let Some(message) = read_message(&mut input)? {
+        for reply in server.handle(&message) {
+            write_message(&mut output, &reply)?;
+        }
+        if server.exit {
+            break;
+        }
+    }
+    Ok(())
+}
+
+#[derive(Default)]
+pub struct Server {
+    docs: HashMap<String, String>,
+    exit: bool,
+}
+
+impl Server {
+    // returns the messages to send back (a response and/or notifications)
+    pub fn handle(&mut self, message: &Json) -> Vec<Json> {
+        let method = message["method"].as_str().unwrap_or("");
+        let params = &message["params"];
+
+        let result = match method {
+            "initialize" => Some(capabilities()),
+            "shutdown" => Some(Json::Null),
+            "exit" => {
+                self.exit = true;
+                None
+            }
+
+            "textDocument/didOpen" => {
+                let doc = &params["textDocument"];
+                let uri = doc["uri"].as_str().unwrap_or("").to_string();
+                let text = doc["text"].as_str().unwrap_or("").to_string();
+                return self.update(uri, text);
+            }
+            "textDocument/didChange" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or("").to_string();
+                // we only ask for full syncs so the last change is the whole text
+                let text = params["contentChanges"]
+                    .as_array()
+                    .and_then(|changes| changes.last())
+                    .and_then(|change| change["text"].as_str());
+                match text {
+                    Some(text) => return self.update(uri, text.to_string()),
+                    None => None,
+                }
+            }
+            "textDocument/didClose" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or("");
+                self.docs.remove(uri);
+                return vec![publish_diagnostics(uri, Vec::new())];
+            }
+
+            "textDocument/definition" => self.position(params).map(|(uri, text, offset)| {
+                definition(&Analysis::new(text), text, offset, uri)
+            }),
+            "textDocument/hover" => self.position(params).map(|(_, text, offset)| {
+                hover(&Analysis::new(text), text, offset)
+            }),
+            "textDocument/completion" => self.position(params).map(|(_, text, offset)| {
+                completion(text, offset)
+            }),
+            "textDocument/documentSymbol" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or("");
+                self.docs.get(uri).map(|text| document_symbols(&Analysis::new(text), text))
+            }
+
+            _ => {
+                if message.get("id").is_some() {
+                    return vec![json!({
+                        "jsonrpc": "2.0",
+                        "id": message["id"],
+                        "error": {"code": METHOD_NOT_FOUND, "message": format!("unknown method {}", method)},
+                    })];
+                }
+                None
+            }
+        };
+
+        match (message.get("id"), result) {
+            (Some(id), Some(result)) => vec![json!({"jsonrpc": "2.0", "id": id, "result": result})],
+            (Some(id), None) => vec![json!({"jsonrpc": "2.0", "id": id, "result": null})],
+            _ => Vec::new(),
+        }
+    }
+
+    fn update(&mut self, uri: String, text: String) -> Vec<Json> {
+        let diagnostics = Analysis::new(&text).diagnostics.iter().map(|d| to_lsp_diagnostic(&text, d)).collect();
+        let reply = publish_diagnostics(&uri, diagnostics);
+        self.docs.insert(uri, text);
+        vec![reply]
+    }
+
+    // the document and byte offset a request points at
+    fn position<'a>(&'a self, params: &'a Json) -> Option<(&'a str, &'a str, usize)> {
+        let uri = params["textDocument"]["uri"].as_str()?;
+        let text = self.docs.get(uri)?;
+        let line = params["position"]["line"].as_u64().unwrap_or(0) as usize;
+        let character = params["position"]["character"].as_u64().unwrap_or(0) as usize;
+        Some((uri, text, position_to_offset(text, line, character)))
+    }
+}
+
+fn capabilities() -> Json {
+    json!({
+        "capabilities": {
+            "textDocumentSync": 1,
+            "definitionProvider": true,
+            "hoverProvider": true,
+            "completionProvider": {"triggerCharacters": [":"]},
+            "documentSymbolProvider": true,
+        },
+        "serverInfo": {"name": "faeyne_lsp", "version": env!("CARGO_PKG_VERSION")},
+    })
+}
+
+fn publish_diagnostics(uri: &str, diagnostics: Vec<Json>) -> Json {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/publishDiagnostics",
+        "params": {"uri": uri, "diagnostics": diagnostics},
+    })
+}
+
+// everything we know about a document
+struct Analysis<'a> {
+    table: Arc<RwLock<StringTable<'a>>>,
+    program: Vec<OuterExp>, // empty if the parse failed
+    refs: Vec<NameRef>,
+    diagnostics: Vec<Diagnostic<usize>>,
+}
+
+impl<'a> Analysis<'a> {
+    fn new(text: &'a str) -> Self {
+        let table = Arc::new(RwLock::new(StringTable::new()));
+        let mut diagnostics = Vec::new();
+        let mut refs = Vec::new();
+
+        let parsed = ProgramParser::new().parse(text, &mut table.write().unwrap(), Lexer::new(text));
+        let program = match parsed {
+            Ok(program) => program,
+            Err(e) => {
+                diagnostics.push(parse_error_diagnostic(e, 0));
+                Vec::new()
+            }
+        };
+
+        // a few language features are still todo!() in the translator, that should not kill the server
+        let translated = catch_unwind(AssertUnwindSafe(|| {
+            translate_program_with_refs(&program, table.clone(), &mut refs).map(|(_code, warnings)| warnings)
+        }));
+
+        let read_table = table.read().unwrap();
+        match translated {
+            Ok(Ok(warnings)) => {
+                diagnostics.extend(warnings.iter().map(|w| warning_diagnostic(w, 0, &read_table)))
+            }
+            Ok(Err(errors)) => {
+                for err in errors.iter() {
+                    error_diagnostics(err, 0, &read_table, &mut diagnostics);
+                }
+            }
+            Err(_) => diagnostics.push(Diagnostic::bug().with_message("the compiler crashed on this file")),
+        }
+        std::mem::drop(read_table);
+
+        Analysis { table, program, refs, diagnostics }
+    }
+
+    fn funcs(&self) -> impl Iterator<Item = &FuncDec> {
+        self.program.iter().filter_map(|exp| match exp {
+            OuterExp::FuncDec(func) => Some(func),
+            OuterExp::ImportFunc(_) => None,
+        })
+    }
+
+    fn find_func(&self, id: u32) -> Option<&FuncDec> {
+        self.funcs().find(|func| func.sig.name == id)
+    }
+
+    // the innermost name under the cursor
+    fn ref_at(&self, offset: usize) -> Option<&NameRef> {
+        self.refs
+            .iter()
+            .filter(|r| contains(r.span, offset))
+            .min_by_key(|r| r.span.end().to_usize() - r.span.start().to_usize())
+    }
+
+    // a def whose name is under the cursor
+    fn def_at(&self, offset: usize) -> Option<&FuncDec> {
+        let table = self.table.read().unwrap();
+        self.funcs().find(|func| contains(name_span(func, &table), offset))
+    }
+
+    fn signature(&self, func: &FuncDec) -> String {
+        let table = self.table.read().unwrap();
+        let args: Vec<&str> = func.sig.args.iter().map(|id| table.get_raw_str(*id)).collect();
+        format!("def {}({})", table.get_raw_str(func.sig.name), args.join(", "))
+    }
+}
+
+fn contains(span: Span, offset: usize) -> bool {
+    span.start().to_usize() <= offset && offset <= span.end().to_usize()
+}
+
+// the sig span starts at the name
+fn name_span(func: &FuncDec, table: &StringTable) -> Span {
+    let start = func.debug_span.start().0;
+    Span::new(start, start + table.get_raw_str(func.sig.name).len() as u32)
+}
+
+fn definition(analysis: &Analysis, text: &str, offset: usize, uri: &str) -> Json {
+    if let Some(func) = analysis.def_at(offset) {
+        return json!({"uri": uri, "range": to_range(text, name_span(func, &analysis.table.read().unwrap()))});
+    }
+
+    let Some(name_ref) = analysis.ref_at(offset) else {
+        return Json::Null;
+    };
+    let span = match name_ref.def {
+        Some(span) => span,
+        None => match analysis.find_func(name_ref.id) {
+            Some(func) => name_span(func, &analysis.table.read().unwrap()),
+            None => return Json::Null,
+        },
+    };
+    json!({"uri": uri, "range": to_range(text, span)})
+}
+
+fn hover(analysis: &Analysis, text: &str, offset: usize) -> Json {
+    let (func, span) = match (analysis.def_at(offset), analysis.ref_at(offset)) {
+        (Some(func), _) => (func, name_span(func, &analysis.table.read().unwrap())),
+        (None, Some(name_ref)) => match (name_ref.def, analysis.find_func(name_ref.id)) {
+            (None, Some(func)) => (func, name_ref.span),
+            (Some(_), _) => {
+                let name = analysis.table.read().unwrap().get_raw_str(name_ref.id);
+                return json!({
+                    "contents": {"kind": "markdown", "value": format!("```\n{}\n```\nlocal variable", name)},
+                    "range": to_range(text, name_ref.span),
+                });
+            }
+            (None, None) => return Json::Null,
+        },
+        (None, None) => return Json::Null,
+    };
+
+    let arity = match func.sig.args.len() {
+        1 => "takes 1 argument".to_string(),
+        n => format!("takes {} arguments", n),
+    };
+    json!({
+        "contents": {"kind": "markdown", "value": format!("```\n{}\n```\n{}", analysis.signature(func), arity)},
+        "range": to_range(text, span),
+    })
+}
+
+const FUNCTION_KIND: u32 = 3;
+const CONSTANT_KIND: u32 = 21;
+const SYMBOL_FUNCTION_KIND: u32 = 12;
+
+fn completion(text: &str, offset: usize) -> Json {
+    let word_start = text[..offset]
+        .rfind(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .map_or(0, |i| i + 1);
+
+    // after a : we are writing an atom, most likely for system
+    if text[..word_start].ends_with(':') {
+        let system = SYSTEM_FUNCS.iter().map(|atom| (atom, "system function"));
+        let strings = STRING_FUNCS.iter().map(|atom| (atom, "string function"));
+        let bytes = BYTES_FUNCS.iter().map(|atom| (atom, "bytes function"));
+        let math = MATH_FUNCS.iter().map(|atom| (atom, "math function"));
+        let random = RANDOM_FUNCS.iter().map(|atom| (atom, "random generator"));
+        let process = PROCESS_FUNCS.iter().map(|atom| (atom, "process handle"));
+        let stream = STREAM_FUNCS.iter().map(|atom| (atom, "stream"));
+        let items: Vec<Json> = system
+            .chain(strings)
+            .chain(bytes)
+            .chain(math)
+            .chain(random)
+            .chain(process)
+            .chain(stream)
+            .map(|(atom, detail)| json!({"label": &atom[1..], "kind": CONSTANT_KIND, "detail": detail}))
+            .collect();
+        return json!(items);
+    }
+
+    let items: Vec<Json> = lex_defs(text)
+        .into_iter()
+        .map(|(name, args)| {
+            json!({
+                "label": name,
+                "kind": FUNCTION_KIND,
+                "detail": format!("def {}({})", name, args.join(", ")),
+            })
+        })
+        .collect();
+    json!(items)
+}
+
+// while typing the file usually does not parse, so the defs are found from the tokens
+fn lex_defs(text: &str) -> Vec<(&str, Vec<&str>)> {
+    let tokens: Vec<(usize, LexTag, usize)> = Lexer::new(text).map_while(Result::ok).collect();
+    let mut defs = Vec::new();
+    for (i, (_, tag, _)) in tokens.iter().enumerate() {
+        let (LexTag::FuncDec, Some((start, LexTag::Name, end))) = (tag, tokens.get(i + 1)) else {
+            continue;
+        };
+
+        let mut args = Vec::new();
+        if let Some((_, LexTag::OpenParen, _)) = tokens.get(i + 2) {
+            for (start, tag, end) in tokens[i + 3..].iter() {
+                match tag {
+                    LexTag::Name => args.push(&text[*start..*end]),
+                    LexTag::Comma => {}
+                    _ => break,
+                }
+            }
+        }
+        defs.push((&text[*start..*end], args));
+    }
+    defs
+}
+
+fn document_symbols(analysis: &Analysis, text: &str) -> Json {
+    let table = analysis.table.read().unwrap();
+    let symbols: Vec<Json> = analysis
+        .funcs()
+        .map(|func| {
+            let whole = Span::new(func.debug_span.start(), func.body.debug_span.end());
+            json!({
+                "name": table.get_raw_str(func.sig.name),
+                "detail": analysis.signature(func),
+                "kind": SYMBOL_FUNCTION_KIND,
+                "range": to_range(text, whole),
+                "selectionRange": to_range(text, name_span(func, &table)),
+            })
+        })
+        .collect();
+    json!(symbols)
+}
+
+fn to_lsp_diagnostic(text: &str, diagnostic: &Diagnostic<usize>) -> Json {
+    let primary = diagnostic.labels.iter().find(|l| l.style == LabelStyle::Primary);
+    let range = match primary {
+        Some(label) => to_range(text, Span::new(label.range.start as u32, label.range.end as u32)),
+        None => to_range(text, Span::new(0, 0)),
+    };
+
+    let mut message = diagnostic.message.clone();
+    for label in diagnostic.labels.iter().filter(|l| !l.message.is_empty()) {
+        message.push('\n');
+        message.push_str(&label.message);
+    }
+    for note in diagnostic.notes.iter() {
+        message.push('\n');
+        message.push_str(note);
+    }
+
+    let severity = match diagnostic.severity {
+        Severity::Bug | Severity::Error => 1,
+        Severity::Warning => 2,
+        Severity::Note => 3,
+        Severity::Help => 4,
+    };
+    json!({"range": range, "severity": severity, "source": "faeyne", "message": message})
+}
+
+fn to_range(text: &str, span: Span) -> Json {
+    json!({
+        "start": offset_to_position(text, span.start().to_usize()),
+        "end": offset_to_position(text, span.end().to_usize()),
+    })
+}
+
+// lsp positions count utf16 code units
+fn offset_to_position(text: &str, offset: usize) -> Json {
+    let offset = offset.min(text.len());
+    let line_start = text[..offset].rfind('\n').map_or(0, |i| i + 1);
+    let line = text[..line_start].matches('\n').count();
+    let character: usize = text[line_start..offset].chars().map(char::len_utf16).sum();
+    json!({"line": line, "character": character})
+}
+
+fn position_to_offset(text: &str, line: usize, character: usize) -> usize {
+    let line_start = match line {
+        0 => 0,
+        _ => match text.match_indices('\n').nth(line - 1) {
+            Some((i, _)) => i + 1,
+            None => return text.len(),
+        },
+    };
+
+    let mut units = 0;
+    for (i, c) in text[line_start..].char_indices() {
+        if units >= character || c == '\n' {
+            return line_start + i;
+        }
+        units += c.len_utf16();
+    }
+    text.len()
+}
+
+#[cfg(test)]
+fn open_doc(server: &mut Server, text: &str) -> Json {
+    let mut replies = server.handle(&json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/didOpen",
+        "params": {"textDocument": {"uri": "file:///test.fay", "languageId": "faeyne", "version": 1, "text": text}},
+    }));
+    replies.remove(0)
+}
+
+#[cfg(test)]
+fn request(server: &mut Server, method: &str, line: u32, character: u32) -> Json {
+    let mut replies = server.handle(&json!({
+        "jsonrpc": "2.0",
+        "id": 7,
+        "method": method,
+        "params": {"textDocument": {"uri": "file:///test.fay"}, "position": {"line": line, "character": character}},
+    }));
+    assert_eq!(replies.len(), 1);
+    replies.remove(0)["result"].take()
+}
+
+#[test]
+fn test_positions_count_utf16() {
+    let text = "ab\nü😀x\n";
+    assert_eq!(offset_to_position(text, 0), json!({"line": 0, "character": 0}));
+    assert_eq!(offset_to_position(text, 3), json!({"line": 1, "character": 0}));
+    let x = text.find('x').unwrap();
+    assert_eq!(offset_to_position(text, x), json!({"line": 1, "character": 3}));
+    assert_eq!(position_to_offset(text, 1, 3), x);
+    assert_eq!(position_to_offset(text, 1, 100), text.len() - 1);
+    assert_eq!(position_to_offset(text, 9, 0), text.len());
+}
+
+#[test]
+fn test_stdio_session() {
+    let messages = [
+        json!({"jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {}}),
+        json!({"jsonrpc": "2.0", "id": 2, "method": "shutdown"}),
+        json!({"jsonrpc": "2.0", "method": "exit"}),
+        json!({"jsonrpc": "2.0", "id": 3, "method": "never_read"}),
+    ];
+    let mut input = Vec::new();
+    for m in messages.iter() {
+        write_message(&mut input, m).unwrap();
+    }
+
+    let mut output = Vec::new();
+    run(io::Cursor::new(input), &mut output).unwrap();
+
+    let mut output = io::Cursor::new(output);
+    let init = read_message(&mut output).unwrap().unwrap();
+    assert_eq!(init["id"], 1);
+    assert_eq!(init["result"]["capabilities"]["definitionProvider"], true);
+    let shutdown = read_message(&mut output).unwrap().unwrap();
+    assert_eq!(shutdown, json!({"jsonrpc": "2.0", "id": 2, "result": null}));
+    assert!(read_message(&mut output).unwrap().is_none());
+}
+
+#[test]
+fn test_diagnostics() {
+    let mut server = Server::default();
+
+    let reply = open_doc(&mut server, "def main(system) {\n    valeu = 1;\n    value\n}\n");
+    assert_eq!(reply["method"], "textDocument/publishDiagnostics");
+    let diagnostics = reply["params"]["diagnostics"].as_array().unwrap();
+    assert_eq!(diagnostics.len(), 2);
+    assert_eq!(diagnostics[0]["severity"], 1);
+    assert_eq!(diagnostics[0]["range"]["start"], json!({"line": 2, "character": 4}));
+    assert_eq!(diagnostics[1]["severity"], 4);
+    assert!(diagnostics[1]["message"].as_str().unwrap().contains("valeu"));
+
+    let reply = open_doc(&mut server, "def main(system) {\n    x = 1;\n    0\n}\n");
+    let diagnostics = reply["params"]["diagnostics"].as_array().unwrap();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0]["severity"], 2);
+    assert_eq!(diagnostics[0]["range"]["start"], json!({"line": 1, "character": 4}));
+
+    let reply = open_doc(&mut server, "def main(system) {\n    x = ;\n}\n");
+    let diagnostics = reply["params"]["diagnostics"].as_array().unwrap();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0]["range"]["start"], json!({"line": 1, "character": 8}));
+}
+
+#[test]
+fn test_definition_and_hover() {
+    let mut server = Server::default();
+    let text = "def helper(a, b) {\n    c = a;\n    fn() -> {c + b}\n}\n\ndef main(system) {\n    helper(1, 2)\n}\n";
+    open_doc(&mut server, text);
+
+    // local variable used inside a lambda
+    let def = request(&mut server, "textDocument/definition", 2, 14);
+    assert_eq!(def["uri"], "file:///test.fay");
+    assert_eq!(def["range"]["start"], json!({"line": 1, "character": 4}));
+
+    // args point at the signature
+    let def = request(&mut server, "textDocument/definition", 1, 8);
+    assert_eq!(def["range"]["start"], json!({"line": 0, "character": 4}));
+
+    // globals point at the def name
+    let def = request(&mut server, "textDocument/definition", 6, 6);
+    assert_eq!(def["range"], json!({"start": {"line": 0, "character": 4}, "end": {"line": 0, "character": 10}}));
+
+    assert_eq!(request(&mut server, "textDocument/definition", 4, 0), Json::Null);
+
+    let hover = request(&mut server, "textDocument/hover", 6, 6);
+    let value = hover["contents"]["value"].as_str().unwrap();
+    assert!(value.contains("def helper(a, b)"));
+    assert!(value.contains("takes 2 arguments"));
+
+    let hover = request(&mut server, "textDocument/hover", 5, 5);
+    assert!(hover["contents"]["value"].as_str().unwrap().contains("takes 1 argument"));
+}
+
+#[test]
+fn test_completion_and_symbols() {
+    let mut server = Server::default();
+    let text = "def helper(a, b) {a}\n\ndef main(system) {\n    system(:pr\n";
+    open_doc(&mut server, text);
+
+    let atoms = request(&mut server, "textDocument/completion", 3, 14);
+    let labels: Vec<&str> = atoms.as_array().unwrap().iter().map(|i| i["label"].as_str().unwrap()).collect();
+    assert!(labels.contains(&"println"));
+
+    // the file does not parse but the defs are still offered
+    let globals = request(&mut server, "textDocument/completion", 3, 5);
+    let globals = globals.as_array().unwrap();
+    assert_eq!(globals.len(), 2);
+    assert_eq!(globals[0]["label"], "helper");
+    assert_eq!(globals[0]["detail"], "def helper(a, b)");
+
+    open_doc(&mut server, "def helper(a, b) {a}\n\ndef main(system) {\n    helper(1, 2)\n}\n");
+    let symbols = server.handle(&json!({
+        "jsonrpc": "2.0",
+        "id": 9,
+        "method": "textDocument/documentSymbol",
+        "params": {"textDocument": {"uri": "file:///test.fay"}},
+    }))[0]["result"].take();
+    let symbols = symbols.as_array().unwrap();
+    assert_eq!(symbols.len(), 2);
+    assert_eq!(symbols[1]["name"], "main");
+    assert_eq!(symbols[1]["range"]["end"], json!({"line": 4, "character": 1}));
+    assert_eq!(symbols[1]["selectionRange"]["start"], json!({"line": 2, "character": 4}));
+}
@@ -1,11 +1,16 @@
-use clap::{Arg, Command};
+use clap::{Arg, ArgAction, Command};
 use faeyne_lang::value::Value;
 use faeyne_lang::system::system;
-use faeyne_lang::reporting::report_err_list;
+use faeyne_lang::scheduler::Mode;
+use faeyne_lang::reporting::{report_err_list, report_parse_error, report_warnings};
+use ast::ast::StringTable;
+use ast::format::format_source;
 use std::fs;
 use std::process;
 
-use faeyne_lang::translate::compile_source_to_code;
+use faeyne_lang::translate::try_compile_source;
+use faeyne_lang::runtime::Code;
+use faeyne_lang::testing::{run_tests, TestSummary};
 
 fn main() {
     // Define the command-line argument structure using clap
@@ -22,8 +27,50 @@ fn main() {
             .long("repeat")
             .help("Number of times to repeat the execution")
             .default_value("1"))
+        .arg(Arg::new("threads")
+            .long("threads")
+            .help("Run processes on this many worker threads instead of taking turns on one")
+            .default_value("0"))
+        .arg(Arg::new("deny-warnings")
+            .long("deny-warnings")
+            .help("Fail instead of running when the compiler reports warnings")
+            .action(ArgAction::SetTrue))
+        .args_conflicts_with_subcommands(true)
+        .subcommand(Command::new("fmt")
+            .about("Rewrites scripts in the canonical format")
+            .arg(Arg::new("files")
+                .help("The Faeyne_lang script files to format")
+                .required(true)
+                .num_args(1..))
+            .arg(Arg::new("check")
+                .long("check")
+                .help("Only list the files that are not formatted, exiting with an error if there are any")
+                .action(ArgAction::SetTrue)))
+        .subcommand(Command::new("test")
+            .about("Runs the test_* functions in the given scripts")
+            .arg(Arg::new("files")
+                .help("The Faeyne_lang script files to test")
+                .required(true)
+                .num_args(1..))
+            .arg(Arg::new("filter")
+                .short('f')
+                .long("filter")
+                .help("Only run tests whose name contains this")))
         .get_matches();
 
+    if let Some(("fmt", fmt_matches)) = matches.subcommand() {
+        let files = fmt_matches.get_many::<String>("files").unwrap();
+        let failed = format_files(files, fmt_matches.get_flag("check"));
+        process::exit(if failed { 1 } else { 0 });
+    }
+
+    if let Some(("test", test_matches)) = matches.subcommand() {
+        let files = test_matches.get_many::<String>("files").unwrap();
+        let filter = test_matches.get_one::<String>("filter").map(|s| s.as_str());
+        let failed = test_files(files, filter);
+        process::exit(if failed { 1 } else { 0 });
+    }
+
     // Get the file path and repeat count
     let file_path = matches.get_one::<String>("file").unwrap();
     let repeat_count: usize = matches.get_one::<String>("repeat")
@@ -33,6 +80,15 @@ fn main() {
             eprintln!("Invalid repeat count");
             process::exit(1);
         });
+    let mode = match matches.get_one::<String>("threads").unwrap().parse() {
+        Ok(0) => Mode::RoundRobin,
+        Ok(n) => Mode::Threads(n),
+        Err(_) => {
+            eprintln!("Invalid thread count");
+            process::exit(1);
+        }
+    };
+    let deny_warnings = matches.get_flag("deny-warnings");
 
     // Read the source code from the file
     let source_code = match fs::read_to_string(file_path) {
@@ -44,13 +100,100 @@ fn main() {
     };
 
     // Compile the source code
-    let code = compile_source_to_code(&source_code);
+    let Some(code) = compile(&source_code, deny_warnings) else {
+        process::exit(1);
+    };
 
     // Run the code multiple times based on the repeat count
     for _ in 0..repeat_count {
-        match code.run("main", vec![Value::StaticFunc(system)]) {
+        match code.run_processes("main", vec![Value::StaticFunc(system)], mode, |_| ()) {
             Ok(()) => {},
             Err(e) => report_err_list(&e, &source_code, &code.table.try_read().unwrap()),
         }
     }
 }
+
+// compiles and shows the warnings on stderr, None when it failed (or warnings are denied)
+fn compile(source_code: &str, deny_warnings: bool) -> Option<Code<'_>> {
+    let (code, warnings) = try_compile_source(source_code)?;
+    if !warnings.is_empty() {
+        report_warnings(&warnings, source_code, &code.table.try_read().unwrap());
+        if deny_warnings {
+            eprintln!("Warnings are denied");
+            return None;
+        }
+    }
+    Some(code)
+}
+
+// returns true if anything failed or (with check) is not formatted
+fn format_files<'a>(files: impl Iterator<Item = &'a String>, check: bool) -> bool {
+    let mut failed = false;
+    for file_path in files {
+        let source_code = match fs::read_to_string(file_path) {
+            Ok(content) => content,
+            Err(_) => {
+                eprintln!("Failed to read file: {}", file_path);
+                failed = true;
+                continue;
+            }
+        };
+
+        let formatted = match format_source(&source_code) {
+            Ok(formatted) => formatted,
+            Err(e) => {
+                report_parse_error(e, &source_code, &StringTable::new());
+                failed = true;
+                continue;
+            }
+        };
+
+        if formatted == source_code {
+            continue;
+        }
+
+        if check {
+            println!("{}", file_path);
+            failed = true;
+        } else if fs::write(file_path, formatted).is_err() {
+            eprintln!("Failed to write file: {}", file_path);
+            failed = true;
+        }
+    }
+    failed
+}
+
+// returns true if any file failed to compile or any test failed
+fn test_files<'a>(files: impl Iterator<Item = &'a String>, filter: Option<&str>) -> bool {
+    let mut summary = TestSummary::default();
+    let mut broken_files = 0;
+    for file_path in files {
+        let source_code = match fs::read_to_string(file_path) {
+            Ok(content) => content,
+            Err(_) => {
+                eprintln!("Failed to read file: {}", file_path);
+                broken_files += 1;
+                continue;
+            }
+        };
+
+        println!("\n{}", file_path);
+        match compile(&source_code, false) {
+            Some(code) => summary.add(run_tests(&code, &source_code, filter)),
+            None => broken_files += 1,
+        }
+    }
+
+    let failed = summary.failed > 0 || broken_files > 0;
+    println!(
+        "\ntest result: {}. {} passed; {} failed; {} filtered out",
+        if failed { "FAILED" } else { "ok" },
+        summary.passed,
+        summary.failed,
+        summary.filtered,
+    );
+    if broken_files > 0 {
+        println!("{} files could not be compiled", broken_files);
+    }
+    failed
+}
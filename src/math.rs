@@ -0,0 +1,262 @@
+// The math module, reached with system(:math)(:name).
+// Ints are promoted to floats the same way the arithmetic ops do it:
+// functions that can stay in ints (abs, min, max) only give a float when a float goes in.
+
+use ast::ast::StringTable;
+use ast::get_id;
+use ast::id::*;
+
+use crate::basic_ops::{pop_args, push};
+use crate::reporting::*;
+use crate::stack::ValueStack;
+use crate::value::Value;
+
+//every atom the module answers to (used for editor completion)
+pub const MATH_FUNCS: &[&str] = &[
+    ":sqrt", ":exp", ":ln", ":log", ":sin", ":cos", ":tan", ":asin", ":acos", ":atan", ":atan2", ":floor", ":ceil",
+    ":round", ":trunc", ":abs", ":min", ":max", ":gcd", ":int", ":float", ":pi", ":e",
+];
+
+pub fn math_module<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+    let atom = stack.pop_atom().ok_or_else(|| arg_error(1, 0))?;
+    stack.pop_terminator().ok_or_else(sig_error)?;
+
+    let func = match atom {
+        // constants are given directly
+        get_id!(":pi") => return push(stack, Value::Float(std::f64::consts::PI)),
+        get_id!(":e") => return push(stack, Value::Float(std::f64::consts::E)),
+
+        get_id!(":sqrt") => sqrt_fn,
+        get_id!(":exp") => exp_fn,
+        get_id!(":ln") => ln_fn,
+        get_id!(":log") => log_fn,
+        get_id!(":sin") => sin_fn,
+        get_id!(":cos") => cos_fn,
+        get_id!(":tan") => tan_fn,
+        get_id!(":asin") => asin_fn,
+        get_id!(":acos") => acos_fn,
+        get_id!(":atan") => atan_fn,
+        get_id!(":atan2") => atan2_fn,
+        get_id!(":floor") => floor_fn,
+        get_id!(":ceil") => ceil_fn,
+        get_id!(":round") => round_fn,
+        get_id!(":trunc") => trunc_fn,
+        get_id!(":abs") => abs_fn,
+        get_id!(":min") => min_fn,
+        get_id!(":max") => max_fn,
+        get_id!(":gcd") => gcd_fn,
+        get_id!(":int") => int_fn,
+        get_id!(":float") => float_fn,
+        _ => return Err(sig_error()),
+    };
+
+    push(stack, Value::StaticFunc(func))
+}
+
+fn to_float(value: &Value) -> Result<f64, ErrList> {
+    match value {
+        Value::Int(i) => Ok(*i as f64),
+        Value::Float(f) => Ok(*f),
+        _ => Err(sig_error()),
+    }
+}
+
+fn unary(stack: &mut ValueStack, f: fn(f64) -> f64) -> Result<(), ErrList> {
+    let [x] = pop_args(stack)?;
+    let x = to_float(&x)?;
+    push(stack, Value::Float(f(x)))
+}
+
+fn binary(stack: &mut ValueStack, f: fn(f64, f64) -> f64) -> Result<(), ErrList> {
+    let [a, b] = pop_args(stack)?;
+    let (a, b) = (to_float(&a)?, to_float(&b)?);
+    push(stack, Value::Float(f(a, b)))
+}
+
+// rounding keeps ints as they are
+fn rounding(stack: &mut ValueStack, f: fn(f64) -> f64) -> Result<(), ErrList> {
+    match pop_args(stack)? {
+        [Value::Int(i)] => push(stack, Value::Int(i)),
+        [Value::Float(x)] => push(stack, Value::Float(f(x))),
+        _ => Err(sig_error()),
+    }
+}
+
+pub fn sqrt_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+    unary(stack, f64::sqrt)
+}
+
+pub fn exp_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+    unary(stack, f64::exp)
+}
+
+pub fn ln_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+    unary(stack, f64::ln)
+}
+
+// log(x, base)
+pub fn log_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+    binary(stack, f64::log)
+}
+
+pub fn sin_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+    unary(stack, f64::sin)
+}
+
+pub fn cos_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+    unary(stack, f64::cos)
+}
+
+pub fn tan_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+    unary(stack, f64::tan)
+}
+
+pub fn asin_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+    unary(stack, f64::asin)
+}
+
+pub fn acos_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+    unary(stack, f64::acos)
+}
+
+pub fn atan_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+    unary(stack, f64::atan)
+}
+
+// atan2(y, x)
+pub fn atan2_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+    binary(stack, f64::atan2)
+}
+
+pub fn floor_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+    rounding(stack, f64::floor)
+}
+
+pub fn ceil_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+    rounding(stack, f64::ceil)
+}
+
+pub fn round_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+    rounding(stack, f64::round)
+}
+
+pub fn trunc_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+    rounding(stack, f64::trunc)
+}
+
+pub fn abs_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+    match pop_args(stack)? {
+        [Value::Int(i)] => match i.checked_abs() {
+            Some(i) => push(stack, Value::Int(i)),
+            None => Err(sig_error()),
+        },
+        [Value::Float(x)] => push(stack, Value::Float(x.abs())),
+        _ => Err(sig_error()),
+    }
+}
+
+fn min_max(stack: &mut ValueStack, pick_first: fn(f64, f64) -> bool) -> Result<(), ErrList> {
+    let [a, b] = pop_args(stack)?;
+    let first = pick_first(to_float(&a)?, to_float(&b)?);
+    let ans = match (a, b) {
+        (Value::Int(a), Value::Int(b)) => Value::Int(if first { a } else { b }),
+        (a, b) => Value::Float(to_float(if first { &a } else { &b })?),
+    };
+    push(stack, ans)
+}
+
+pub fn min_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+    min_max(stack, |a, b| a <= b)
+}
+
+pub fn max_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+    min_max(stack, |a, b| a >= b)
+}
+
+pub fn gcd_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+    let [Value::Int(a), Value::Int(b)] = pop_args(stack)? else {
+        return Err(sig_error());
+    };
+    let (mut a, mut b) = (a.unsigned_abs(), b.unsigned_abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    match i64::try_from(a) {
+        Ok(a) => push(stack, Value::Int(a)),
+        Err(_) => Err(sig_error()),
+    }
+}
+
+// int(x) truncates floats, nan and floats out of range give :err
+pub fn int_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+    match pop_args(stack)? {
+        [Value::Int(i)] => push(stack, Value::Int(i)),
+        [Value::Float(x)] if (i64::MIN as f64..i64::MAX as f64).contains(&x) => {
+            push(stack, Value::Int(x as i64))
+        }
+        [Value::Float(_)] => push(stack, Value::Atom(get_id!(":err"))),
+        _ => Err(sig_error()),
+    }
+}
+
+pub fn float_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+    let [x] = pop_args(stack)?;
+    let x = to_float(&x)?;
+    push(stack, Value::Float(x))
+}
+
+#[cfg(test)]
+use crate::basic_ops::call_native;
+
+#[test]
+fn test_constants() {
+    let constant = |name| {
+        let mut stack = ValueStack::new();
+        stack.push_terminator().unwrap();
+        stack.push_atom(name).unwrap();
+        math_module(&mut stack, &StringTable::new()).unwrap();
+        stack.pop_value().unwrap()
+    };
+    assert_eq!(constant(get_id!(":pi")), Value::Float(std::f64::consts::PI));
+    assert_eq!(constant(get_id!(":e")), Value::Float(std::f64::consts::E));
+}
+
+#[test]
+fn test_floats() {
+    use std::f64::consts::{E, PI};
+
+    assert_eq!(call_native(sqrt_fn, [Value::Int(16)]).unwrap(), Value::Float(4.0));
+    assert_eq!(call_native(exp_fn, [Value::Int(0)]).unwrap(), Value::Float(1.0));
+    assert_eq!(call_native(ln_fn, [Value::Float(E)]).unwrap(), Value::Float(1.0));
+    assert_eq!(call_native(log_fn, [Value::Int(8), Value::Int(2)]).unwrap(), Value::Float(3.0));
+    assert_eq!(call_native(sin_fn, [Value::Int(0)]).unwrap(), Value::Float(0.0));
+    assert_eq!(call_native(cos_fn, [Value::Float(PI)]).unwrap(), Value::Float(-1.0));
+    assert_eq!(call_native(atan2_fn, [Value::Int(1), Value::Int(1)]).unwrap(), Value::Float(PI / 4.0));
+    assert_eq!(call_native(float_fn, [Value::Int(3)]).unwrap(), Value::Float(3.0));
+}
+
+#[test]
+fn test_rounding() {
+    assert_eq!(call_native(floor_fn, [Value::Float(2.7)]).unwrap(), Value::Float(2.0));
+    assert_eq!(call_native(ceil_fn, [Value::Float(-2.7)]).unwrap(), Value::Float(-2.0));
+    assert_eq!(call_native(round_fn, [Value::Float(2.5)]).unwrap(), Value::Float(3.0));
+    assert_eq!(call_native(trunc_fn, [Value::Float(-2.7)]).unwrap(), Value::Float(-2.0));
+    assert_eq!(call_native(floor_fn, [Value::Int(3)]).unwrap(), Value::Int(3));
+}
+
+#[test]
+fn test_integers() {
+    assert_eq!(call_native(abs_fn, [Value::Int(-3)]).unwrap(), Value::Int(3));
+    assert_eq!(call_native(abs_fn, [Value::Float(-1.5)]).unwrap(), Value::Float(1.5));
+    assert!(call_native(abs_fn, [Value::Int(i64::MIN)]).is_err());
+    assert_eq!(call_native(min_fn, [Value::Int(3), Value::Int(2)]).unwrap(), Value::Int(2));
+    assert_eq!(call_native(max_fn, [Value::Int(3), Value::Float(2.5)]).unwrap(), Value::Float(3.0));
+    assert_eq!(call_native(min_fn, [Value::Int(1), Value::Float(2.5)]).unwrap(), Value::Float(1.0));
+    assert_eq!(call_native(gcd_fn, [Value::Int(12), Value::Int(-18)]).unwrap(), Value::Int(6));
+    assert!(call_native(gcd_fn, [Value::Int(i64::MIN), Value::Int(0)]).is_err());
+    assert!(call_native(gcd_fn, [Value::Float(1.5), Value::Int(2)]).is_err());
+    assert_eq!(call_native(int_fn, [Value::Float(-3.9)]).unwrap(), Value::Int(-3));
+    assert_eq!(call_native(int_fn, [Value::Float(f64::NAN)]).unwrap(), Value::Atom(get_id!(":err")));
+    assert_eq!(call_native(int_fn, [Value::Float(9223372036854775808.0)]).unwrap(), Value::Atom(get_id!(":err")));
+    assert_eq!(call_native(int_fn, [Value::Float(-9223372036854775808.0)]).unwrap(), Value::Int(i64::MIN));
+}
@@ -0,0 +1,244 @@
+// Parsing and formatting numbers, reached with system(:parse_int) and friends.
+// Parsing ignores surrounding whitespace (so lines read from files work) and gives :err on failure.
+
+use std::iter::Peekable;
+use std::str::Chars;
+use std::sync::Arc;
+
+use ast::ast::StringTable;
+use ast::get_id;
+use ast::id::*;
+
+use crate::basic_ops::{pop_args, push, to_string_runtime};
+use crate::reporting::*;
+use crate::stack::ValueStack;
+use crate::value::Value;
+
+fn push_str(stack: &mut ValueStack, s: String) -> Result<(), ErrList> {
+    stack.push_string(Arc::new(s.into())).map_err(|_| overflow_error())
+}
+
+fn push_parsed<T>(stack: &mut ValueStack, parsed: Option<T>, wrap: fn(T) -> Value<'static>) -> Result<(), ErrList> {
+    match parsed {
+        Some(x) => push(stack, wrap(x)),
+        None => push(stack, Value::Atom(get_id!(":err"))),
+    }
+}
+
+fn check_radix(radix: i64) -> Result<u32, ErrList> {
+    match radix {
+        2..=36 => Ok(radix as u32),
+        _ => Err(sig_error()),
+    }
+}
+
+pub fn parse_int_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+    let [Value::String(s)] = pop_args(stack)? else {
+        return Err(sig_error());
+    };
+    push_parsed(stack, s.trim().parse().ok(), Value::Int)
+}
+
+pub fn parse_float_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+    let [Value::String(s)] = pop_args(stack)? else {
+        return Err(sig_error());
+    };
+    push_parsed(stack, s.trim().parse().ok(), Value::Float)
+}
+
+// parse_radix(s, radix) reads an int written in base 2 to 36
+pub fn parse_radix_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+    let [Value::String(s), Value::Int(radix)] = pop_args(stack)? else {
+        return Err(sig_error());
+    };
+    let radix = check_radix(radix)?;
+    push_parsed(stack, i64::from_str_radix(s.trim(), radix).ok(), Value::Int)
+}
+
+// to_radix(n, radix) writes an int in base 2 to 36 using lowercase digits
+pub fn to_radix_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+    let [Value::Int(n), Value::Int(radix)] = pop_args(stack)? else {
+        return Err(sig_error());
+    };
+    let radix = check_radix(radix)?;
+
+    let mut digits = Vec::new();
+    let mut rest = n.unsigned_abs();
+    loop {
+        digits.push(char::from_digit((rest % radix as u64) as u32, radix).unwrap());
+        rest /= radix as u64;
+        if rest == 0 {
+            break;
+        }
+    }
+    if n < 0 {
+        digits.push('-');
+    }
+    push_str(stack, digits.iter().rev().collect())
+}
+
+// a format spec looks like [[fill]align][0][width][.precision]
+// align is one of < > ^ and a leading 0 pads numbers with zeros after the sign
+#[derive(Debug, PartialEq)]
+struct FormatSpec {
+    fill: char,
+    align: Option<char>,
+    zero: bool,
+    width: usize,
+    precision: Option<usize>,
+}
+
+// Ok(None) when there are no digits, Err when they do not fit a u16 (the same limit rust's format! has)
+fn read_number(chars: &mut Peekable<Chars>) -> Result<Option<usize>, ()> {
+    let mut digits = String::new();
+    while let Some(d) = chars.next_if(|c| c.is_ascii_digit()) {
+        digits.push(d);
+    }
+    match digits.is_empty() {
+        true => Ok(None),
+        false => digits.parse::<u16>().map(|n| Some(n.into())).map_err(|_| ()),
+    }
+}
+
+fn parse_spec(spec: &str) -> Option<FormatSpec> {
+    let is_align = |c: char| matches!(c, '<' | '>' | '^');
+    let mut chars = spec.chars().peekable();
+    let mut ans = FormatSpec { fill: ' ', align: None, zero: false, width: 0, precision: None };
+
+    let mut lookahead = spec.chars();
+    match (lookahead.next(), lookahead.next()) {
+        (Some(fill), Some(align)) if is_align(align) => {
+            ans.fill = fill;
+            ans.align = Some(align);
+            chars.nth(1);
+        }
+        (Some(align), _) if is_align(align) => {
+            ans.align = Some(align);
+            chars.next();
+        }
+        _ => {}
+    }
+
+    if chars.next_if_eq(&'0').is_some() {
+        ans.zero = true;
+    }
+
+    ans.width = read_number(&mut chars).ok()?.unwrap_or(0);
+    if chars.next_if_eq(&'.').is_some() {
+        ans.precision = Some(read_number(&mut chars).ok()??);
+    }
+
+    match chars.next() {
+        None => Some(ans),
+        Some(_) => None,
+    }
+}
+
+fn format_value(value: &Value, spec: &FormatSpec, table: &StringTable) -> String {
+    let numeric = matches!(value, Value::Int(_) | Value::Float(_));
+    let text = match (value, spec.precision) {
+        //written out directly since going through a float loses digits past 2^53
+        (Value::Int(i), Some(0)) => i.to_string(),
+        (Value::Int(i), Some(p)) => format!("{}.{}", i, "0".repeat(p)),
+        (Value::Float(f), Some(p)) => format!("{:.*}", p, f),
+        (_, Some(p)) if !numeric => to_string_runtime(value, table).chars().take(p).collect(),
+        _ => to_string_runtime(value, table),
+    };
+
+    let len = text.chars().count();
+    if len >= spec.width {
+        return text;
+    }
+    let pad = spec.width - len;
+
+    if spec.zero && numeric && spec.align.is_none() {
+        let (sign, digits) = match text.strip_prefix('-') {
+            Some(digits) => ("-", digits),
+            None => ("", text.as_str()),
+        };
+        return format!("{}{}{}", sign, "0".repeat(pad), digits);
+    }
+
+    let fill = |n: usize| spec.fill.to_string().repeat(n);
+    match spec.align.unwrap_or(if numeric { '>' } else { '<' }) {
+        '<' => text + &fill(pad),
+        '>' => fill(pad) + &text,
+        _ => fill(pad / 2) + &text + &fill(pad - pad / 2),
+    }
+}
+
+// format(value, spec) renders any value like to_string would and then applies the spec
+pub fn format_fn<'code>(stack: &mut ValueStack<'code>, table: &StringTable<'code>) -> Result<(), ErrList> {
+    let [value, Value::String(spec)] = pop_args(stack)? else {
+        return Err(sig_error());
+    };
+    let spec = parse_spec(&spec).ok_or_else(sig_error)?;
+    push_str(stack, format_value(&value, &spec, table))
+}
+
+#[test]
+fn test_parse_spec() {
+    assert_eq!(parse_spec(""), Some(FormatSpec { fill: ' ', align: None, zero: false, width: 0, precision: None }));
+    assert_eq!(parse_spec("*^10.2"), Some(FormatSpec { fill: '*', align: Some('^'), zero: false, width: 10, precision: Some(2) }));
+    assert_eq!(parse_spec("08"), Some(FormatSpec { fill: ' ', align: None, zero: true, width: 8, precision: None }));
+    assert_eq!(parse_spec("<<3"), Some(FormatSpec { fill: '<', align: Some('<'), zero: false, width: 3, precision: None }));
+    assert_eq!(parse_spec(".3"), Some(FormatSpec { fill: ' ', align: None, zero: false, width: 0, precision: Some(3) }));
+    assert_eq!(parse_spec("5."), None);
+    assert_eq!(parse_spec("5x"), None);
+    assert_eq!(parse_spec("65535").map(|s| s.width), Some(65535));
+    assert_eq!(parse_spec("65536"), None);
+    assert_eq!(parse_spec("99999999999999999999999"), None);
+    assert_eq!(parse_spec(".99999999999999999999999"), None);
+}
+
+#[cfg(test)]
+use crate::basic_ops::{call_native, text};
+
+#[test]
+fn test_format_ints() {
+    let format = |v: Value<'static>, spec: &str| call_native(format_fn, [v, text(spec)]);
+    assert_eq!(format(Value::Int(9007199254740993), ".2").unwrap(), text("9007199254740993.00"));
+    assert_eq!(format(Value::Int(i64::MIN), ".1").unwrap(), text("-9223372036854775808.0"));
+    assert_eq!(format(Value::Int(-5), "06.1").unwrap(), text("-005.0"));
+    assert_eq!(format(Value::Int(7), ".0").unwrap(), text("7"));
+    assert!(format(Value::Int(7), "99999999999999999999").is_err());
+}
+
+#[cfg(test)]
+fn err() -> Value<'static> {
+    Value::Atom(get_id!(":err"))
+}
+
+#[test]
+fn test_parse() {
+    assert_eq!(call_native(parse_int_fn, [text(" 42\n")]).unwrap(), Value::Int(42));
+    assert_eq!(call_native(parse_int_fn, [text("-7")]).unwrap(), Value::Int(-7));
+    assert_eq!(call_native(parse_int_fn, [text("4.5")]).unwrap(), err());
+    assert_eq!(call_native(parse_float_fn, [text("2.5")]).unwrap(), Value::Float(2.5));
+    assert_eq!(call_native(parse_float_fn, [text("3")]).unwrap(), Value::Float(3.0));
+    assert_eq!(call_native(parse_float_fn, [text("x")]).unwrap(), err());
+}
+
+#[test]
+fn test_radix() {
+    assert_eq!(call_native(parse_radix_fn, [text("ff"), Value::Int(16)]).unwrap(), Value::Int(255));
+    assert_eq!(call_native(parse_radix_fn, [text("-101"), Value::Int(2)]).unwrap(), Value::Int(-5));
+    assert_eq!(call_native(parse_radix_fn, [text("2"), Value::Int(2)]).unwrap(), err());
+    assert!(call_native(parse_radix_fn, [text("1"), Value::Int(37)]).is_err());
+    assert_eq!(call_native(to_radix_fn, [Value::Int(255), Value::Int(16)]).unwrap(), text("ff"));
+    assert_eq!(call_native(to_radix_fn, [Value::Int(-5), Value::Int(2)]).unwrap(), text("-101"));
+    assert_eq!(call_native(to_radix_fn, [Value::Int(0), Value::Int(36)]).unwrap(), text("0"));
+}
+
+#[test]
+fn test_format() {
+    let format = |v: Value<'static>, spec: &str| call_native(format_fn, [v, text(spec)]).unwrap();
+    assert_eq!(format(Value::Float(1.23456), ".2"), text("1.23"));
+    assert_eq!(format(Value::Int(42), "5"), text("   42"));
+    assert_eq!(format(Value::Int(-42), "06"), text("-00042"));
+    assert_eq!(format(text("ab"), "4"), text("ab  "));
+    assert_eq!(format(text("ab"), "*^6"), text("**ab**"));
+    assert_eq!(format(text("hello"), ".3"), text("hel"));
+    assert_eq!(format(err(), ">5"), text(" :err"));
+    assert_eq!(format(Value::Bool(true), ""), text("true"));
+}
@@ -0,0 +1,212 @@
+// Running other programs, reached with system(:run) and system(:start_process).
+// A finished program gives a map with :status :stdout and :stderr,
+// a non zero exit gives [:err, :exit_status, that_map] so the output is not lost
+// and failing to start gives the same [:err, reason] as the file functions.
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, ExitStatus, Stdio};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+use ast::ast::StringTable;
+use ast::get_id;
+use ast::id::*;
+
+use crate::basic_ops::{get_arg_vec, pop_args, push};
+use crate::reporting::*;
+use crate::stack::ValueStack;
+use crate::system::{array_items, io_error_value, make_array, make_map};
+use crate::value::Value;
+use crate::vm::DataFunc;
+
+//every atom a process handle answers to (used for editor completion)
+pub const PROCESS_FUNCS: &[&str] = &[":write", ":read_line", ":wait"];
+
+fn string_value(s: String) -> Value<'static> {
+    Value::String(Arc::new(s.into()))
+}
+
+// the command and its args as strings
+fn pop_command(stack: &mut ValueStack) -> Result<Command, ErrList> {
+    let [Value::String(cmd), args] = pop_args(stack)? else {
+        return Err(sig_error());
+    };
+    let mut command = Command::new(cmd.as_str());
+    for arg in array_items(&args)? {
+        let Value::String(arg) = arg else {
+            return Err(sig_error());
+        };
+        command.arg(arg.as_str());
+    }
+    Ok(command)
+}
+
+fn finished(status: ExitStatus, stdout: String, stderr: String) -> Value<'static> {
+    let code = status.code().map_or(Value::Nil, |c| Value::Int(c as i64));
+    let result = Value::DataFunc(make_map(vec![
+        (Value::Atom(get_id!(":status")), code),
+        (Value::Atom(get_id!(":stdout")), string_value(stdout)),
+        (Value::Atom(get_id!(":stderr")), string_value(stderr)),
+    ]));
+    match status.success() {
+        true => result,
+        false => Value::DataFunc(make_array(vec![
+            Value::Atom(get_id!(":err")),
+            Value::Atom(get_id!(":exit_status")),
+            result,
+        ])),
+    }
+}
+
+// run(cmd, args) waits for the program and gives everything it wrote
+pub fn run_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+    let mut command = pop_command(stack)?;
+    let value = match command.stdin(Stdio::null()).output() {
+        Ok(out) => finished(
+            out.status,
+            String::from_utf8_lossy(&out.stdout).into_owned(),
+            String::from_utf8_lossy(&out.stderr).into_owned(),
+        ),
+        Err(e) => io_error_value(&e),
+    };
+    push(stack, value)
+}
+
+struct Running {
+    child: Child,
+    stdin: Option<ChildStdin>,
+    stdout: BufReader<ChildStdout>,
+    //stderr is read on the side so a chatty program can not block on it
+    stderr: Option<JoinHandle<String>>,
+}
+
+//a handle dropped without :wait takes its program down with it
+impl Drop for Running {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+// start_process(cmd, args) gives a handle answering
+// (:write, s) -> :ok, (:read_line) -> the next line without its newline or nil at the end
+// and (:wait) -> the same as run with the rest of stdout (stdin is closed first)
+pub fn start_process_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+    let mut command = pop_command(stack)?;
+    let spawned = command.stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::piped()).spawn();
+    let mut child = match spawned {
+        Ok(child) => child,
+        Err(e) => return push(stack, io_error_value(&e)),
+    };
+
+    let mut stderr = child.stderr.take().expect("stderr is piped");
+    let running = Running {
+        stdin: child.stdin.take(),
+        stdout: BufReader::new(child.stdout.take().expect("stdout is piped")),
+        stderr: Some(thread::spawn(move || {
+            let mut ans = Vec::new();
+            let _ = stderr.read_to_end(&mut ans);
+            String::from_utf8_lossy(&ans).into_owned()
+        })),
+        child,
+    };
+    push(stack, Value::DataFunc(make_handle(running)))
+}
+
+fn make_handle(running: Running) -> DataFunc {
+    let running = Mutex::new(Some(running));
+    DataFunc::new(move |stack, _table| {
+        let mut args = get_arg_vec(stack);
+        args.reverse();
+        stack.pop_terminator().ok_or_else(sig_error)?;
+
+        let mut guard = running.lock().unwrap_or_else(|e| e.into_inner());
+        //anything after :wait is a use of a closed handle
+        let Some(process) = guard.as_mut() else {
+            return push(stack, io_error_value(&io::Error::from(io::ErrorKind::InvalidInput)));
+        };
+
+        let value = match args.as_slice() {
+            [Value::Atom(get_id!(":write")), Value::String(s)] => {
+                let stdin = process.stdin.as_mut().expect("stdin stays open until :wait");
+                match stdin.write_all(s.as_bytes()).and_then(|_| stdin.flush()) {
+                    Ok(()) => Value::Atom(get_id!(":ok")),
+                    Err(e) => io_error_value(&e),
+                }
+            }
+            [Value::Atom(get_id!(":write")), ..] => return Err(arg_error(2, args.len())),
+            [Value::Atom(get_id!(":read_line"))] => {
+                let mut line = String::new();
+                match process.stdout.read_line(&mut line) {
+                    Ok(0) => Value::Nil,
+                    Ok(_) => {
+                        let end = line.trim_end_matches(['\n', '\r']).len();
+                        line.truncate(end);
+                        string_value(line)
+                    }
+                    Err(e) => io_error_value(&e),
+                }
+            }
+            [Value::Atom(get_id!(":wait"))] => {
+                let mut process = guard.take().expect("checked above");
+                drop(process.stdin.take());
+                let mut rest = Vec::new();
+                let read = process.stdout.read_to_end(&mut rest);
+                let stderr = process.stderr.take().and_then(|h| h.join().ok()).unwrap_or_default();
+                match read.and_then(|_| process.child.wait()) {
+                    Ok(status) => finished(status, String::from_utf8_lossy(&rest).into_owned(), stderr),
+                    Err(e) => io_error_value(&e),
+                }
+            }
+            _ => return Err(sig_error()),
+        };
+        push(stack, value)
+    })
+}
+
+#[cfg(test)]
+use crate::basic_ops::{call_data, call_native, text};
+
+#[cfg(test)]
+fn strings(parts: &[&str]) -> Value<'static> {
+    Value::DataFunc(make_array(parts.iter().map(|s| text(s)).collect()))
+}
+
+#[cfg(test)]
+fn field(map: &Value<'static>, key: u32) -> Value<'static> {
+    let Value::DataFunc(f) = map else { panic!("expected a map got {:?}", map) };
+    let crate::vm::DataKind::Map(entries) = f.kind() else { panic!("expected a map") };
+    entries.iter().find(|(k, _)| *k == Value::Atom(key)).map(|(_, v)| v.clone()).unwrap()
+}
+
+#[test]
+fn test_run() {
+    let ok = call_native(run_fn, [text("sh"), strings(&["-c", "echo hi; echo oops >&2"])]).unwrap();
+    assert_eq!(field(&ok, get_id!(":status")), Value::Int(0));
+    assert_eq!(field(&ok, get_id!(":stdout")), text("hi\n"));
+    assert_eq!(field(&ok, get_id!(":stderr")), text("oops\n"));
+
+    let failed = call_native(run_fn, [text("sh"), strings(&["-c", "echo partial; exit 3"])]).unwrap();
+    let items = array_items(&failed).unwrap();
+    assert_eq!(items[..2], [Value::Atom(get_id!(":err")), Value::Atom(get_id!(":exit_status"))]);
+    assert_eq!(field(&items[2], get_id!(":status")), Value::Int(3));
+    assert_eq!(field(&items[2], get_id!(":stdout")), text("partial\n"));
+
+    let missing = call_native(run_fn, [text("faeyne-no-such-program"), strings(&[])]).unwrap();
+    assert_eq!(array_items(&missing).unwrap()[1], Value::Atom(get_id!(":not_found")));
+    assert!(call_native(run_fn, [text("sh"), Value::DataFunc(make_array(vec![Value::Int(1)]))]).is_err());
+}
+
+#[test]
+fn test_start_process() {
+    let atom = |id| Value::Atom(id);
+    let Value::DataFunc(p) = call_native(start_process_fn, [text("cat"), strings(&[])]).unwrap() else { panic!() };
+    assert_eq!(call_data(&p, [atom(get_id!(":write")), text("first\nsecond")]).unwrap(), atom(get_id!(":ok")));
+    assert_eq!(call_data(&p, [atom(get_id!(":read_line"))]).unwrap(), text("first"));
+    assert_eq!(call_data(&p, [atom(get_id!(":write")), text(" line\n")]).unwrap(), atom(get_id!(":ok")));
+    assert_eq!(call_data(&p, [atom(get_id!(":read_line"))]).unwrap(), text("second line"));
+    assert_eq!(call_data(&p, [atom(get_id!(":write")), text("rest")]).unwrap(), atom(get_id!(":ok")));
+    assert_eq!(field(&call_data(&p, [atom(get_id!(":wait"))]).unwrap(), get_id!(":stdout")), text("rest"));
+    let closed = call_data(&p, [atom(get_id!(":wait"))]).unwrap();
+    assert_eq!(array_items(&closed).unwrap()[1], atom(get_id!(":invalid_input")));
+}
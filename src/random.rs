@@ -0,0 +1,157 @@
+// Seedable random numbers, reached with system(:random)(seed).
+// A generator is an immutable value, asking it for a number gives [number, next_generator]
+// so the same seed always gives the same sequence on every platform.
+//
+// The algorithm is xoshiro256** (https://prng.di.unimi.it) with the state filled by splitmix64,
+// both as published by their authors.
+
+
+use ast::ast::StringTable;
+use ast::get_id;
+use ast::id::*;
+
+use crate::basic_ops::{get_arg_vec, pop_args};
+use crate::reporting::*;
+use crate::stack::ValueStack;
+use crate::system::make_array;
+use crate::value::Value;
+use crate::vm::DataFunc;
+
+//every atom a generator answers to (used for editor completion)
+pub const RANDOM_FUNCS: &[&str] = &[":next_int", ":next_float", ":range"];
+
+fn splitmix64(x: &mut u64) -> u64 {
+    *x = x.wrapping_add(0x9e3779b97f4a7c15);
+    let mut z = *x;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Xoshiro256 {
+    s: [u64; 4],
+}
+
+impl Xoshiro256 {
+    pub fn from_seed(seed: u64) -> Self {
+        let mut x = seed;
+        Xoshiro256 { s: std::array::from_fn(|_| splitmix64(&mut x)) }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let s = &mut self.s;
+        let ans = s[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = s[1] << 17;
+
+        s[2] ^= s[0];
+        s[3] ^= s[1];
+        s[1] ^= s[2];
+        s[0] ^= s[3];
+
+        s[2] ^= t;
+        s[3] = s[3].rotate_left(45);
+        ans
+    }
+
+    // uniform in [0, 1) using the top 53 bits
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 * (1.0 / (1u64 << 53) as f64)
+    }
+
+    // uniform in [low, high), numbers past the last whole multiple of the range are thrown away to avoid bias
+    pub fn next_range(&mut self, low: i64, high: i64) -> Option<i64> {
+        if high <= low {
+            return None;
+        }
+        let span = high.abs_diff(low);
+        let limit = u64::MAX - u64::MAX % span;
+        loop {
+            let x = self.next_u64();
+            if x < limit {
+                return Some(low.wrapping_add((x % span) as i64));
+            }
+        }
+    }
+}
+
+pub fn random_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+    let [Value::Int(seed)] = pop_args(stack)? else {
+        return Err(sig_error());
+    };
+    let generator = make_generator(Xoshiro256::from_seed(seed as u64));
+    stack.push_value(Value::DataFunc(generator)).map_err(|_| overflow_error())
+}
+
+pub fn make_generator(rng: Xoshiro256) -> DataFunc {
+    DataFunc::new(move |stack: &mut ValueStack, _table: &StringTable| {
+        let mut args = get_arg_vec(stack);
+        args.reverse();
+        stack.pop_terminator().ok_or_else(sig_error)?;
+
+        let mut next = rng;
+        let value = match args.as_slice() {
+            [Value::Atom(get_id!(":next_int"))] => Value::Int(next.next_u64() as i64),
+            [Value::Atom(get_id!(":next_float"))] => Value::Float(next.next_f64()),
+            [Value::Atom(get_id!(":range")), Value::Int(low), Value::Int(high)] => {
+                Value::Int(next.next_range(*low, *high).ok_or_else(sig_error)?)
+            }
+            [Value::Atom(get_id!(":range")), ..] => return Err(arg_error(3, args.len())),
+            _ => return Err(sig_error()),
+        };
+
+        let pair = make_array(vec![value, Value::DataFunc(make_generator(next))]);
+        stack.push_value(Value::DataFunc(pair)).map_err(|_| overflow_error())
+    })
+}
+
+#[test]
+fn test_reference_outputs() {
+    // first splitmix64 output for a zero seed
+    assert_eq!(splitmix64(&mut 0), 0xe220a8397b1dcdaf);
+
+    // outputs of the reference xoshiro256** code for the state 1,2,3,4
+    let mut rng = Xoshiro256 { s: [1, 2, 3, 4] };
+    let outputs: Vec<u64> = (0..4).map(|_| rng.next_u64()).collect();
+    assert_eq!(outputs, [11520, 0, 1509978240, 1215971899390074240]);
+
+    let mut rng = Xoshiro256::from_seed(7);
+    for _ in 0..1000 {
+        let f = rng.next_f64();
+        assert!((0.0..1.0).contains(&f));
+        let r = rng.next_range(-3, 4).unwrap();
+        assert!((-3..4).contains(&r));
+    }
+    assert_eq!(rng.next_range(i64::MIN, i64::MAX).map(|_| ()), Some(()));
+    assert_eq!(rng.next_range(1, 1), None);
+}
+
+// calls a generator and splits the [value, next generator] pair it gives
+#[cfg(test)]
+fn draw(generator: &DataFunc, args: Vec<Value<'static>>) -> Result<(Value<'static>, DataFunc), ErrList> {
+    let pair = crate::basic_ops::call_data(generator, args)?;
+    match crate::system::array_items(&pair)?.as_slice() {
+        [value, Value::DataFunc(next)] => Ok((value.clone(), next.clone())),
+        _ => panic!("expected a pair got {:?}", pair),
+    }
+}
+
+#[test]
+fn test_generators() {
+    let next_int = || vec![Value::Atom(get_id!(":next_int"))];
+    let a = make_generator(Xoshiro256::from_seed(42));
+    let b = make_generator(Xoshiro256::from_seed(42));
+    let (first, next) = draw(&a, next_int()).unwrap();
+    assert_eq!(first, draw(&b, next_int()).unwrap().0);
+    assert_eq!(first, Value::Int(Xoshiro256::from_seed(42).next_u64() as i64));
+    assert_ne!(first, draw(&make_generator(Xoshiro256::from_seed(43)), next_int()).unwrap().0);
+    assert_eq!(draw(&a, next_int()).unwrap().0, first, "a generator does not change when drawn from");
+    assert_ne!(draw(&next, next_int()).unwrap().0, first);
+
+    let Value::Float(x) = draw(&a, vec![Value::Atom(get_id!(":next_float"))]).unwrap().0 else { panic!() };
+    assert!((0.0..1.0).contains(&x));
+    let range = |low, high| vec![Value::Atom(get_id!(":range")), Value::Int(low), Value::Int(high)];
+    assert_eq!(draw(&a, range(5, 6)).unwrap().0, Value::Int(5));
+    assert!(draw(&a, range(6, 5)).is_err());
+    assert!(draw(&a, vec![Value::Atom(get_id!(":range")), Value::Int(1)]).is_err());
+}
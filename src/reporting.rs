@@ -6,7 +6,7 @@ use codespan::ByteIndex;
 
 use std::collections::LinkedList;
 
-use codespan_reporting::diagnostic::{Diagnostic, Label};
+use codespan_reporting::diagnostic::{Diagnostic, Label, Severity};
 use codespan_reporting::term::{self, termcolor::Buffer};
 use codespan_reporting::files::SimpleFiles;
 
@@ -14,6 +14,8 @@ use lalrpop_util::ParseError;
 use ast::lexer::LexTag;
 
 use ast::ast::FuncSig;
+use ast::get_id;
+use ast::id::*;
 use ast::ast::StringTable;
 
 #[derive(Debug,PartialEq)]
@@ -37,6 +39,15 @@ pub enum Error {
     Recursion(RecursionError),
     StackOverflow,
 
+    //a native asking the process scheduler to finish its call (the args are left on the stack)
+    Yield(u32),
+    Deadlock,
+    //a host function answered with DataFunc::pending(token) where nothing can wait for it
+    Suspend(u64),
+    NotSuspended(u64),
+    //a value that only makes sense in the program it came from (see transfer)
+    Transfer(&'static str),
+
     Bug(&'static str),
     //UndocumentedError,
 }
@@ -87,6 +98,37 @@ pub fn overflow_error() -> ErrList {
     Error::StackOverflow.to_list() 
 }
 
+#[cold]
+#[inline(never)]
+pub fn transfer_error(reason:&'static str) -> ErrList {
+    Error::Transfer(reason).to_list()
+}
+
+//a yield stops the context without unwinding it
+pub fn is_pause(err:&ErrList) -> bool {
+    yielded(err).is_some()
+}
+
+#[cold]
+#[inline(never)]
+pub fn yield_error(atom:u32) -> ErrList {
+    Error::Yield(atom).to_list()
+}
+
+//the request if err is a yield and not a real error
+pub fn yielded(err:&ErrList) -> Option<u32> {
+    match (err.len(),err.front()) {
+        (1,Some(Error::Yield(atom))) => Some(*atom),
+        _ => None,
+    }
+}
+
+#[cold]
+#[inline(never)]
+pub fn deadlock_error() -> ErrList {
+    Error::Deadlock.to_list()
+}
+
 #[cold]
 #[inline(never)]
 pub fn sig_error() -> ErrList {
@@ -149,8 +191,8 @@ pub fn missing_func_error(name:String) -> ErrList {
 
 #[cold]
 #[inline(never)]
-pub fn missing_error(id:u32) -> ErrList {
-    Error::Missing(UndefinedName{id}).to_list()
+pub fn missing_error(id:u32,span:Span,suggestion:Option<u32>) -> ErrList {
+    Error::Missing(UndefinedName{id,span,suggestion}).to_list()
 }
 
 #[cold]
@@ -189,6 +231,8 @@ pub struct UnreachableCase {
 #[derive(Debug,PartialEq)]
 pub struct UndefinedName {
     pub id : u32,
+    pub span : Span,
+    pub suggestion : Option<u32>,//closest name in scope
 }
 
 #[derive(Debug,PartialEq)]
@@ -209,6 +253,19 @@ pub struct IllegalSelfRef {
     pub span : Span,
 }
 
+#[derive(Debug,PartialEq)]
+pub enum Warning {
+    UnusedVariable(NameWarning),
+    Shadowing(NameWarning),
+    UnusedFunction(NameWarning),
+}
+
+#[derive(Debug,PartialEq)]
+pub struct NameWarning {
+    pub id : u32,
+    pub span : Span,
+}
+
 pub trait DiagnosticDisplay {
     fn display_with_table(&self, table: &StringTable) -> String;
 }
@@ -231,7 +288,16 @@ pub fn report_parse_error(err: ParseError<usize, LexTag, ()>, input_ref: &str,_t
     let mut files = SimpleFiles::new();
     let file_id = files.add("input", input_ref);
 
-    let diagnostic = match err {
+    let diagnostic = parse_error_diagnostic(err, file_id);
+    let config = term::Config::default();
+    term::emit(&mut buffer, &config, &files, &diagnostic).unwrap();
+
+    println!("{}", String::from_utf8(buffer.into_inner()).unwrap());
+    // panic!("Parse error occurred");
+}
+
+pub fn parse_error_diagnostic(err: ParseError<usize, LexTag, ()>, file_id: usize) -> Diagnostic<usize> {
+    match err {
         ParseError::InvalidToken { location } => Diagnostic::error()
             .with_message("Invalid token")
             .with_labels(vec![Label::primary(file_id, location..location + 1)]),
@@ -247,13 +313,7 @@ pub fn report_parse_error(err: ParseError<usize, LexTag, ()>, input_ref: &str,_t
             .with_message("Extra token")
             .with_labels(vec![Label::primary(file_id, token.0..token.2)]),
         ParseError::User { .. } => unreachable!(),
-    };
-
-    let config = term::Config::default();
-    term::emit(&mut buffer, &config, &files, &diagnostic).unwrap();
-
-    println!("{}", String::from_utf8(buffer.into_inner()).unwrap());
-    // panic!("Parse error occurred");
+    }
 }
 
 
@@ -306,6 +366,18 @@ fn test_subslice_span_and_diagnostic_reporting() {
 
 
 
+//the innermost error message, for when there is no source to point into
+pub fn error_message(err_list: &ErrList, table: &StringTable) -> String {
+    let mut diagnostics = Vec::new();
+    for err in err_list {
+        error_diagnostics(err, 0, table, &mut diagnostics);
+    }
+    diagnostics.into_iter().rev()
+        .find(|d| d.severity == Severity::Error)
+        .map(|d| d.message)
+        .unwrap_or_default()
+}
+
 pub fn report_err_list(err_list: &ErrList, input_ref: &str, table: &StringTable) {
     let mut buffer = Buffer::ansi();
     let mut files = SimpleFiles::new();
@@ -313,22 +385,69 @@ pub fn report_err_list(err_list: &ErrList, input_ref: &str, table: &StringTable)
     let config = term::Config::default();
 
     // Emit each error directly
+    let mut diagnostics = Vec::new();
     for err in err_list {
-        emit_error(err, file_id, &mut buffer, &config, &files, table);
+        error_diagnostics(err, file_id, table, &mut diagnostics);
+    }
+    for diagnostic in diagnostics {
+        term::emit(&mut buffer, &config, &files, &diagnostic).unwrap();
     }
 
     // Print the full diagnostic report
     println!("{}", String::from_utf8(buffer.into_inner()).unwrap());
 }
 
-fn emit_error(
-    err: &Error,
-    file_id: usize,
-    buffer: &mut Buffer,
-    config: &term::Config,
-    files: &SimpleFiles<&str, &str>,
-    table: &StringTable,
-) {
+pub fn report_warnings(warnings: &[Warning], input_ref: &str, table: &StringTable) {
+    let mut buffer = Buffer::ansi();
+    let mut files = SimpleFiles::new();
+    let file_id = files.add("input", input_ref);
+    let config = term::Config::default();
+
+    for warning in warnings {
+        let diagnostic = warning_diagnostic(warning, file_id, table);
+        term::emit(&mut buffer, &config, &files, &diagnostic).unwrap();
+    }
+
+    eprintln!("{}", String::from_utf8(buffer.into_inner()).unwrap());
+}
+
+pub fn warning_diagnostic(warning: &Warning, file_id: usize, table: &StringTable) -> Diagnostic<usize> {
+    match warning {
+        Warning::UnusedVariable(NameWarning { id, span }) => {
+            let name = table.get_display_str(*id).unwrap_or("Unknown name");
+            Diagnostic::warning()
+                .with_message(format!("unused variable: {}", name))
+                .with_labels(vec![
+                    Label::primary(file_id, span.start().to_usize()..span.end().to_usize())
+                        .with_message("assigned here but never read"),
+                ])
+                .with_notes(vec![
+                    format!("if this is intentional, prefix it with an underscore: _{}", name),
+                ])
+        },
+        Warning::Shadowing(NameWarning { id, span }) => {
+            let name = table.get_display_str(*id).unwrap_or("Unknown name");
+            Diagnostic::warning()
+                .with_message(format!("{} shadows a variable from an outer scope", name))
+                .with_labels(vec![
+                    Label::primary(file_id, span.start().to_usize()..span.end().to_usize())
+                        .with_message("this makes a new variable, the outer one is not changed"),
+                ])
+        },
+        Warning::UnusedFunction(NameWarning { id, span }) => Diagnostic::warning()
+            .with_message(format!(
+                "function '{}' is never used",
+                table.get_display_str(*id).unwrap_or("Unknown name")
+            ))
+            .with_labels(vec![
+                Label::primary(file_id, span.start().to_usize()..span.end().to_usize())
+                    .with_message("not reachable from main"),
+            ]),
+    }
+}
+
+//an error can render as several diagnostics (stacked errors and help messages)
+pub fn error_diagnostics(err: &Error, file_id: usize, table: &StringTable, out: &mut Vec<Diagnostic<usize>>) {
     let diagnostic = match err {
         Error::Match(m_err) => Diagnostic::error()
             .with_message("Match error")
@@ -340,11 +459,27 @@ fn emit_error(
         Error::Sig(_s_err) => Diagnostic::error()
             .with_message("Signature error: ___"),
 
-        Error::Missing(UndefinedName { id }) => Diagnostic::error()
-            .with_message(format!(
-                "Undefined name error: {}",
-                table.get_display_str(*id).unwrap_or("Unknown name")
-            )),
+        Error::Missing(UndefinedName { id, span, suggestion }) => {
+            let error = Diagnostic::error()
+                .with_message(format!(
+                    "Undefined name error: {}",
+                    table.get_display_str(*id).unwrap_or("Unknown name")
+                ))
+                .with_labels(vec![
+                    Label::primary(file_id, span.start().to_usize()..span.end().to_usize())
+                        .with_message("not found in this scope"),
+                ]);
+
+            out.push(error);
+
+            let Some(suggestion) = suggestion else {
+                return;
+            };
+            Diagnostic::help().with_message(format!(
+                "did you mean '{}'?",
+                table.get_display_str(*suggestion).unwrap_or("Unknown name")
+            ))
+        },
 
         Error::MissingCall(s) => Diagnostic::error()
             .with_message(format!(
@@ -383,12 +518,12 @@ fn emit_error(
                 Label::primary(file_id, span.start().to_usize()..span.end().to_usize())
                     ,
             ]);
-            term::emit(buffer, config, files, &diagnostic).unwrap();
+            out.push(diagnostic);
 
 
             // Emit each error inside `Error::Stacked` recursively
             for e in err {
-                emit_error(e, file_id, buffer, config, files, table);
+                error_diagnostics(e, file_id, table, out);
             }
 
             return;
@@ -401,12 +536,12 @@ fn emit_error(
             ]).with_notes(vec![
                 "Note: some self recursive calls may be missing due to tail call optimization".to_string()
             ]);
-            term::emit(buffer, config, files, &diagnostic).unwrap();
+            out.push(diagnostic);
 
 
             // Emit each error inside `Error::Stacked` recursively
             for e in err {
-                emit_error(e, file_id, buffer, config, files, table);
+                error_diagnostics(e, file_id, table, out);
             }
 
             return;
@@ -419,7 +554,7 @@ fn emit_error(
                         Label::primary(file_id, span.start().to_usize()..span.end().to_usize())
                             .with_message("this self ref creates a cycle"),
                     ]);
-            term::emit(buffer, config, files, &error).unwrap();
+            out.push(error);
             Diagnostic::help().with_message("try refering to the function by name (define it with def)")
 
         },
@@ -434,18 +569,37 @@ fn emit_error(
                 .with_notes(vec![
                     format!("Details: {}", message),
                 ]);
-            term::emit(buffer, config, files, &diagnostic).unwrap();
+            out.push(diagnostic);
             Diagnostic::help().with_message("This is not your fault, but rather an implementation bug. Please report this to the maintainers.")
         },
         Error::StackOverflow => {
             let diagnostic = Diagnostic::error()
                 .with_message("StackOverflow");
-            term::emit(buffer, config, files, &diagnostic).unwrap();
+            out.push(diagnostic);
 
 
             Diagnostic::help().with_message("probably caused by an infinite loop or excessive memory consumbtion")
         },
 
+        Error::Yield(get_id!(":emit")) => Diagnostic::error()
+            .with_message("emit only works while its stream is being pulled"),
+        Error::Yield(atom) => Diagnostic::error()
+            .with_message(format!("system({}) only works in a program run by the process scheduler",table.get_raw_str(*atom)))
+            .with_notes(vec![
+                "run it with Code::run_processes".to_string(),
+                "a stream generator runs apart from its process so it can not use it either".to_string(),
+            ]),
+        Error::Suspend(token) => Diagnostic::error()
+            .with_message(format!("a host function suspended with token {} but nothing can resume it",token))
+            .with_notes(vec![
+                "run it with Context::run_suspendable".to_string(),
+            ]),
+        Error::NotSuspended(token) => Diagnostic::error()
+            .with_message(format!("resumed with token {} but the context is not waiting on it",token)),
+        Error::Transfer(reason) => Diagnostic::error()
+            .with_message(format!("can not move this value to another program: {}",reason)),
+        Error::Deadlock => Diagnostic::error()
+            .with_message("every process is waiting in :receive with no timeout"),
         Error::ZeroDiv => Diagnostic::error()
             .with_message("attempted to divide by zero"),
         Error::ArgNum(e) => Diagnostic::error()
@@ -453,7 +607,7 @@ fn emit_error(
     };
 
     // Emit the diagnostic for the current error
-    term::emit(buffer, config, files, &diagnostic).unwrap();
+    out.push(diagnostic);
 }
 
 // fn add_error
@@ -466,6 +620,7 @@ fn test_err_list_reporting() {
     let unreachable_name_id = table.get_id("foo");
 
     let source = "fn main() { let x = 5; baba}";
+    let baba_span = Span::new(ByteIndex(23), ByteIndex(27));
     let mut err_list = LinkedList::new();
 
     // Example errors for testing
@@ -474,7 +629,7 @@ fn test_err_list_reporting() {
     });
 
     let sig_err = Error::Sig(SigError {});
-    let undef_err = Error::Missing(UndefinedName { id: undef_id });
+    let undef_err = Error::Missing(UndefinedName { id: undef_id, span: baba_span, suggestion: Some(unreachable_name_id) });
 
     // Simulate unreachable case
     let unreachable_case_err = Error::UnreachableFunction(
@@ -508,7 +663,7 @@ fn test_err_list_reporting_with_stacking() {
     });
 
     let sig_err = Error::Sig(SigError {});
-    let _undef_err = Error::Missing(UndefinedName { id: undef_id });
+    let _undef_err = Error::Missing(UndefinedName { id: undef_id, span: Span::new(ByteIndex(24), ByteIndex(28)), suggestion: None });
 
     // Simulate unreachable case
     let _unreachable_case_err = Error::UnreachableFunction(
@@ -521,7 +676,7 @@ fn test_err_list_reporting_with_stacking() {
         span: Span::new(ByteIndex(23), ByteIndex(27)),
         message: "junk",
         err: vec_to_list(vec![
-            Error::Missing(UndefinedName { id: undef_id }),
+            Error::Missing(UndefinedName { id: undef_id, span: Span::new(ByteIndex(24), ByteIndex(28)), suggestion: None }),
             Error::UnreachableFunction(
                 
                 FuncSig {name: unreachable_name_id, args: vec![undef_id] },
@@ -12,6 +12,7 @@ use crate::vm::FuncData;
 use crate::value::VarTable;
 use crate::value::Value as IRValue;
 use crate::vm::{Operation,Context};
+use crate::scheduler::{self,Mode};
 
 pub struct Code<'a> {
 	pub names: Vec<u32>,
@@ -122,6 +123,26 @@ impl Code<'_> {
 	}
 }
 
+impl Code<'_> {
+	//like run_map but main may spawn processes (see scheduler)
+	pub fn run_processes<
+		'a,ValueIter:IntoIterator<Item = IRValue<'a>>, 
+		T,F:FnOnce(IRValue) -> T
+		>
+	(&self,name:&str,values:ValueIter,mode:Mode,map:F) 
+	-> Result<T,ErrList> {
+
+		let func = *self.name_map.get(name).ok_or_else(|| missing_func_error(name.to_string()))?;
+		let table = &*self.table.read().unwrap();
+
+		let global = self.get_global();
+		let Some(IRValue::Func(main)) = global.get(func) else { return Err(missing_func_error(name.to_string())) };
+		let x = scheduler::run(main,values,&global,table,mode)?;
+
+		Ok(map(x))
+	}
+}
+
 #[test]
 fn test_unified_code_runs() {
 
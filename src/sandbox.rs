@@ -0,0 +1,228 @@
+// A restricted system for running scripts we do not trust.
+// SystemBuilder::new().build() behaves like system::system, every allow_* call switches
+// file access to a whitelist: paths are canonicalized before checking them so ".." and symlinks
+// can not leave the allowed directories, and a denied operation gives [:err, :permission_denied].
+// Running other programs is only allowed with full file access.
+
+use std::io::{self, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use ast::get_id;
+use ast::id::*;
+
+use crate::basic_ops::get_arg_vec;
+use crate::reporting::*;
+use crate::system::*;
+use crate::value::Value;
+use crate::filesystem::{FileSystem, RealFs};
+use crate::vm::DataFunc;
+
+type Sink = Arc<Mutex<dyn Write + Send>>;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Access {
+    Read,
+    Write,
+    Data,
+}
+
+#[derive(Debug, Clone)]
+enum FsAccess {
+    Full,
+    Confined { read: Vec<PathBuf>, write: Vec<PathBuf> },
+}
+
+pub struct SystemBuilder {
+    fs: FsAccess,
+    backend: Arc<dyn FileSystem>,
+    out: Option<Sink>,
+    err: Option<Sink>,
+}
+
+impl Default for SystemBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SystemBuilder {
+    pub fn new() -> Self {
+        SystemBuilder { fs: FsAccess::Full, backend: Arc::new(RealFs), out: None, err: None }
+    }
+
+    fn confined(&mut self) -> (&mut Vec<PathBuf>, &mut Vec<PathBuf>) {
+        if let FsAccess::Full = self.fs {
+            self.fs = FsAccess::Confined { read: Vec::new(), write: Vec::new() };
+        }
+        match &mut self.fs {
+            FsAccess::Confined { read, write } => (read, write),
+            FsAccess::Full => unreachable!(),
+        }
+    }
+
+    // reading anything under root (files, directory listings and metadata)
+    pub fn allow_read(mut self, root: impl Into<PathBuf>) -> Self {
+        self.confined().0.push(root.into());
+        self
+    }
+
+    // creating, changing and deleting anything under root (reading needs allow_read)
+    pub fn allow_write(mut self, root: impl Into<PathBuf>) -> Self {
+        self.confined().1.push(root.into());
+        self
+    }
+
+    // no file access at all, including anything allowed before
+    pub fn deny_fs(mut self) -> Self {
+        self.fs = FsAccess::Confined { read: Vec::new(), write: Vec::new() };
+        self
+    }
+
+    // file functions work on this instead of the disk (like a MemoryFs in tests)
+    pub fn with_fs(mut self, fs: Arc<dyn FileSystem>) -> Self {
+        self.backend = fs;
+        self
+    }
+
+    // println and print write here instead of stdout
+    pub fn with_println_sink(mut self, writer: impl Write + Send + 'static) -> Self {
+        self.out = Some(Arc::new(Mutex::new(writer)));
+        self
+    }
+
+    // eprintln writes here instead of stderr
+    pub fn with_eprintln_sink(mut self, writer: impl Write + Send + 'static) -> Self {
+        self.err = Some(Arc::new(Mutex::new(writer)));
+        self
+    }
+
+    pub fn build(self) -> DataFunc {
+        let access = Arc::new(self.fs);
+        let backend = self.backend;
+        let (out, err) = (self.out, self.err);
+        DataFunc::new(move |stack, table| {
+            let atom = stack.pop_atom().ok_or_else(|| arg_error(1, 0))?;
+            stack.pop_terminator().ok_or_else(sig_error)?;
+
+            let sink = match atom {
+                get_id!(":println") | get_id!(":print") => &out,
+                get_id!(":eprintln") => &err,
+                _ => &None,
+            };
+            let confined = matches!(*access, FsAccess::Confined { .. });
+            let func = match (sink, file_func(atom)) {
+                //other programs could touch any file so they only run with full access.
+                //the working directory and environment belong to the host process
+                _ if confined && matches!(atom,
+                    get_id!(":run") | get_id!(":start_process")
+                    | get_id!(":set_cwd") | get_id!(":cwd") | get_id!(":env") | get_id!(":env_vars")
+                ) => denied(),
+                (Some(sink), _) => printer(sink.clone(), if atom == get_id!(":print") { "" } else { "\n" }),
+                (_, Some(func)) => guarded(func, atom, access.clone(), backend.clone()),
+                _ => {
+                    stack.push_terminator().map_err(|_| overflow_error())?;
+                    stack.push_atom(atom).map_err(|_| overflow_error())?;
+                    return system(stack, table);
+                }
+            };
+            stack.push_value(Value::DataFunc(func)).map_err(|_| overflow_error())
+        })
+    }
+}
+
+// what each arg of a file function needs (Data is not a path)
+fn path_args(atom: u32) -> Option<&'static [Access]> {
+    use Access::*;
+    let args: &'static [Access] = match atom {
+        get_id!(":read_file") | get_id!(":read_bytes") | get_id!(":read_lines") | get_id!(":read_dir") | get_id!(":exists") | get_id!(":metadata") => &[Read],
+        get_id!(":write_file") | get_id!(":write_bytes") | get_id!(":append_file") => &[Write, Data],
+        get_id!(":delete_file") | get_id!(":make_dir") | get_id!(":make_dir_all") | get_id!(":delete_dir") | get_id!(":delete_dir_all") => &[Write],
+        get_id!(":rename") => &[Write, Write],
+        get_id!(":copy_file") => &[Read, Write],
+        _ => return None,
+    };
+    Some(args)
+}
+
+// the canonical form of a path that may not exist yet.
+// the missing part is only allowed plain names, and an entry we can not resolve (like a dangling symlink) is rejected
+pub fn resolve_path(fs: &dyn FileSystem, path: &Path) -> Option<PathBuf> {
+    let mut existing = fs.current_dir().ok()?.join(path);
+    let mut missing = Vec::new();
+    let canonical = loop {
+        match fs.canonicalize(&existing) {
+            Ok(p) => break p,
+            Err(_) if fs.entry_exists(&existing) => return None,
+            Err(_) => {
+                missing.push(existing.file_name()?.to_owned());
+                existing = existing.parent()?.to_path_buf();
+            }
+        }
+    };
+    Some(missing.iter().rev().fold(canonical, |p, name| p.join(name)))
+}
+
+fn is_allowed(fs: &dyn FileSystem, path: &Path, roots: &[PathBuf]) -> bool {
+    roots.iter().filter_map(|root| fs.canonicalize(root).ok()).any(|root| path.starts_with(root))
+}
+
+fn guarded(func: FileFunc, atom: u32, access: Arc<FsAccess>, fs: Arc<dyn FileSystem>) -> DataFunc {
+    DataFunc::new(move |stack, _table| {
+        let (FsAccess::Confined { read, write }, Some(paths)) = (&*access, path_args(atom)) else {
+            return func(&*fs, stack);
+        };
+        let mut args = get_arg_vec(stack);
+        args.reverse();
+        if args.len() != paths.len() {
+            stack.pop_terminator().ok_or_else(sig_error)?;
+            return Err(arg_error(paths.len(), args.len()));
+        }
+
+        for (arg, access) in args.iter_mut().zip(paths) {
+            let roots = match access {
+                Access::Read => read,
+                Access::Write => write,
+                Access::Data => continue,
+            };
+            let Value::String(s) = arg else {
+                stack.pop_terminator().ok_or_else(sig_error)?;
+                return Err(sig_error());
+            };
+            let resolved = resolve_path(&*fs, Path::new(s.as_str()))
+                .filter(|p| is_allowed(&*fs, p, roots))
+                .and_then(|p| p.to_str().map(str::to_string));
+            match resolved {
+                Some(p) => *arg = Value::String(Arc::new(p.into())),
+                None => {
+                    stack.pop_terminator().ok_or_else(sig_error)?;
+                    return stack.push_value(permission_denied()).map_err(|_| overflow_error());
+                }
+            }
+        }
+
+        for arg in args {
+            stack.push_value(arg).map_err(|_| overflow_error())?;
+        }
+        func(&*fs, stack)
+    })
+}
+
+fn permission_denied() -> Value<'static> {
+    io_error_value(&io::Error::from(ErrorKind::PermissionDenied))
+}
+
+fn denied() -> DataFunc {
+    DataFunc::new(|stack, _table| {
+        get_arg_vec(stack);
+        stack.pop_terminator().ok_or_else(sig_error)?;
+        stack.push_value(permission_denied()).map_err(|_| overflow_error())
+    })
+}
+
+fn printer(sink: Sink, end: &'static str) -> DataFunc {
+    DataFunc::new(move |stack, table| {
+        let mut sink = sink.lock().unwrap_or_else(|e| e.into_inner());
+        print_to(&mut *sink, end, stack, table)
+    })
+}
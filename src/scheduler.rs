@@ -0,0 +1,743 @@
+// Lightweight processes, reached with system(:spawn), :send, :receive and :self_pid.
+// Every process is its own Context over the shared code with a mailbox and a pid (an int, main is 0).
+// The natives only check their args and yield, the scheduler then finishes the call on the process that made it.
+// Messages (and spawn args) are deep copied, native data funcs are shared since they are Send + Sync.
+//
+// spawn(f, args) -> pid, send(pid, msg) -> :ok (even if pid is gone),
+// receive(timeout_ms) -> the oldest message or :timeout (nil waits forever), self_pid() -> pid.
+// The program ends when main does, a process that errors just stops.
+//
+// link(pid) -> :ok, when either side errors the other gets [:exit, pid, reason] (reason is the error message).
+// monitor(pid) -> :ok, when pid ends for any reason we get [:down, pid, reason] (:normal if it returned).
+// Either on a pid that already ended gives reason :noproc right away.
+// supervise(:one_for_one or :one_for_all, max_restarts, child...) -> pid of a supervisor linked to the caller.
+// It runs every child (a function with no args) and when one errors restarts it, or with :one_for_all
+// kills the others (reason :killed) and restarts them all. A child returning is not restarted.
+// After max_restarts, or an error in a linked process that is not a child, it kills its children
+// and ends with reason :shutdown.
+
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use ast::ast::StringTable;
+use ast::get_id;
+use ast::id::*;
+
+use crate::basic_ops::{get_arg_vec, pop_args, push};
+#[cfg(test)]
+use crate::basic_ops::text;
+use crate::reporting::*;
+use crate::stack::ValueStack;
+use crate::system::{array_items, make_array};
+use crate::value::{Value, VarTable};
+use crate::vm::{Context, FuncData};
+
+//ops a process runs before the next one gets a turn
+pub const SLICE: usize = 1_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    //one thread taking turns in spawn order, the same program always interleaves the same way
+    RoundRobin,
+    //this many worker threads share the processes
+    Threads(usize),
+}
+
+// puts the checked args back for whoever handles the yield
+pub(crate) fn yield_with<'code>(atom: u32, args: &[Value<'code>], stack: &mut ValueStack<'code>) -> Result<(), ErrList> {
+    stack.push_terminator().map_err(|_| overflow_error())?;
+    for arg in args {
+        push(stack, arg.clone())?;
+    }
+    Err(yield_error(atom))
+}
+
+pub fn spawn_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+    let args = pop_args::<2>(stack)?;
+    match &args {
+        [Value::Func(_) | Value::WeakFunc(_), Value::DataFunc(_)] => yield_with(get_id!(":spawn"), &args, stack),
+        _ => Err(sig_error()),
+    }
+}
+
+pub fn send_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+    let args = pop_args::<2>(stack)?;
+    match &args {
+        [Value::Int(_), _] => yield_with(get_id!(":send"), &args, stack),
+        _ => Err(sig_error()),
+    }
+}
+
+pub fn receive_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+    let args = pop_args::<1>(stack)?;
+    match &args {
+        [Value::Int(0..) | Value::Nil] => yield_with(get_id!(":receive"), &args, stack),
+        _ => Err(sig_error()),
+    }
+}
+
+pub fn self_pid_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+    pop_args::<0>(stack)?;
+    yield_with(get_id!(":self_pid"), &[], stack)
+}
+
+pub fn link_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+    let args = pop_args::<1>(stack)?;
+    match &args {
+        [Value::Int(_)] => yield_with(get_id!(":link"), &args, stack),
+        _ => Err(sig_error()),
+    }
+}
+
+pub fn monitor_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+    let args = pop_args::<1>(stack)?;
+    match &args {
+        [Value::Int(_)] => yield_with(get_id!(":monitor"), &args, stack),
+        _ => Err(sig_error()),
+    }
+}
+
+pub fn supervise_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+    let mut args = get_arg_vec(stack);
+    args.reverse();
+    stack.pop_terminator().ok_or_else(sig_error)?;
+    let [Value::Atom(get_id!(":one_for_one") | get_id!(":one_for_all")), Value::Int(0..), children @ ..] = args.as_slice() else {
+        return Err(sig_error());
+    };
+    for child in children {
+        if func_of(child)?.num_args != 0 {
+            return Err(sig_error());
+        }
+    }
+    yield_with(get_id!(":supervise"), &args, stack)
+}
+
+// a copy sharing nothing but code and native data funcs
+pub fn deep_copy<'code>(value: &Value<'code>) -> Value<'code> {
+    match value {
+        Value::String(s) => Value::String(Arc::new(s.to_string().into())),
+        Value::Bytes(b) => Value::Bytes(b.iter().copied().collect()),
+        Value::Func(f) => {
+            let mut vars = f.vars.clone();
+            for v in vars.data.iter_mut().flatten() {
+                *v = deep_copy(v);
+            }
+            Value::Func(Arc::new(FuncData::new(vars, f.mut_vars_template, f.code, f.num_args)))
+        }
+        _ => value.clone(),
+    }
+}
+
+fn func_of<'code>(value: &Value<'code>) -> Result<Arc<FuncData<'code>>, ErrList> {
+    match value {
+        Value::Func(f) => Ok(f.clone()),
+        Value::WeakFunc(f) => f.upgrade().ok_or_else(|| bug_error("weak function failed to upgrade")),
+        _ => Err(sig_error()),
+    }
+}
+
+// [kind, pid, reason] as sent for links and monitors
+fn notice(kind: u32, pid: i64, reason: Value<'static>) -> Value<'static> {
+    Value::DataFunc(make_array(vec![Value::Atom(kind), Value::Int(pid), reason]))
+}
+
+struct Process<'code> {
+    pid: i64,
+    context: Box<Context<'code>>,
+    //set while blocked in :receive, with when to give up (None waits forever)
+    receiving: Option<Option<Instant>>,
+}
+
+enum Turn<'code> {
+    Done(Value<'code>),
+    Failed(ErrList),
+    Yielded(u32),
+    Paused,
+}
+
+fn run_slice<'code>(context: &mut Context<'code>) -> Turn<'code> {
+    for _ in 0..SLICE {
+        match context.next_op() {
+            Ok(true) => {}
+            Ok(false) => {
+                return match context.stack.pop_value() {
+                    Some(value) => Turn::Done(value),
+                    None => Turn::Failed(bug_error("over poping")),
+                }
+            }
+            Err(err) => {
+                return match yielded(&err) {
+                    Some(atom) => Turn::Yielded(atom),
+                    None => Turn::Failed(err),
+                }
+            }
+        }
+    }
+    Turn::Paused
+}
+
+struct Supervisor<'code> {
+    strategy: u32,
+    //the function each child runs and the pid running it now
+    children: Vec<(Arc<FuncData<'code>>, i64)>,
+    restarts_left: i64,
+}
+
+struct Shared<'code> {
+    queue: VecDeque<Process<'code>>,
+    mailboxes: HashMap<i64, VecDeque<Value<'code>>>,
+    next_pid: i64,
+    //processes a worker is running right now
+    running: usize,
+    result: Option<Result<Value<'code>, ErrList>>,
+
+    //sets so exits are sent in the same order every run
+    links: HashMap<i64, BTreeSet<i64>>,
+    monitors: HashMap<i64, Vec<i64>>,
+    supervisors: HashMap<i64, Supervisor<'code>>,
+    //ended while a worker was running them, dropped once they are back
+    killed: HashSet<i64>,
+}
+
+impl<'code> Shared<'code> {
+    fn new_pid(&mut self) -> i64 {
+        self.next_pid += 1;
+        self.next_pid - 1
+    }
+
+    fn add(&mut self, pid: i64, context: Box<Context<'code>>) {
+        self.mailboxes.insert(pid, VecDeque::new());
+        self.queue.push_back(Process { pid, context, receiving: None });
+    }
+
+    fn alive(&self, pid: i64) -> bool {
+        self.mailboxes.contains_key(&pid) || self.supervisors.contains_key(&pid)
+    }
+
+    fn post(&mut self, pid: i64, message: Value<'code>) {
+        if let Some(mailbox) = self.mailboxes.get_mut(&pid) {
+            mailbox.push_back(message);
+        }
+    }
+
+    fn link(&mut self, a: i64, b: i64) {
+        self.links.entry(a).or_default().insert(b);
+        self.links.entry(b).or_default().insert(a);
+    }
+
+    fn unlink(&mut self, a: i64, b: i64) {
+        for (x, y) in [(a, b), (b, a)] {
+            if let Some(links) = self.links.get_mut(&x) {
+                links.remove(&y);
+            }
+        }
+    }
+
+    fn ready(&self, process: &Process, now: Instant) -> bool {
+        match process.receiving {
+            None => true,
+            Some(deadline) => {
+                deadline.is_some_and(|t| t <= now) || self.mailboxes.get(&process.pid).is_some_and(|m| !m.is_empty())
+            }
+        }
+    }
+
+    // ends a blocked receive with the next message or :timeout
+    fn deliver(&mut self, process: &mut Process<'code>) -> Result<(), ErrList> {
+        if process.receiving.take().is_none() {
+            return Ok(());
+        }
+        let message = self.mailboxes.get_mut(&process.pid).and_then(VecDeque::pop_front);
+        push(&mut process.context.stack, message.unwrap_or(Value::Atom(get_id!(":timeout"))))
+    }
+
+}
+
+struct Scheduler<'code> {
+    globals: &'code VarTable<'code>,
+    table: &'code StringTable<'code>,
+    shared: Mutex<Shared<'code>>,
+    wake: Condvar,
+}
+
+impl<'code> Scheduler<'code> {
+    fn lock(&self) -> MutexGuard<'_, Shared<'code>> {
+        self.shared.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    // a new process running func(args), a call with the wrong args is the spawner's error
+    fn launch(&self, shared: &mut Shared<'code>, func: Arc<FuncData<'code>>, args: &[Value<'code>]) -> Result<i64, ErrList> {
+        let mut context = Box::new(Context::new(func, self.globals, self.table));
+        for arg in args {
+            push(&mut context.stack, deep_copy(arg))?;
+        }
+        context.start()?;
+        let pid = shared.new_pid();
+        shared.add(pid, context);
+        Ok(pid)
+    }
+
+    // tells everyone watching pid that it ended, links only hear about errors
+    fn exit(&self, shared: &mut Shared<'code>, pid: i64, reason: Value<'static>, crashed: bool) {
+        shared.mailboxes.remove(&pid);
+        for other in shared.links.remove(&pid).unwrap_or_default() {
+            shared.unlink(other, pid);
+            if !crashed {
+                continue;
+            }
+            match shared.supervisors.contains_key(&other) {
+                true => self.child_crashed(shared, other, pid),
+                false => shared.post(other, notice(get_id!(":exit"), pid, reason.clone())),
+            }
+        }
+        for watcher in shared.monitors.remove(&pid).unwrap_or_default() {
+            shared.post(watcher, notice(get_id!(":down"), pid, reason.clone()));
+        }
+    }
+
+    fn kill(&self, shared: &mut Shared<'code>, pid: i64) {
+        if shared.supervisors.contains_key(&pid) {
+            return self.shutdown(shared, pid);
+        }
+        if !shared.mailboxes.contains_key(&pid) {
+            return;
+        }
+        match shared.queue.iter().position(|p| p.pid == pid) {
+            Some(i) => drop(shared.queue.remove(i)),
+            None => drop(shared.killed.insert(pid)),
+        }
+        self.exit(shared, pid, Value::Atom(get_id!(":killed")), true);
+    }
+
+    fn shutdown(&self, shared: &mut Shared<'code>, sup: i64) {
+        let Some(supervisor) = shared.supervisors.remove(&sup) else {
+            return;
+        };
+        for (_, pid) in supervisor.children {
+            shared.unlink(sup, pid);
+            self.kill(shared, pid);
+        }
+        self.exit(shared, sup, Value::Atom(get_id!(":shutdown")), true);
+    }
+
+    fn child_crashed(&self, shared: &mut Shared<'code>, sup: i64, pid: i64) {
+        let Some(supervisor) = shared.supervisors.get_mut(&sup) else {
+            return;
+        };
+        let Some(i) = supervisor.children.iter().position(|(_, child)| *child == pid) else {
+            return self.shutdown(shared, sup);
+        };
+        if supervisor.restarts_left == 0 {
+            return self.shutdown(shared, sup);
+        }
+        supervisor.restarts_left -= 1;
+
+        let restart = match supervisor.strategy {
+            get_id!(":one_for_all") => 0..supervisor.children.len(),
+            _ => i..i + 1,
+        };
+        let mut children = std::mem::take(&mut supervisor.children);
+        for (_, child) in &children[restart.clone()] {
+            shared.unlink(sup, *child);
+            self.kill(shared, *child);
+        }
+        // every child already started once with no args so starting it again cannot fail
+        for (func, child) in &mut children[restart] {
+            if let Ok(pid) = self.launch(shared, func.clone(), &[]) {
+                *child = pid;
+                shared.link(sup, pid);
+            }
+        }
+        if let Some(supervisor) = shared.supervisors.get_mut(&sup) {
+            supervisor.children = children;
+        }
+    }
+
+    // finishes the call a process yielded on (or leaves it blocked in :receive)
+    fn serve(&self, shared: &mut Shared<'code>, process: &mut Process<'code>, atom: u32) -> Result<(), ErrList> {
+        let stack = &mut process.context.stack;
+        let mut args = get_arg_vec(stack);
+        args.reverse();
+        stack.pop_terminator().ok_or_else(sig_error)?;
+
+        let value = match (atom, args.as_slice()) {
+            (get_id!(":spawn"), [f, list]) => {
+                let func = func_of(f)?;
+                let args = array_items(list)?;
+                Value::Int(self.launch(shared, func, &args)?)
+            }
+            (get_id!(":send"), [Value::Int(pid), message]) => {
+                shared.post(*pid, deep_copy(message));
+                Value::Atom(get_id!(":ok"))
+            }
+            (get_id!(":receive"), [timeout]) => {
+                let deadline = match timeout {
+                    Value::Int(ms) => Some(Instant::now() + Duration::from_millis(*ms as u64)),
+                    _ => None,
+                };
+                match shared.mailboxes.get_mut(&process.pid).and_then(VecDeque::pop_front) {
+                    Some(message) => message,
+                    None => {
+                        process.receiving = Some(deadline);
+                        return Ok(());
+                    }
+                }
+            }
+            (get_id!(":self_pid"), []) => Value::Int(process.pid),
+            (get_id!(":link"), [Value::Int(pid)]) => {
+                match shared.alive(*pid) {
+                    true if *pid != process.pid => shared.link(process.pid, *pid),
+                    true => {}
+                    false => shared.post(process.pid, notice(get_id!(":exit"), *pid, Value::Atom(get_id!(":noproc")))),
+                }
+                Value::Atom(get_id!(":ok"))
+            }
+            (get_id!(":monitor"), [Value::Int(pid)]) => {
+                match shared.alive(*pid) {
+                    true => shared.monitors.entry(*pid).or_default().push(process.pid),
+                    false => shared.post(process.pid, notice(get_id!(":down"), *pid, Value::Atom(get_id!(":noproc")))),
+                }
+                Value::Atom(get_id!(":ok"))
+            }
+            (get_id!(":supervise"), [Value::Atom(strategy), Value::Int(max), funcs @ ..]) => {
+                let sup = shared.new_pid();
+                let mut children = Vec::new();
+                for func in funcs {
+                    let func = func_of(func)?;
+                    let pid = self.launch(shared, func.clone(), &[])?;
+                    shared.link(sup, pid);
+                    children.push((func, pid));
+                }
+                shared.supervisors.insert(sup, Supervisor { strategy: *strategy, children, restarts_left: *max });
+                shared.link(sup, process.pid);
+                Value::Int(sup)
+            }
+            (get_id!(":spawn") | get_id!(":send") | get_id!(":receive") | get_id!(":self_pid"), _) => return Err(sig_error()),
+            (get_id!(":link") | get_id!(":monitor") | get_id!(":supervise"), _) => return Err(sig_error()),
+            _ => return Err(yield_error(atom)),
+        };
+        push(stack, value)
+    }
+
+    fn work(&self) {
+        let mut shared = self.lock();
+        loop {
+            if shared.result.is_some() {
+                self.wake.notify_all();
+                return;
+            }
+
+            let now = Instant::now();
+            let Some(i) = shared.queue.iter().position(|p| shared.ready(p, now)) else {
+                if shared.running == 0 && shared.queue.iter().all(|p| p.receiving == Some(None)) {
+                    shared.result = Some(Err(deadlock_error()));
+                    continue;
+                }
+                let nearest = shared.queue.iter().filter_map(|p| p.receiving.flatten()).min();
+                shared = match nearest {
+                    Some(t) => self.wake.wait_timeout(shared, t.saturating_duration_since(now)).unwrap_or_else(|e| e.into_inner()).0,
+                    None => self.wake.wait(shared).unwrap_or_else(|e| e.into_inner()),
+                };
+                continue;
+            };
+
+            let mut process = shared.queue.remove(i).expect("found above");
+            let turn = match shared.deliver(&mut process) {
+                Ok(()) => {
+                    shared.running += 1;
+                    drop(shared);
+                    let turn = run_slice(&mut process.context);
+                    shared = self.lock();
+                    shared.running -= 1;
+                    if shared.killed.remove(&process.pid) {
+                        self.wake.notify_all();
+                        continue;
+                    }
+                    turn
+                }
+                Err(err) => Turn::Failed(err),
+            };
+
+            let turn = match turn {
+                Turn::Yielded(atom) => match self.serve(&mut shared, &mut process, atom) {
+                    Ok(()) => Turn::Paused,
+                    Err(err) => Turn::Failed(err),
+                },
+                turn => turn,
+            };
+            let result = match turn {
+                Turn::Done(value) => {
+                    self.exit(&mut shared, process.pid, Value::Atom(get_id!(":normal")), false);
+                    Ok(value)
+                }
+                Turn::Failed(err) => {
+                    let reason = Value::String(Arc::new(error_message(&err, self.table).into()));
+                    self.exit(&mut shared, process.pid, reason, true);
+                    Err(err)
+                }
+                _ => {
+                    shared.queue.push_back(process);
+                    self.wake.notify_all();
+                    continue;
+                }
+            };
+            if process.pid == 0 {
+                shared.result = Some(result);
+            }
+            self.wake.notify_all();
+        }
+    }
+}
+
+// runs main(args) as process 0 until it returns
+pub fn run<'code, 'a: 'code>(
+    main: Arc<FuncData<'code>>,
+    args: impl IntoIterator<Item = Value<'a>>,
+    globals: &'code VarTable<'code>,
+    table: &'code StringTable<'code>,
+    mode: Mode,
+) -> Result<Value<'code>, ErrList> {
+    let mut context = Box::new(Context::new(main, globals, table));
+    for arg in args {
+        push(&mut context.stack, arg)?;
+    }
+    context.start()?;
+
+    let scheduler = Scheduler {
+        globals,
+        table,
+        shared: Mutex::new(Shared {
+            queue: VecDeque::new(),
+            mailboxes: HashMap::new(),
+            next_pid: 0,
+            running: 0,
+            result: None,
+            links: HashMap::new(),
+            monitors: HashMap::new(),
+            supervisors: HashMap::new(),
+            killed: HashSet::new(),
+        }),
+        wake: Condvar::new(),
+    };
+    {
+        let mut shared = scheduler.lock();
+        let pid = shared.new_pid();
+        shared.add(pid, context);
+    }
+
+    match mode {
+        Mode::RoundRobin => scheduler.work(),
+        Mode::Threads(n) => thread::scope(|s| {
+            for _ in 0..n.max(1) {
+                s.spawn(|| scheduler.work());
+            }
+        }),
+    }
+    let result = scheduler.lock().result.take();
+    result.unwrap_or_else(|| Err(bug_error("scheduler stopped before main")))
+}
+
+#[test]
+fn test_deep_copy() {
+    let s: Arc<crate::fstring::FString> = Arc::new(String::from("shared").into());
+    let copy = deep_copy(&Value::String(s.clone()));
+    let Value::String(c) = &copy else { panic!() };
+    assert!(!Arc::ptr_eq(&s, c));
+    assert_eq!(copy, Value::String(s));
+    assert_eq!(deep_copy(&Value::Int(3)), Value::Int(3));
+}
+
+// runs name(system) under the scheduler and gives what it returned to check
+#[cfg(test)]
+fn run_main<'a>(code: &'a crate::runtime::Code, name: &str, mode: Mode, check: impl FnOnce(Value) + 'a) {
+    let system = Value::StaticFunc(crate::system::system);
+    code.run_processes(name, vec![system], mode, check).unwrap_or_else(|e| panic!("{} failed with {:?}", name, e));
+}
+
+#[cfg(test)]
+const HELPERS: &str = r#"
+def no_args(system) {
+    system(:string)(:split)('', '')
+}
+
+#reports its pid then errors when told to
+def child(system, parent) {
+    system(:send)(parent, system(:self_pid)());
+    match system(:receive)(nil) {
+        :crash => 1 / 0,
+        _ => :done
+    }
+}
+"#;
+
+#[test]
+fn test_messages() {
+    let source = HELPERS.to_string()
+        + r#"
+def echo(send, receive, parent, count) {
+    match count {
+        0 => :done,
+        _ => {
+            send(parent, receive(nil) + '!');
+            self(send, receive, parent, count - 1)
+        }
+    }
+}
+
+def count(n) {
+    match n {
+        0 => 'counted',
+        _ => self(n - 1)
+    }
+}
+
+def pids(system) {
+    system(:self_pid)() * 10 + system(:spawn)(fn() -> {:ok}, no_args(system))
+}
+
+def echoes(system) {
+    me = system(:self_pid)();
+    echoer = system(:spawn)(fn() -> {echo(system(:send), system(:receive), me, 2)}, no_args(system));
+    system(:send)(echoer, 'a');
+    system(:send)(echoer, 'b');
+    system(:receive)(nil) + system(:receive)(nil)
+}
+
+#the long count is paused many times but still answers after the short one
+def turns(system) {
+    me = system(:self_pid)();
+    system(:spawn)(fn() -> {system(:send)(me, count(5000))}, no_args(system));
+    system(:spawn)(fn(a, b) -> {system(:send)(me, a + b)}, system(:string)(:split)('x|y', '|'));
+    system(:receive)(nil) + system(:receive)(nil)
+}
+
+def after_crash(system) {
+    system(:spawn)(fn() -> {1 / 0}, no_args(system));
+    system(:receive)(10)
+}
+"#;
+    let code = crate::translate::compile_source_to_code(&source);
+    for mode in [Mode::RoundRobin, Mode::Threads(4)] {
+        run_main(&code, "pids", mode, |v| assert_eq!(v, Value::Int(1), "{:?}", mode));
+        run_main(&code, "echoes", mode, |v| assert_eq!(v, text("a!b!"), "{:?}", mode));
+        run_main(&code, "turns", mode, |v| assert_eq!(v, text("xycounted"), "{:?}", mode));
+        run_main(&code, "after_crash", mode, |v| assert_eq!(v, Value::Atom(get_id!(":timeout")), "{:?}", mode));
+    }
+}
+
+#[test]
+fn test_spawn_wrong_args() {
+    let source = HELPERS.to_string()
+        + r#"
+def wrong_args(system) {
+    system(:spawn)(fn(a) -> {a}, no_args(system))
+}
+"#;
+    let code = crate::translate::compile_source_to_code(&source);
+    let system = Value::StaticFunc(crate::system::system);
+    for mode in [Mode::RoundRobin, Mode::Threads(4)] {
+        let result = code.run_processes("wrong_args", vec![system.clone()], mode, |v| panic!("spawned {:?}", v));
+        assert!(result.is_err(), "{:?}", mode);
+    }
+}
+
+#[test]
+fn test_links() {
+    let source = HELPERS.to_string()
+        + r#"
+def linked_crash(system) {
+    receive = system(:receive);
+    crasher = system(:spawn)(fn() -> {1 / receive(nil)}, no_args(system));
+    system(:link)(crasher);
+    system(:send)(crasher, 0);
+    receive(nil)
+}
+
+def stop_quiet(system) {
+    quiet = system(:spawn)(fn() -> {system(:receive)(nil)}, no_args(system));
+    system(:monitor)(quiet);
+    system(:send)(quiet, :stop);
+    quiet
+}
+
+def monitored(system) {
+    stop_quiet(system);
+    system(:receive)(nil)
+}
+
+def linked_after_end(system) {
+    quiet = stop_quiet(system);
+    system(:receive)(nil);
+    system(:link)(quiet);
+    system(:receive)(nil)
+}
+"#;
+    let code = crate::translate::compile_source_to_code(&source);
+    let atom = |id| Value::Atom(id);
+    run_main(&code, "linked_crash", Mode::RoundRobin, |v| {
+        assert_eq!(array_items(&v).unwrap(), [atom(get_id!(":exit")), Value::Int(1), text("attempted to divide by zero")]);
+    });
+    run_main(&code, "monitored", Mode::RoundRobin, |v| {
+        assert_eq!(array_items(&v).unwrap(), [atom(get_id!(":down")), Value::Int(1), atom(get_id!(":normal"))]);
+    });
+    run_main(&code, "linked_after_end", Mode::RoundRobin, |v| {
+        assert_eq!(array_items(&v).unwrap(), [atom(get_id!(":exit")), Value::Int(1), atom(get_id!(":noproc"))]);
+    });
+}
+
+#[test]
+fn test_supervisors() {
+    //in round robin the supervisor is pid 1 and its children 2 and 3, restarts count up from 4
+    let source = HELPERS.to_string()
+        + r#"
+def two_children(system, strategy) {
+    me = system(:self_pid)();
+    start = fn() -> {child(system, me)};
+    system(:supervise)(strategy, 5, start, start);
+    system(:receive)(nil);
+    system(:receive)(nil)
+}
+
+def one_for_one_restart(system) {
+    two_children(system, :one_for_one);
+    system(:send)(2, :crash);
+    system(:receive)(nil)
+}
+
+def one_for_one_normal_end(system) {
+    two_children(system, :one_for_one);
+    system(:send)(3, :stop);
+    system(:receive)(10)
+}
+
+def one_for_all_kill(system) {
+    two_children(system, :one_for_all);
+    system(:monitor)(3);
+    system(:send)(2, :crash);
+    system(:receive)(nil)
+}
+
+def one_for_all_restart(system) {
+    one_for_all_kill(system);
+    system(:receive)(nil) * 10 + system(:receive)(nil)
+}
+
+def give_up(system) {
+    system(:supervise)(:one_for_one, 2, fn() -> {1 / 0});
+    system(:receive)(nil)
+}
+"#;
+    let code = crate::translate::compile_source_to_code(&source);
+    let atom = |id| Value::Atom(id);
+    run_main(&code, "one_for_one_restart", Mode::RoundRobin, |v| assert_eq!(v, Value::Int(4)));
+    run_main(&code, "one_for_one_normal_end", Mode::RoundRobin, |v| assert_eq!(v, atom(get_id!(":timeout"))));
+    run_main(&code, "one_for_all_kill", Mode::RoundRobin, |v| {
+        assert_eq!(array_items(&v).unwrap(), [atom(get_id!(":down")), Value::Int(3), atom(get_id!(":killed"))]);
+    });
+    run_main(&code, "one_for_all_restart", Mode::RoundRobin, |v| assert_eq!(v, Value::Int(45)));
+    run_main(&code, "give_up", Mode::RoundRobin, |v| {
+        assert_eq!(array_items(&v).unwrap(), [atom(get_id!(":exit")), Value::Int(1), atom(get_id!(":shutdown"))]);
+    });
+}
@@ -8,6 +8,7 @@ use core::marker::PhantomData;
 use std::sync::Weak;
 use crate::vm::FuncData;
 use std::sync::Arc;
+use crate::fstring::FString;
 use crate::value::Value;
 use core::ptr;
 use std::mem::{MaybeUninit, size_of};
@@ -220,7 +221,8 @@ pub enum ValueTag{
     WeakFunc=8,
     StaticFunc=9,
     DataFunc=10,
-    
+    Bytes=11,
+    Stream=12,
 }
 
 
@@ -295,6 +297,18 @@ impl<'code,const STACK_CAPACITY:usize> ValueStack<'code, STACK_CAPACITY> {
                     std::mem::forget(aligned_value); //stack has sucessfully took ownership of the value
                     self.stack.push(&Aligned::new(ValueTag::DataFunc))
                 },
+                Value::Bytes(b) => {
+                    let aligned_value = Aligned::new(b);
+                    self.stack.push(&aligned_value)?;
+                    std::mem::forget(aligned_value); //stack has sucessfully took ownership of the value
+                    self.stack.push(&Aligned::new(ValueTag::Bytes))
+                },
+                Value::Stream(s) => {
+                    let aligned_value = Aligned::new(s);
+                    self.stack.push(&aligned_value)?;
+                    std::mem::forget(aligned_value); //stack has sucessfully took ownership of the value
+                    self.stack.push(&Aligned::new(ValueTag::Stream))
+                },
             }
         }
     }
@@ -319,6 +333,8 @@ impl<'code,const STACK_CAPACITY:usize> ValueStack<'code, STACK_CAPACITY> {
                 
                 ValueTag::StaticFunc => Some(Value::StaticFunc(self.stack.pop()?.to_inner())),
                 ValueTag::DataFunc => Some(Value::DataFunc(self.stack.pop()?.to_inner())),
+                ValueTag::Bytes => Some(Value::Bytes(self.stack.pop()?.to_inner())),
+                ValueTag::Stream => Some(Value::Stream(self.stack.pop()?.to_inner())),
 
 
                 ValueTag::Terminator => {
@@ -345,6 +361,8 @@ impl<'code,const STACK_CAPACITY:usize> ValueStack<'code, STACK_CAPACITY> {
                 
                 ValueTag::StaticFunc => Some(Value::StaticFunc(self.stack.pop()?.to_inner())),
                 ValueTag::DataFunc => Some(Value::DataFunc(self.stack.pop()?.to_inner())),
+                ValueTag::Bytes => Some(Value::Bytes(self.stack.pop()?.to_inner())),
+                ValueTag::Stream => Some(Value::Stream(self.stack.pop()?.to_inner())),
 
 
                 ValueTag::Terminator => None
@@ -468,7 +486,7 @@ impl<'code,const STACK_CAPACITY:usize> ValueStack<'code, STACK_CAPACITY> {
     }
 
     #[inline]
-    pub fn push_string(&mut self, s: Arc<String>) -> Result<(), StackOverflow> {
+    pub fn push_string(&mut self, s: Arc<FString>) -> Result<(), StackOverflow> {
         unsafe {
             let aligned_value = Aligned::new(s);
             self.stack.push(&aligned_value)?;
@@ -478,10 +496,30 @@ impl<'code,const STACK_CAPACITY:usize> ValueStack<'code, STACK_CAPACITY> {
     }
 
     #[inline]
-    pub fn pop_string(&mut self) -> Option<Arc<String>> {
+    pub fn pop_string(&mut self) -> Option<Arc<FString>> {
         if self.peak_tag()? == ValueTag::String {
             self.stack.len -= std::mem::size_of::<Aligned<ValueTag>>();
-            Some(unsafe { self.stack.pop::<Arc<String>>()?.to_inner() })
+            Some(unsafe { self.stack.pop::<Arc<FString>>()?.to_inner() })
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    pub fn push_bytes(&mut self, b: Arc<[u8]>) -> Result<(), StackOverflow> {
+        unsafe {
+            let aligned_value = Aligned::new(b);
+            self.stack.push(&aligned_value)?;
+            std::mem::forget(aligned_value);
+            self.stack.push(&Aligned::new(ValueTag::Bytes))
+        }
+    }
+
+    #[inline]
+    pub fn pop_bytes(&mut self) -> Option<Arc<[u8]>> {
+        if self.peak_tag()? == ValueTag::Bytes {
+            self.stack.len -= std::mem::size_of::<Aligned<ValueTag>>();
+            Some(unsafe { self.stack.pop::<Arc<[u8]>>()?.to_inner() })
         } else {
             None
         }
@@ -588,7 +626,7 @@ fn test_stack_operations() {
 
     let mut value_stack = Box::new(ValueStack::<1_000>::new());
 
-    let data_func = Value::DataFunc(DataFunc{inner:Arc::new(|a,b| is_equal_wraped(a,b))});
+    let data_func = Value::DataFunc(DataFunc::new(is_equal_wraped));
     value_stack.push_value(data_func.clone()).unwrap();
     assert_eq!(value_stack.pop_value(),Some(data_func));
 
@@ -684,8 +722,10 @@ fn test_typed_stack_operations() {
     stack.push_int(42).unwrap();
     stack.push_float(6.9).unwrap();
     stack.push_atom(123).unwrap();
-    let s = Arc::new(String::from("Hello"));
+    let s = Arc::new(FString::from("Hello"));
     stack.push_string(s.clone()).unwrap();
+    let b: Arc<[u8]> = Arc::from(&[1u8, 2, 255][..]);
+    stack.push_bytes(b.clone()).unwrap();
     let f = Arc::new(func_data);
 
     stack.push_func(f.clone()).unwrap();
@@ -695,6 +735,7 @@ fn test_typed_stack_operations() {
     // Pop all values in reverse order
     assert!(stack.pop_weak_func().is_some()); // WeakFunc
     assert!(matches!(stack.pop_func(), Some(func) if Arc::ptr_eq(&func, &f))); // Func
+    assert_eq!(stack.pop_bytes(), Some(b)); // Bytes
     assert_eq!(stack.pop_string(), Some(s)); // String
     assert_eq!(stack.pop_atom(), Some(123)); // Atom
     assert_eq!(stack.pop_float(), Some(6.9)); // Float
@@ -0,0 +1,200 @@
+// Lazy streams, made with system(:stream)(f) and system(:read_lines)(path).
+// Values are pulled one at a time so walking a big file or an endless generator runs in constant memory.
+// system(:stream)(f) runs f(emit) in its own context on the first pull, every emit(v) hands v to the puller
+// and leaves f suspended right there until the next pull. f returning ends the stream.
+//
+// s(:next) -> the next value or nil at the end, s(:map, f) s(:filter, f) s(:take, n) -> a new stream,
+// s(:fold, init, f) -> f(..f(f(init, a), b)..) over everything left.
+// Walking a stream uses it up, a stream made from another one moves both along.
+
+use std::fmt;
+use std::io::BufRead;
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+
+use ast::ast::StringTable;
+use ast::get_id;
+use ast::id::*;
+use codespan::Span;
+
+use crate::basic_ops::{get_arg_vec, pop_args, to_bool};
+use crate::reporting::*;
+use crate::scheduler::yield_with;
+use crate::stack::ValueStack;
+use crate::system::io_error_value;
+use crate::value::Value;
+use crate::vm::{Context, FuncData};
+
+//every atom a stream answers to (used for editor completion)
+pub const STREAM_FUNCS: &[&str] = &[":next", ":map", ":filter", ":take", ":fold"];
+
+pub type StreamRef<'code> = Arc<Stream<'code>>;
+
+//a running generator lives in the context that pulls it and not in the stream,
+//so the only state a stream holds itself never borrows from the code
+pub type Generators<'code> = Vec<(Weak<Stream<'code>>, Box<Context<'code>>)>;
+
+pub enum Source<'code> {
+    Generator(Arc<FuncData<'code>>),
+    Lines(Mutex<Box<dyn BufRead + Send>>),
+    Map(StreamRef<'code>, Value<'code>),
+    Filter(StreamRef<'code>, Value<'code>),
+    Take(StreamRef<'code>, AtomicI64),
+}
+
+pub struct Stream<'code> {
+    source: Source<'code>,
+    started: AtomicBool,
+    done: AtomicBool,
+}
+
+impl fmt::Debug for Stream<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self.source {
+            Source::Generator(_) => "Generator",
+            Source::Lines(_) => "Lines",
+            Source::Map(..) => "Map",
+            Source::Filter(..) => "Filter",
+            Source::Take(..) => "Take",
+        };
+        write!(f, "Stream::{}", name)
+    }
+}
+
+fn new_stream(source: Source<'_>) -> Value<'_> {
+    Value::Stream(Arc::new(Stream { source, started: AtomicBool::new(false), done: AtomicBool::new(false) }))
+}
+
+pub fn lines_stream(reader: Box<dyn BufRead + Send>) -> Value<'static> {
+    new_stream(Source::Lines(Mutex::new(reader)))
+}
+
+// stream(f) where f takes the emit function
+pub fn stream_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+    let func = match pop_args(stack)? {
+        [Value::Func(f)] if f.num_args == 1 => f,
+        [Value::WeakFunc(f)] => f.upgrade().filter(|f| f.num_args == 1).ok_or_else(sig_error)?,
+        _ => return Err(sig_error()),
+    };
+    stack.push_value(new_stream(Source::Generator(func))).map_err(|_| overflow_error())
+}
+
+// emit(v) -> :ok once the stream is pulled again
+fn emit_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+    let args = pop_args::<1>(stack)?;
+    yield_with(get_id!(":emit"), &args, stack)
+}
+
+fn resume<'code>(generator: &mut Context<'code>) -> Result<Option<Value<'code>>, ErrList> {
+    loop {
+        match generator.next_op() {
+            Ok(true) => {}
+            Ok(false) => return Ok(None),
+            Err(err) if yielded(&err) == Some(get_id!(":emit")) => {
+                let [value] = pop_args(&mut generator.stack)?;
+                generator.stack.push_atom(get_id!(":ok")).map_err(|_| overflow_error())?;
+                return Ok(Some(value));
+            }
+            //any other yield goes out as is, the op pulling the stream reports it since a generator runs apart from its process
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+fn pull_generator<'code>(
+    stream: &StreamRef<'code>,
+    func: &Arc<FuncData<'code>>,
+    ctx: &mut Context<'code>,
+) -> Result<Option<Value<'code>>, ErrList> {
+    let found = ctx.generators.iter().position(|(s, _)| s.as_ptr() == Arc::as_ptr(stream));
+    let mut generator = match found {
+        Some(i) => ctx.generators.swap_remove(i).1,
+        //started but not here, so its generator is running right now or belongs to another context
+        None if stream.started.swap(true, Ordering::Relaxed) => return Err(sig_error()),
+        None => {
+            let mut generator = Box::new(ctx.fork(func.clone()));
+            generator.stack.push_value(Value::StaticFunc(emit_fn)).map_err(|_| overflow_error())?;
+            generator.start()?;
+            generator
+        }
+    };
+    let next = resume(&mut generator)?;
+    if next.is_some() {
+        ctx.generators.retain(|(s, _)| s.strong_count() > 0);
+        ctx.generators.push((Arc::downgrade(stream), generator));
+    }
+    Ok(next)
+}
+
+// the next value, calling back into the script through ctx for map and filter
+pub fn pull<'code>(stream: &StreamRef<'code>, ctx: &mut Context<'code>, span: Span) -> Result<Option<Value<'code>>, ErrList> {
+    if stream.done.load(Ordering::Relaxed) {
+        return Ok(None);
+    }
+    let next = match &stream.source {
+        Source::Generator(func) => pull_generator(stream, func, ctx),
+        Source::Lines(reader) => {
+            let mut reader = reader.try_lock().map_err(|_| sig_error())?;
+            let mut line = String::new();
+            match reader.read_line(&mut line) {
+                Ok(0) => Ok(None),
+                Ok(_) => {
+                    let end = line.trim_end_matches(['\n', '\r']).len();
+                    line.truncate(end);
+                    Ok(Some(Value::String(Arc::new(line.into()))))
+                }
+                //the error is the last thing the stream gives
+                Err(e) => {
+                    stream.done.store(true, Ordering::Relaxed);
+                    return Ok(Some(io_error_value(&e)));
+                }
+            }
+        }
+        Source::Map(source, f) => match pull(source, ctx, span) {
+            Ok(Some(value)) => ctx.call_value(f.clone(), &[value], span).map(Some),
+            other => other,
+        },
+        Source::Filter(source, f) => loop {
+            match pull(source, ctx, span) {
+                Ok(Some(value)) => match ctx.call_value(f.clone(), std::slice::from_ref(&value), span) {
+                    Ok(keep) if to_bool(&keep) => break Ok(Some(value)),
+                    Ok(_) => {}
+                    Err(err) => break Err(err),
+                },
+                other => break other,
+            }
+        },
+        Source::Take(source, left) => match left.fetch_sub(1, Ordering::Relaxed) > 0 {
+            true => pull(source, ctx, span),
+            false => Ok(None),
+        },
+    };
+    //a stream that ended or failed stays ended
+    if !matches!(next, Ok(Some(_))) {
+        stream.done.store(true, Ordering::Relaxed);
+    }
+    next
+}
+
+// what the VM runs when a stream is called
+pub fn call_stream<'code>(stream: &StreamRef<'code>, ctx: &mut Context<'code>, span: Span) -> Result<(), ErrList> {
+    let mut args = get_arg_vec(&mut ctx.stack);
+    args.reverse();
+    ctx.stack.pop_terminator().ok_or_else(sig_error)?;
+
+    let ans = match args.as_slice() {
+        [Value::Atom(get_id!(":next"))] => pull(stream, ctx, span)?.unwrap_or(Value::Nil),
+        [Value::Atom(get_id!(":map")), f] => new_stream(Source::Map(stream.clone(), f.clone())),
+        [Value::Atom(get_id!(":filter")), f] => new_stream(Source::Filter(stream.clone(), f.clone())),
+        [Value::Atom(get_id!(":take")), Value::Int(n)] => new_stream(Source::Take(stream.clone(), AtomicI64::new(*n))),
+        [Value::Atom(get_id!(":fold")), init, f] => {
+            let mut acc = init.clone();
+            while let Some(value) = pull(stream, ctx, span)? {
+                acc = ctx.call_value(f.clone(), &[acc, value], span)?;
+            }
+            acc
+        }
+        _ => return Err(sig_error()),
+    };
+    ctx.stack.push_value(ans).map_err(|_| overflow_error())
+}
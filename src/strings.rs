@@ -0,0 +1,270 @@
+// The string module, reached with system(:string)(:name).
+// Indexes count chars (not bytes), negative ones count from the end, and are clamped to the string.
+
+use std::sync::Arc;
+
+use ast::ast::StringTable;
+use ast::get_id;
+use ast::id::*;
+
+use crate::basic_ops::{pop_args, push, to_string_runtime};
+use crate::fstring::FString;
+use crate::reporting::*;
+use crate::stack::ValueStack;
+use crate::system::{array_items, make_array};
+use crate::value::Value;
+
+//every atom the module answers to (used for editor completion)
+pub const STRING_FUNCS: &[&str] = &[
+    ":split",
+    ":join",
+    ":slice",
+    ":find",
+    ":replace",
+    ":trim",
+    ":upper",
+    ":lower",
+    ":starts_with",
+    ":ends_with",
+    ":repeat",
+    ":char_code",
+    ":from_char_code",
+];
+
+pub fn string_module<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+    let atom = stack.pop_atom().ok_or_else(|| arg_error(1, 0))?;
+    stack.pop_terminator().ok_or_else(sig_error)?;
+
+    let func = match atom {
+        get_id!(":split") => split_fn,
+        get_id!(":join") => join_fn,
+        get_id!(":slice") => slice_fn,
+        get_id!(":find") => find_fn,
+        get_id!(":replace") => replace_fn,
+        get_id!(":trim") => trim_fn,
+        get_id!(":upper") => upper_fn,
+        get_id!(":lower") => lower_fn,
+        get_id!(":starts_with") => starts_with_fn,
+        get_id!(":ends_with") => ends_with_fn,
+        get_id!(":repeat") => repeat_fn,
+        get_id!(":char_code") => char_code_fn,
+        get_id!(":from_char_code") => from_char_code_fn,
+        _ => return Err(sig_error()),
+    };
+
+    stack.push_value(Value::StaticFunc(func)).map_err(|_| overflow_error())
+}
+
+fn push_str(stack: &mut ValueStack, s: String) -> Result<(), ErrList> {
+    stack.push_string(Arc::new(s.into())).map_err(|_| overflow_error())
+}
+
+// byte offset of a char index, clamped to the string
+fn byte_index(s: &FString, index: i64) -> usize {
+    let index = s.resolve_index(index).unwrap_or(0).min(s.char_len());
+    s.byte_offset(index).unwrap_or(s.len())
+}
+
+// split(s, sep) with an empty sep splits into chars
+pub fn split_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+    let [Value::String(s), Value::String(sep)] = pop_args(stack)? else {
+        return Err(sig_error());
+    };
+
+    let parts: Vec<Value> = match sep.is_empty() {
+        true => s.chars().map(|c| Value::String(Arc::new(c.to_string().into()))).collect(),
+        false => s.split(sep.as_str()).map(|p| Value::String(Arc::new(p.into()))).collect(),
+    };
+    push(stack, Value::DataFunc(make_array(parts)))
+}
+
+// join(array, sep) works on anything made by make_array, values are turned into strings
+pub fn join_fn<'code>(stack: &mut ValueStack<'code>, table: &StringTable<'code>) -> Result<(), ErrList> {
+    let [array, Value::String(sep)] = pop_args(stack)? else {
+        return Err(sig_error());
+    };
+
+    let parts: Vec<String> = array_items(&array)?
+        .iter()
+        .map(|v| to_string_runtime(v, table))
+        .collect();
+    push_str(stack, parts.join(sep.as_str()))
+}
+
+// slice(s, start, end) from start up to (not including) end
+pub fn slice_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+    let [Value::String(s), Value::Int(start), Value::Int(end)] = pop_args(stack)? else {
+        return Err(sig_error());
+    };
+
+    let start = byte_index(&s, start);
+    let end = byte_index(&s, end).max(start);
+    push_str(stack, s[start..end].to_string())
+}
+
+// find(s, sub) gives the char index of the first match or nil
+pub fn find_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+    let [Value::String(s), Value::String(sub)] = pop_args(stack)? else {
+        return Err(sig_error());
+    };
+
+    match s.find(sub.as_str()) {
+        Some(i) => push(stack, Value::Int(s[..i].chars().count() as i64)),
+        None => push(stack, Value::Nil),
+    }
+}
+
+pub fn replace_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+    let [Value::String(s), Value::String(from), Value::String(to)] = pop_args(stack)? else {
+        return Err(sig_error());
+    };
+    if from.is_empty() {
+        return Err(sig_error());
+    }
+
+    push_str(stack, s.replace(from.as_str(), &to))
+}
+
+pub fn trim_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+    let [Value::String(s)] = pop_args(stack)? else {
+        return Err(sig_error());
+    };
+    push_str(stack, s.trim().to_string())
+}
+
+pub fn upper_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+    let [Value::String(s)] = pop_args(stack)? else {
+        return Err(sig_error());
+    };
+    push_str(stack, s.to_uppercase())
+}
+
+pub fn lower_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+    let [Value::String(s)] = pop_args(stack)? else {
+        return Err(sig_error());
+    };
+    push_str(stack, s.to_lowercase())
+}
+
+pub fn starts_with_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+    let [Value::String(s), Value::String(prefix)] = pop_args(stack)? else {
+        return Err(sig_error());
+    };
+    push(stack, Value::Bool(s.starts_with(prefix.as_str())))
+}
+
+pub fn ends_with_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+    let [Value::String(s), Value::String(suffix)] = pop_args(stack)? else {
+        return Err(sig_error());
+    };
+    push(stack, Value::Bool(s.ends_with(suffix.as_str())))
+}
+
+// repeat(s, n) gives :err when the result would not fit in memory
+pub fn repeat_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+    let [Value::String(s), Value::Int(n)] = pop_args(stack)? else {
+        return Err(sig_error());
+    };
+    let Ok(n) = usize::try_from(n) else {
+        return Err(sig_error());
+    };
+    let mut out = String::new();
+    match s.len().checked_mul(n).map(|len| out.try_reserve_exact(len)) {
+        Some(Ok(())) => {
+            (0..n).for_each(|_| out.push_str(&s));
+            push_str(stack, out)
+        }
+        _ => push(stack, Value::Atom(get_id!(":err"))),
+    }
+}
+
+// char_code(s) is the code point of the first char (nil for an empty string)
+pub fn char_code_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+    let [Value::String(s)] = pop_args(stack)? else {
+        return Err(sig_error());
+    };
+    match s.chars().next() {
+        Some(c) => push(stack, Value::Int(c as i64)),
+        None => push(stack, Value::Nil),
+    }
+}
+
+pub fn from_char_code_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+    let [Value::Int(code)] = pop_args(stack)? else {
+        return Err(sig_error());
+    };
+    match u32::try_from(code).ok().and_then(char::from_u32) {
+        Some(c) => push_str(stack, c.to_string()),
+        None => push(stack, Value::Atom(get_id!(":err"))),
+    }
+}
+
+#[cfg(test)]
+use crate::basic_ops::{call_native, text};
+
+#[cfg(test)]
+fn texts(parts: &[&str]) -> Value<'static> {
+    Value::DataFunc(make_array(parts.iter().map(|s| text(s)).collect()))
+}
+
+#[test]
+fn test_split() {
+    let parts = call_native(split_fn, [text("a,b,,c"), text(",")]).unwrap();
+    assert_eq!(array_items(&parts).unwrap(), array_items(&texts(&["a", "b", "", "c"])).unwrap());
+    let chars = call_native(split_fn, [text("héllo"), text("")]).unwrap();
+    assert_eq!(array_items(&chars).unwrap(), array_items(&texts(&["h", "é", "l", "l", "o"])).unwrap());
+}
+
+#[test]
+fn test_join() {
+    assert_eq!(call_native(join_fn, [texts(&["a", "b", "", "c"]), text("-")]).unwrap(), text("a-b--c"));
+    assert_eq!(call_native(join_fn, [texts(&[]), text("-")]).unwrap(), text(""));
+    let mixed = Value::DataFunc(make_array(vec![Value::Int(1), Value::Nil, text("x")]));
+    assert_eq!(call_native(join_fn, [mixed, text("+")]).unwrap(), text("1+nil+x"));
+}
+
+#[test]
+fn test_slice() {
+    assert_eq!(call_native(slice_fn, [text("héllo"), Value::Int(1), Value::Int(3)]).unwrap(), text("él"));
+    assert_eq!(call_native(slice_fn, [text("abc"), Value::Int(2), Value::Int(100)]).unwrap(), text("c"));
+    assert_eq!(call_native(slice_fn, [text("abc"), Value::Int(2), Value::Int(1)]).unwrap(), text(""));
+    assert_eq!(call_native(slice_fn, [text("héllo"), Value::Int(-4), Value::Int(-1)]).unwrap(), text("éll"));
+}
+
+#[test]
+fn test_find() {
+    assert_eq!(call_native(find_fn, [text("héllo"), text("llo")]).unwrap(), Value::Int(2));
+    assert_eq!(call_native(find_fn, [text("abc"), text("x")]).unwrap(), Value::Nil);
+}
+
+#[test]
+fn test_edits() {
+    assert_eq!(call_native(replace_fn, [text("a-b-c"), text("-"), text("+")]).unwrap(), text("a+b+c"));
+    assert!(call_native(replace_fn, [text("abc"), text(""), text("+")]).is_err());
+    assert_eq!(call_native(trim_fn, [text("  x y ")]).unwrap(), text("x y"));
+    assert_eq!(call_native(upper_fn, [text("abc")]).unwrap(), text("ABC"));
+    assert_eq!(call_native(lower_fn, [text("ÀB")]).unwrap(), text("àb"));
+}
+
+#[test]
+fn test_affixes() {
+    assert_eq!(call_native(starts_with_fn, [text("hello"), text("he")]).unwrap(), Value::Bool(true));
+    assert_eq!(call_native(starts_with_fn, [text("hello"), text("lo")]).unwrap(), Value::Bool(false));
+    assert_eq!(call_native(ends_with_fn, [text("hello"), text("lo")]).unwrap(), Value::Bool(true));
+}
+
+#[test]
+fn test_char_codes() {
+    assert_eq!(call_native(char_code_fn, [text("A")]).unwrap(), Value::Int(65));
+    assert_eq!(call_native(char_code_fn, [text("")]).unwrap(), Value::Nil);
+    assert_eq!(call_native(from_char_code_fn, [Value::Int(233)]).unwrap(), text("é"));
+    assert_eq!(call_native(from_char_code_fn, [Value::Int(-1)]).unwrap(), Value::Atom(get_id!(":err")));
+}
+
+#[test]
+fn test_repeat() {
+    assert_eq!(call_native(repeat_fn, [text("ab"), Value::Int(3)]).unwrap(), text("ababab"));
+    assert_eq!(call_native(repeat_fn, [text("ab"), Value::Int(0)]).unwrap(), text(""));
+    assert_eq!(call_native(repeat_fn, [text("ab"), Value::Int(i64::MAX)]).unwrap(), Value::Atom(get_id!(":err")));
+    assert!(call_native(repeat_fn, [text("ab"), Value::Int(-1)]).is_err());
+}
@@ -1,4 +1,4 @@
-use crate::vm::DataFunc;
+use crate::vm::{DataFunc, DataKind};
 use std::sync::Arc;
 use ast::id::*;
 
@@ -8,12 +8,79 @@ use crate::stack::ValueStack;
 use crate::reporting::*;
 // use crate::ir::*;
 use crate::basic_ops::*;
+use crate::strings::string_module;
+use crate::bytes::bytes_module;
+use crate::numbers::*;
+use crate::json::{json_parse_fn, json_stringify_fn};
+use crate::process::{run_fn, start_process_fn};
+use crate::stream::{stream_fn, lines_stream};
+use crate::scheduler::{spawn_fn, send_fn, receive_fn, self_pid_fn, link_fn, monitor_fn, supervise_fn};
+use crate::math::math_module;
+use crate::random::random_fn;
+use crate::time::*;
+use crate::filesystem::{FileSystem, RealFs};
 use ast::ast::StringTable;
 use ast::get_id;
 
-use std::fs::{File, OpenOptions, remove_file};
-use std::io::{Read, Write};
-use std::fs::{create_dir, read_dir, remove_dir};
+use std::collections::HashMap;
+use std::env;
+use std::io::{self, ErrorKind, Write};
+use std::path::PathBuf;
+
+//every atom system answers to, keep in sync with the match below (used for editor completion)
+pub const SYSTEM_FUNCS: &[&str] = &[
+    ":println",
+    ":print",
+    ":eprintln",
+    ":read_file",
+    ":write_file",
+    ":read_bytes",
+    ":read_lines",
+    ":write_bytes",
+    ":delete_file",
+    ":make_dir",
+    ":delete_dir",
+    ":read_dir",
+    ":append_file",
+    ":metadata",
+    ":exists",
+    ":rename",
+    ":copy_file",
+    ":make_dir_all",
+    ":delete_dir_all",
+    ":type",
+    ":string",
+    ":bytes",
+    ":parse_int",
+    ":parse_float",
+    ":parse_radix",
+    ":to_radix",
+    ":format",
+    ":json_parse",
+    ":json_stringify",
+    ":math",
+    ":random",
+    ":now",
+    ":unix_time",
+    ":sleep",
+    ":format_time",
+    ":parse_time",
+    ":date",
+    ":env",
+    ":env_vars",
+    ":cwd",
+    ":set_cwd",
+    ":run",
+    ":start_process",
+    ":spawn",
+    ":send",
+    ":receive",
+    ":self_pid",
+    ":link",
+    ":monitor",
+    ":supervise",
+    ":stream",
+];
 
 pub fn system<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
     let atom = stack.pop_atom().ok_or_else(|| arg_error(1,0))?;
@@ -21,13 +88,56 @@ pub fn system<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>)
 
     let func = match atom {
         get_id!(":println") => print_fn,
+        get_id!(":print") => print_inline_fn,
+        get_id!(":eprintln") => eprint_fn,
         get_id!(":read_file") => file_read_fn,
         get_id!(":write_file") => file_write_fn,
+        get_id!(":read_bytes") => file_read_bytes_fn,
+        get_id!(":read_lines") => file_read_lines_fn,
+        get_id!(":write_bytes") => file_write_bytes_fn,
         get_id!(":delete_file") => file_delete_fn,
         get_id!(":make_dir") => create_dir_fn,
         get_id!(":delete_dir") => delete_dir_fn,
         get_id!(":read_dir") => read_dir_fn,
+        get_id!(":append_file") => file_append_fn,
+        get_id!(":metadata") => metadata_fn,
+        get_id!(":exists") => exists_fn,
+        get_id!(":rename") => rename_fn,
+        get_id!(":copy_file") => copy_file_fn,
+        get_id!(":make_dir_all") => create_dir_all_fn,
+        get_id!(":delete_dir_all") => delete_dir_all_fn,
         get_id!(":type") => get_type,
+        get_id!(":string") => string_module,
+        get_id!(":bytes") => bytes_module,
+        get_id!(":parse_int") => parse_int_fn,
+        get_id!(":parse_float") => parse_float_fn,
+        get_id!(":parse_radix") => parse_radix_fn,
+        get_id!(":to_radix") => to_radix_fn,
+        get_id!(":format") => format_fn,
+        get_id!(":json_parse") => json_parse_fn,
+        get_id!(":json_stringify") => json_stringify_fn,
+        get_id!(":math") => math_module,
+        get_id!(":random") => random_fn,
+        get_id!(":now") => now_fn,
+        get_id!(":unix_time") => unix_time_fn,
+        get_id!(":sleep") => sleep_fn,
+        get_id!(":format_time") => format_time_fn,
+        get_id!(":parse_time") => parse_time_fn,
+        get_id!(":date") => date_fn,
+        get_id!(":env") => env_fn,
+        get_id!(":env_vars") => env_vars_fn,
+        get_id!(":cwd") => cwd_fn,
+        get_id!(":set_cwd") => set_cwd_fn,
+        get_id!(":run") => run_fn,
+        get_id!(":start_process") => start_process_fn,
+        get_id!(":spawn") => spawn_fn,
+        get_id!(":send") => send_fn,
+        get_id!(":receive") => receive_fn,
+        get_id!(":self_pid") => self_pid_fn,
+        get_id!(":link") => link_fn,
+        get_id!(":monitor") => monitor_fn,
+        get_id!(":supervise") => supervise_fn,
+        get_id!(":stream") => stream_fn,
         _ => {return Err(sig_error());},
     };
 
@@ -35,11 +145,11 @@ pub fn system<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>)
     Ok(())
 }
 
-pub fn print_fn<'code>(stack:&mut ValueStack<'code>,table:&StringTable<'code>) -> Result<(),ErrList>{
-    let value;
+fn pop_printed<'code>(stack:&mut ValueStack<'code>,_table:&StringTable<'code>) -> Result<Value<'code>,ErrList>{
     #[cfg(not(feature = "debug_print_vm"))] {
-        value = stack.pop_value().ok_or_else(|| arg_error(1,0))?;
+        let value = stack.pop_value().ok_or_else(|| arg_error(1,0))?;
         stack.pop_terminator().ok_or_else(sig_error)?;
+        Ok(value)
     }
     
     #[cfg(feature = "debug_print_vm")] {
@@ -47,26 +157,46 @@ pub fn print_fn<'code>(stack:&mut ValueStack<'code>,table:&StringTable<'code>) -
         if args.len()!=1 {
             println!("error with println got: ");
             for v in args {
-                println!("{}",to_string_debug(&v,table));
+                println!("{}",to_string_debug(&v,_table));
             }
             return Err(sig_error());
         }
 
-        value = args[0].clone();
+        Ok(args[0].clone())
     }
+}
 
-    println!("{:?}",to_string_runtime(&value,table) );
-    stack.push_value(value).unwrap();
+//prints like to_string would (so strings have no quotes) and gives back the printed value.
+//write errors are dropped, a closed output should not stop the script
+pub fn print_to<'code>(out:&mut dyn Write,end:&str,stack:&mut ValueStack<'code>,table:&StringTable<'code>) -> Result<(),ErrList>{
+    let value = pop_printed(stack,table)?;
+    let _ = write!(out,"{}{}",to_string_runtime(&value,table),end);
+    let _ = out.flush();
+    stack.push_value(value).map_err(|_| overflow_error())
+}
 
-    Ok(())
+pub fn print_fn<'code>(stack:&mut ValueStack<'code>,table:&StringTable<'code>) -> Result<(),ErrList>{
+    print_to(&mut io::stdout().lock(),"\n",stack,table)
+}
+
+//print without the newline
+pub fn print_inline_fn<'code>(stack:&mut ValueStack<'code>,table:&StringTable<'code>) -> Result<(),ErrList>{
+    print_to(&mut io::stdout().lock(),"",stack,table)
+}
+
+pub fn eprint_fn<'code>(stack:&mut ValueStack<'code>,table:&StringTable<'code>) -> Result<(),ErrList>{
+    print_to(&mut io::stderr().lock(),"\n",stack,table)
 }
 
 pub fn make_array(v:Vec<Value<'static>>) -> DataFunc {
-    let inner = Arc::new(move |stack: &mut ValueStack, _table: &StringTable|{
+    let v : Arc<[Value<'static>]> = v.into();
+    let items = v.clone();
+    DataFunc::with_kind(DataKind::Array(items),move |stack: &mut ValueStack, _table: &StringTable|{
         let Some(id) = stack.pop_int() else {
             let atom = stack.pop_atom().ok_or_else(sig_error)?;
             match atom {
                 get_id!(":len") => {
+                    stack.pop_terminator().ok_or_else(sig_error)?;
                     return stack.push_int(v.len().try_into().unwrap()).map_err(|_| overflow_error());
                 },
                 _ => {return Err(sig_error())},
@@ -85,122 +215,428 @@ pub fn make_array(v:Vec<Value<'static>>) -> DataFunc {
             None => stack.push_nil().map_err(|_| overflow_error())?,
         }
         Ok(())
-    });
-    DataFunc{inner}
+    })
+}
+
+//a map answers its keys with their value (nil when missing)
+//:len and :keys (the keys in the order given) work unless they are keys themselves
+pub fn make_map(entries:Vec<(Value<'static>,Value<'static>)>) -> DataFunc {
+    let index : HashMap<Value,usize> = entries.iter().enumerate().map(|(i,(k,_))| (k.clone(),i)).collect();
+    let entries : Arc<[(Value<'static>,Value<'static>)]> = entries.into();
+    let kind = DataKind::Map(entries.clone());
+    DataFunc::with_kind(kind,move |stack: &mut ValueStack, _table: &StringTable|{
+        let key = stack.pop_value().ok_or_else(sig_error)?;
+        stack.pop_terminator().ok_or_else(sig_error)?;
+
+        let ans = match (index.get(&key),key) {
+            (Some(i),_) => entries[*i].1.clone(),
+            (None,Value::Atom(get_id!(":len"))) => Value::Int(entries.len() as i64),
+            (None,Value::Atom(get_id!(":keys"))) => {
+                let keys = entries.iter().map(|(k,_)| k.clone()).collect();
+                Value::DataFunc(make_array(keys))
+            },
+            (None,_) => Value::Nil,
+        };
+        stack.push_value(ans).map_err(|_| overflow_error())
+    })
 }
 
+//reads back everything in an array made by make_array, other data funcs are never called
+pub fn array_items<'code>(array:&Value<'code>) -> Result<Vec<Value<'code>>,ErrList> {
+    match array {
+        Value::DataFunc(f) => match f.kind() {
+            DataKind::Array(items) => Ok(items.to_vec()),
+            _ => Err(sig_error()),
+        },
+        _ => Err(sig_error()),
+    }
+}
+
+
+
+//file functions give :ok (or their result) on success and [:err, reason] on failure
+pub(crate) fn io_error_value(err: &io::Error) -> Value<'static> {
+    let reason = match err.kind() {
+        ErrorKind::NotFound => get_id!(":not_found"),
+        ErrorKind::PermissionDenied => get_id!(":permission_denied"),
+        ErrorKind::AlreadyExists => get_id!(":already_exists"),
+        ErrorKind::NotADirectory => get_id!(":not_a_directory"),
+        ErrorKind::IsADirectory => get_id!(":is_a_directory"),
+        ErrorKind::DirectoryNotEmpty => get_id!(":directory_not_empty"),
+        ErrorKind::InvalidData => get_id!(":invalid_data"),
+        ErrorKind::InvalidInput => get_id!(":invalid_input"),
+        _ => get_id!(":io_error"),
+    };
+    Value::DataFunc(make_array(vec![Value::Atom(get_id!(":err")),Value::Atom(reason)]))
+}
+
+fn push_io<'code>(stack: &mut ValueStack<'code>, result: io::Result<Value<'code>>) -> Result<(), ErrList> {
+    let value = result.unwrap_or_else(|err| io_error_value(&err));
+    stack.push_value(value).map_err(|_| overflow_error())
+}
+
+fn ok_value() -> Value<'static> {
+    Value::Atom(get_id!(":ok"))
+}
+
+fn pop_path(stack: &mut ValueStack) -> Result<PathBuf, ErrList> {
+    let name = stack.pop_string().ok_or_else(sig_error)?;
+    Ok(PathBuf::from(name.as_str()))
+}
 
+//a file function works on any FileSystem, system itself hands out the ones on the real disk
+pub type FileFunc = for<'code> fn(&dyn FileSystem, &mut ValueStack<'code>) -> Result<(),ErrList>;
 
 // File Read Function
-pub fn file_read_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
-    let file_name = stack.pop_string().ok_or_else(sig_error)?;
+pub fn read_file(fs: &dyn FileSystem, stack: &mut ValueStack) -> Result<(), ErrList> {
+    let path = pop_path(stack)?;
     stack.pop_terminator().ok_or_else(sig_error)?;
 
-    let mut file = match File::open(&*file_name) {
-        Ok(f) => f,
-        Err(_) => {
-            stack.push_atom(get_id!(":err")).map_err(|_| overflow_error())?;
-            return Ok(());
-        }
-    };
+    let result = fs.read(&path).and_then(|contents| {
+        String::from_utf8(contents).map_err(|_| io::Error::from(ErrorKind::InvalidData))
+    });
+    push_io(stack, result.map(|contents| Value::String(Arc::new(contents.into()))))
+}
 
-    let mut contents = String::new();
-    if file.read_to_string(&mut contents).is_err() {
-        stack.push_atom(get_id!(":err")).map_err(|_| overflow_error())?;
-        return Ok(());
-    }
+// read_lines(name) gives a stream of the lines without their line endings
+pub fn read_lines(fs: &dyn FileSystem, stack: &mut ValueStack) -> Result<(), ErrList> {
+    let path = pop_path(stack)?;
+    stack.pop_terminator().ok_or_else(sig_error)?;
 
-    stack.push_string(Arc::new(contents)).map_err(|_| overflow_error())?;
-    Ok(())
+    push_io(stack, fs.open(&path).map(lines_stream))
+}
+
+// File Read Function that keeps the raw bytes
+pub fn read_bytes(fs: &dyn FileSystem, stack: &mut ValueStack) -> Result<(), ErrList> {
+    let path = pop_path(stack)?;
+    stack.pop_terminator().ok_or_else(sig_error)?;
+
+    push_io(stack, fs.read(&path).map(|contents| Value::Bytes(contents.into())))
 }
 
-// File Write Function
-pub fn file_write_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
-    let file_name = stack.pop_string().ok_or_else(sig_error)?;
+// File Write Function write_file(name, content) replaces the whole file
+pub fn write_file(fs: &dyn FileSystem, stack: &mut ValueStack) -> Result<(), ErrList> {
     let content = stack.pop_string().ok_or_else(sig_error)?;
+    let path = pop_path(stack)?;
     stack.pop_terminator().ok_or_else(sig_error)?;
 
-    let mut file = match OpenOptions::new().create(true).write(true).open(&*file_name) {
-        Ok(f) => f,
-        Err(_) => {
-            stack.push_atom(get_id!(":err")).map_err(|_| overflow_error())?;
-            return Ok(());
-        }
-    };
+    push_io(stack, fs.write(&path, content.as_bytes()).map(|_| ok_value()))
+}
 
-    if file.write_all(content.as_bytes()).is_err() {
-        stack.push_atom(get_id!(":err")).map_err(|_| overflow_error())?;
-        return Ok(());
-    }
+// File Write Function write_bytes(name, bytes) replaces the whole file
+pub fn write_bytes(fs: &dyn FileSystem, stack: &mut ValueStack) -> Result<(), ErrList> {
+    let content = stack.pop_bytes().ok_or_else(sig_error)?;
+    let path = pop_path(stack)?;
+    stack.pop_terminator().ok_or_else(sig_error)?;
 
-    stack.push_atom(get_id!(":ok")).map_err(|_| overflow_error())?;
-    Ok(())
+    push_io(stack, fs.write(&path, &content).map(|_| ok_value()))
+}
+
+// File Append Function append_file(name, content) creates the file if needed
+pub fn append_file(fs: &dyn FileSystem, stack: &mut ValueStack) -> Result<(), ErrList> {
+    let content = stack.pop_string().ok_or_else(sig_error)?;
+    let path = pop_path(stack)?;
+    stack.pop_terminator().ok_or_else(sig_error)?;
+
+    push_io(stack, fs.append(&path, content.as_bytes()).map(|_| ok_value()))
 }
 
 // File Delete Function
-pub fn file_delete_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
-    let file_name = stack.pop_string().ok_or_else(sig_error)?;
+pub fn delete_file(fs: &dyn FileSystem, stack: &mut ValueStack) -> Result<(), ErrList> {
+    let path = pop_path(stack)?;
     stack.pop_terminator().ok_or_else(sig_error)?;
 
-    if remove_file(&*file_name).is_err() {
-        stack.push_atom(get_id!(":err")).map_err(|_| overflow_error())?;
-        return Ok(());
-    }
+    push_io(stack, fs.remove_file(&path).map(|_| ok_value()))
+}
 
-    stack.push_atom(get_id!(":ok")).map_err(|_| overflow_error())?;
-    Ok(())
+// Rename Function rename(from, to) works on files and directories
+pub fn rename(fs: &dyn FileSystem, stack: &mut ValueStack) -> Result<(), ErrList> {
+    let to = pop_path(stack)?;
+    let from = pop_path(stack)?;
+    stack.pop_terminator().ok_or_else(sig_error)?;
+
+    push_io(stack, fs.rename(&from, &to).map(|_| ok_value()))
+}
+
+// File Copy Function copy_file(from, to)
+pub fn copy_file(fs: &dyn FileSystem, stack: &mut ValueStack) -> Result<(), ErrList> {
+    let to = pop_path(stack)?;
+    let from = pop_path(stack)?;
+    stack.pop_terminator().ok_or_else(sig_error)?;
+
+    push_io(stack, fs.copy(&from, &to).map(|_| ok_value()))
+}
+
+// Exists Function (true for files and directories)
+pub fn exists(fs: &dyn FileSystem, stack: &mut ValueStack) -> Result<(), ErrList> {
+    let path = pop_path(stack)?;
+    stack.pop_terminator().ok_or_else(sig_error)?;
+
+    push_io(stack, fs.exists(&path).map(Value::Bool))
 }
 
+// Metadata Function gives a map with :size :is_file :is_dir :readonly and :modified (unix seconds)
+pub fn metadata(fs: &dyn FileSystem, stack: &mut ValueStack) -> Result<(), ErrList> {
+    let path = pop_path(stack)?;
+    stack.pop_terminator().ok_or_else(sig_error)?;
+
+    let result = fs.metadata(&path).map(|meta| {
+        Value::DataFunc(make_map(vec![
+            (Value::Atom(get_id!(":size")),Value::Int(meta.size as i64)),
+            (Value::Atom(get_id!(":is_file")),Value::Bool(meta.is_file)),
+            (Value::Atom(get_id!(":is_dir")),Value::Bool(meta.is_dir)),
+            (Value::Atom(get_id!(":readonly")),Value::Bool(meta.readonly)),
+            (Value::Atom(get_id!(":modified")),meta.modified.map_or(Value::Nil, Value::Int)),
+        ]))
+    });
+    push_io(stack, result)
+}
 
 // Directory Creation Function
-pub fn create_dir_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
-    let dir_name = stack.pop_string().ok_or_else(sig_error)?;
+pub fn make_dir(fs: &dyn FileSystem, stack: &mut ValueStack) -> Result<(), ErrList> {
+    let path = pop_path(stack)?;
     stack.pop_terminator().ok_or_else(sig_error)?;
 
-    if create_dir(&*dir_name).is_err() {
-        stack.push_atom(get_id!(":err")).map_err(|_| overflow_error())?;
-        return Ok(());
-    }
+    push_io(stack, fs.create_dir(&path).map(|_| ok_value()))
+}
 
-    stack.push_atom(get_id!(":ok")).map_err(|_| overflow_error())?;
-    Ok(())
+// Recursive Directory Creation Function (also makes the missing parents)
+pub fn make_dir_all(fs: &dyn FileSystem, stack: &mut ValueStack) -> Result<(), ErrList> {
+    let path = pop_path(stack)?;
+    stack.pop_terminator().ok_or_else(sig_error)?;
+
+    push_io(stack, fs.create_dir_all(&path).map(|_| ok_value()))
 }
 
-// Directory Deletion Function
-pub fn delete_dir_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
-    let dir_name = stack.pop_string().ok_or_else(sig_error)?;
+// Directory Deletion Function (only for empty directories)
+pub fn delete_dir(fs: &dyn FileSystem, stack: &mut ValueStack) -> Result<(), ErrList> {
+    let path = pop_path(stack)?;
     stack.pop_terminator().ok_or_else(sig_error)?;
 
-    if remove_dir(&*dir_name).is_err() {
-        stack.push_atom(get_id!(":err")).map_err(|_| overflow_error())?;
-        return Ok(());
-    }
+    push_io(stack, fs.remove_dir(&path).map(|_| ok_value()))
+}
 
-    stack.push_atom(get_id!(":ok")).map_err(|_| overflow_error())?;
-    Ok(())
+// Recursive Directory Deletion Function (everything inside goes too)
+pub fn delete_dir_all(fs: &dyn FileSystem, stack: &mut ValueStack) -> Result<(), ErrList> {
+    let path = pop_path(stack)?;
+    stack.pop_terminator().ok_or_else(sig_error)?;
+
+    push_io(stack, fs.remove_dir_all(&path).map(|_| ok_value()))
 }
 
 // Directory Read Function
-pub fn read_dir_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
-    let dir_name = stack.pop_string().ok_or_else(sig_error)?;
+pub fn read_dir(fs: &dyn FileSystem, stack: &mut ValueStack) -> Result<(), ErrList> {
+    let path = pop_path(stack)?;
     stack.pop_terminator().ok_or_else(sig_error)?;
 
-    let paths = match read_dir(&*dir_name) {
-        Ok(paths) => paths,
-        Err(_) => {
-            stack.push_atom(get_id!(":err")).map_err(|_| overflow_error())?;
-            return Ok(());
-        }
+    let result = fs.read_dir(&path).map(|paths| {
+        let entries = paths.iter()
+            .map(|p| Value::String(Arc::new(p.display().to_string().into())))
+            .collect();
+        Value::DataFunc(make_array(entries))
+    });
+    push_io(stack, result)
+}
+
+//the file function behind an atom
+pub fn file_func(atom: u32) -> Option<FileFunc> {
+    let func: FileFunc = match atom {
+        get_id!(":read_file") => read_file,
+        get_id!(":read_bytes") => read_bytes,
+        get_id!(":read_lines") => read_lines,
+        get_id!(":write_file") => write_file,
+        get_id!(":write_bytes") => write_bytes,
+        get_id!(":append_file") => append_file,
+        get_id!(":delete_file") => delete_file,
+        get_id!(":rename") => rename,
+        get_id!(":copy_file") => copy_file,
+        get_id!(":exists") => exists,
+        get_id!(":metadata") => metadata,
+        get_id!(":make_dir") => make_dir,
+        get_id!(":make_dir_all") => make_dir_all,
+        get_id!(":delete_dir") => delete_dir,
+        get_id!(":delete_dir_all") => delete_dir_all,
+        get_id!(":read_dir") => read_dir,
+        _ => return None,
     };
+    Some(func)
+}
 
-    let mut entries = Vec::new();
-    for entry in paths {
-        if let Ok(entry) = entry {
-            entries.push(Value::String(Arc::new(entry.path().display().to_string())));
+//system's own file functions, on the real disk
+macro_rules! on_disk {
+    ($($name:ident => $func:ident),* $(,)?) => {$(
+        pub fn $name<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+            $func(&RealFs, stack)
         }
-    }
+    )*};
+}
+
+on_disk! {
+    file_read_fn => read_file,
+    file_read_bytes_fn => read_bytes,
+    file_read_lines_fn => read_lines,
+    file_write_fn => write_file,
+    file_write_bytes_fn => write_bytes,
+    file_append_fn => append_file,
+    file_delete_fn => delete_file,
+    rename_fn => rename,
+    copy_file_fn => copy_file,
+    exists_fn => exists,
+    metadata_fn => metadata,
+    create_dir_fn => make_dir,
+    create_dir_all_fn => make_dir_all,
+    delete_dir_fn => delete_dir,
+    delete_dir_all_fn => delete_dir_all,
+    read_dir_fn => read_dir,
+}
+
+// Environment Variable Function (nil when the variable is not set, [:err, :invalid_data] when it is not unicode)
+pub fn env_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+    let name = stack.pop_string().ok_or_else(sig_error)?;
+    stack.pop_terminator().ok_or_else(sig_error)?;
+
+    match env::var(name.as_str()) {
+        Ok(value) => stack.push_string(Arc::new(value.into())),
+        Err(env::VarError::NotPresent) => stack.push_nil(),
+        Err(env::VarError::NotUnicode(_)) => stack.push_value(io_error_value(&ErrorKind::InvalidData.into())),
+    }.map_err(|_| overflow_error())?;
+    Ok(())
+}
+
+// All Environment Variables as a map sorted by name (variables that are not unicode are left out)
+pub fn env_vars_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+    stack.pop_terminator().ok_or_else(sig_error)?;
+
+    let mut vars : Vec<(String,String)> = env::vars_os()
+        .filter_map(|(k,v)| Some((k.into_string().ok()?,v.into_string().ok()?)))
+        .collect();
+    vars.sort();
+
+    let entries = vars.into_iter()
+        .map(|(k,v)| (Value::String(Arc::new(k.into())),Value::String(Arc::new(v.into()))))
+        .collect();
+    stack.push_value(Value::DataFunc(make_map(entries))).map_err(|_| overflow_error())?;
+    Ok(())
+}
+
+// Current Directory Function
+pub fn cwd_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+    stack.pop_terminator().ok_or_else(sig_error)?;
 
-    let list = Value::DataFunc(make_array(entries));
-    stack.push_value(list).map_err(|_| overflow_error())?;
+    let dir = env::current_dir()
+        .and_then(|dir| dir.into_os_string().into_string().map_err(|_| ErrorKind::InvalidData.into()));
+    match dir {
+        Ok(dir) => stack.push_string(Arc::new(dir.into())),
+        Err(e) => stack.push_value(io_error_value(&e)),
+    }.map_err(|_| overflow_error())?;
     Ok(())
-}
\ No newline at end of file
+}
+
+// Change Directory Function
+pub fn set_cwd_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+    let dir_name = stack.pop_string().ok_or_else(sig_error)?;
+    stack.pop_terminator().ok_or_else(sig_error)?;
+
+    push_io(stack, env::set_current_dir(dir_name.as_str()).map(|()| ok_value()))
+}
+
+#[test]
+fn system_funcs_are_all_known() {
+    let table = StringTable::new();
+    for name in SYSTEM_FUNCS {
+        let mut stack = ValueStack::new();
+        stack.push_terminator().unwrap();
+        stack.push_atom(table.get_existing_id(name)).unwrap();
+        system(&mut stack, &table).unwrap();
+    }
+}
+
+#[cfg(test)]
+use crate::basic_ops::text;
+#[cfg(test)]
+use crate::filesystem::MemoryFs;
+
+//runs a file function on fs with string args
+#[cfg(test)]
+fn on_memory(fs:&MemoryFs,func:FileFunc,args:&[&str]) -> Value<'static> {
+    let mut stack = ValueStack::new();
+    stack.push_terminator().unwrap();
+    for arg in args {
+        stack.push_value(text(arg)).unwrap();
+    }
+    func(fs,&mut stack).unwrap();
+    stack.pop_value().unwrap()
+}
+
+//the reason in an [:err, reason] value
+#[cfg(test)]
+fn err_reason(value:&Value<'static>) -> Value<'static> {
+    match array_items(value).unwrap().as_slice() {
+        [Value::Atom(get_id!(":err")),reason] => reason.clone(),
+        _ => panic!("expected [:err, reason] got {:?}",value),
+    }
+}
+
+#[cfg(test)]
+fn field(map:&Value<'static>,key:u32) -> Value<'static> {
+    let Value::DataFunc(f) = map else { panic!("expected a map got {:?}",map) };
+    let DataKind::Map(entries) = f.kind() else { panic!("expected a map") };
+    entries.iter().find(|(k,_)| *k == Value::Atom(key)).map(|(_,v)| v.clone()).unwrap()
+}
+
+#[test]
+fn test_file_funcs() {
+    let fs = MemoryFs::new();
+    fs.create_dir_all(std::path::Path::new("/d")).unwrap();
+    assert_eq!(on_memory(&fs,write_file,&["/d/a.txt","a long first line"]), ok_value());
+    assert_eq!(on_memory(&fs,write_file,&["/d/a.txt","short"]), ok_value());
+    assert_eq!(on_memory(&fs,append_file,&["/d/a.txt","!"]), ok_value());
+    assert_eq!(on_memory(&fs,read_file,&["/d/a.txt"]), text("short!"));
+    assert_eq!(on_memory(&fs,exists,&["/d/a.txt"]), Value::Bool(true));
+
+    let meta = on_memory(&fs,metadata,&["/d/a.txt"]);
+    assert_eq!(field(&meta,get_id!(":size")), Value::Int(6));
+    assert_eq!(field(&meta,get_id!(":is_file")), Value::Bool(true));
+    assert_eq!(field(&meta,get_id!(":is_dir")), Value::Bool(false));
+
+    assert_eq!(on_memory(&fs,copy_file,&["/d/a.txt","/d/b.txt"]), ok_value());
+    assert_eq!(on_memory(&fs,rename,&["/d/a.txt","/d/c.txt"]), ok_value());
+    assert_eq!(on_memory(&fs,exists,&["/d/a.txt"]), Value::Bool(false));
+    assert_eq!(fs.file("/d/b.txt").unwrap(), b"short!");
+    assert_eq!(fs.file("/d/c.txt").unwrap(), b"short!");
+}
+
+#[test]
+fn test_dir_funcs() {
+    let fs = MemoryFs::new();
+    assert_eq!(err_reason(&on_memory(&fs,make_dir,&["/x/y/z"])), Value::Atom(get_id!(":not_found")));
+    assert_eq!(on_memory(&fs,make_dir_all,&["/x/y/z"]), ok_value());
+    assert_eq!(field(&on_memory(&fs,metadata,&["/x/y/z"]),get_id!(":is_dir")), Value::Bool(true));
+    assert_eq!(array_items(&on_memory(&fs,read_dir,&["/x"])).unwrap(), [text("/x/y")]);
+    assert_eq!(err_reason(&on_memory(&fs,delete_dir,&["/x"])), Value::Atom(get_id!(":directory_not_empty")));
+    assert_eq!(on_memory(&fs,delete_dir_all,&["/x"]), ok_value());
+    assert_eq!(on_memory(&fs,exists,&["/x"]), Value::Bool(false));
+}
+
+#[test]
+fn test_file_errors() {
+    let fs = MemoryFs::new();
+    fs.insert_file("/bin.dat",[0xff,0xfe]);
+    assert_eq!(err_reason(&on_memory(&fs,read_file,&["/missing.txt"])), Value::Atom(get_id!(":not_found")));
+    assert_eq!(err_reason(&on_memory(&fs,metadata,&["/missing.txt"])), Value::Atom(get_id!(":not_found")));
+    assert_eq!(err_reason(&on_memory(&fs,read_file,&["/bin.dat"])), Value::Atom(get_id!(":invalid_data")));
+    assert_eq!(err_reason(&on_memory(&fs,write_file,&["/missing/a.txt","x"])), Value::Atom(get_id!(":not_found")));
+}
+
+#[test]
+fn test_byte_files() {
+    let fs = MemoryFs::new();
+    let mut stack = ValueStack::new();
+    stack.push_terminator().unwrap();
+    stack.push_value(text("/b.dat")).unwrap();
+    stack.push_bytes([0x00,0x9f,0xff].into()).unwrap();
+    write_bytes(&fs,&mut stack).unwrap();
+    assert_eq!(stack.pop_value().unwrap(), ok_value());
+    assert_eq!(on_memory(&fs,read_bytes,&["/b.dat"]), Value::Bytes([0x00,0x9f,0xff].into()));
+    assert_eq!(err_reason(&on_memory(&fs,read_file,&["/b.dat"])), Value::Atom(get_id!(":invalid_data")));
+}
@@ -1,5 +1,6 @@
 #![cfg(test)]
 
+use std::sync::Arc;
 use crate::value::Value;
 use crate::translate::compile_source_to_code;
 
@@ -97,4 +98,543 @@ def main(system) {
     let code = compile_source_to_code(source_code);
 
    	 code.run("main", vec![Value::StaticFunc(system)]).unwrap();
-}
\ No newline at end of file
+}
+//the module itself is tested in strings.rs
+#[test]
+fn string_module() {
+    let source_code = r#"
+def main(system) {
+    s = system(:string);
+    s(:join)(s(:split)('a,b,,c', ','), '-')
+}
+"#;
+    let code = compile_source_to_code(source_code);
+    code.run_map("main", vec![Value::StaticFunc(system)], |v| assert_eq!(v, Value::String(Arc::new("a-b--c".into())))).unwrap();
+}
+
+#[test]
+fn string_indexing() {
+    let source_code = r#"
+def lengths() {
+    s = 'héllo wörld';
+    empty = '';
+    s(:len) == 11 && s(:byte_len) == 13 && empty(:len) == 0
+}
+
+def indexing() {
+    s = 'héllo wörld';
+    s(1) == 'é' && s(7) == 'ö' && s(-1) == 'd' && s(-11) == 'h'
+    && s(11) == :string_out_of_bounds && s(-12) == :string_out_of_bounds
+}
+
+def long_strings(system) {
+    s = system(:string)(:repeat)('aé', 100);
+    s(:len) == 200 && s(:byte_len) == 300 && s(151) == 'é' && s(-2) == 'a'
+}
+"#;
+    let code = compile_source_to_code(source_code);
+
+    for name in ["lengths", "indexing"] {
+        assert!(code.run_compare(name, vec![], Value::Bool(true)).unwrap(), "{} failed", name);
+    }
+    assert!(code.run_compare("long_strings", vec![Value::StaticFunc(system)], Value::Bool(true)).unwrap());
+}
+
+//the natives themselves are tested in numbers.rs
+#[test]
+fn number_parsing_and_formatting() {
+    let source_code = r#"
+def main(system) {
+    system(:format)(system(:parse_int)('41') + system(:parse_radix)('1', 2), '5')
+}
+"#;
+    let code = compile_source_to_code(source_code);
+    code.run_map("main", vec![Value::StaticFunc(system)], |v| assert_eq!(v, Value::String(Arc::new("   42".into())))).unwrap();
+}
+
+#[test]
+fn string_interpolation() {
+    use crate::reporting::Error;
+    use codespan::Span;
+
+    let source_code = r#"
+def main() {
+    x = 2;
+    name = 'world';
+    a = "x = {x}, sum = {x + 3.5}!";
+    b = "hello {name}, {:atom} {nil} \{braces\} {"nested {x * 2}"}";
+    a == 'x = 2, sum = 5.5!' && b == 'hello world, :atom nil {braces} nested 4' && "{x}" == '2'
+}
+
+def broken() {
+    x = 1;
+    "value: {x(2)}"
+}
+"#;
+    let code = compile_source_to_code(source_code);
+    assert!(code.run_compare("main", vec![], Value::Bool(true)).unwrap());
+
+    let err = code.run("broken", vec![]).unwrap_err();
+    let Some(Error::NoneCallble(e)) = err.front() else {
+        panic!("expected a none callable error got {:?}", err);
+    };
+    let start = source_code.find("x(2)").unwrap();
+    assert_eq!(e.span, Span::new(start as u32, (start + 4) as u32));
+}
+
+//the functions themselves are tested in math.rs
+#[test]
+fn math_module() {
+    use crate::reporting::{Error, ArgNumError, SigError};
+
+    let source_code = r#"
+def main(system) {
+    m = system(:math);
+    m(:gcd)(m(:int)(m(:sqrt)(144)), 18)
+}
+
+def wrong_count(system) {
+    system(:math)(:sqrt)()
+}
+
+def wrong_type(system) {
+    system(:math)(:gcd)(1.5, 2)
+}
+"#;
+    let code = compile_source_to_code(source_code);
+
+    code.run_map("main", vec![Value::StaticFunc(system)], |v| assert_eq!(v, Value::Int(6))).unwrap();
+
+    let err = code.run("wrong_count", vec![Value::StaticFunc(system)]).unwrap_err();
+    let Some(Error::Stacked(e)) = err.front() else { panic!("expected a stacked error got {:?}", err) };
+    assert_eq!(e.err.front(), Some(&Error::ArgNum(ArgNumError { expected: 1, got: 0 })));
+    assert_eq!(&source_code[e.span.start().to_usize()..e.span.end().to_usize()], "system(:math)(:sqrt)()");
+
+    let err = code.run("wrong_type", vec![Value::StaticFunc(system)]).unwrap_err();
+    let Some(Error::Stacked(e)) = err.front() else { panic!("expected a stacked error got {:?}", err) };
+    assert_eq!(e.err.front(), Some(&Error::Sig(SigError {})));
+}
+
+//the generators themselves are tested in random.rs
+#[test]
+fn seeded_random() {
+    let source_code = r#"
+def roll(gen, n, acc) {
+    match n {
+        0 => acc,
+        _ => {
+            pair = gen(:range, 1, 7);
+            self(pair(1), n - 1, acc + pair(0))
+        }
+    }
+}
+
+def dice(system) {
+    roll(system(:random)(42), 20, 0)
+}
+"#;
+    let code = compile_source_to_code(source_code);
+
+    // the sum of 20 dice is fixed by the seed
+    let mut rng = crate::random::Xoshiro256::from_seed(42);
+    let expected: i64 = (0..20).map(|_| rng.next_range(1, 7).unwrap()).sum();
+    let ans = code.run_compare("dice", vec![Value::StaticFunc(system)], Value::Int(expected));
+    assert!(ans.unwrap());
+}
+
+//the natives themselves are tested in time.rs
+#[test]
+fn clock_and_dates() {
+    let source_code = r#"
+def main(system) {
+    d = system(:parse_time)('2024-03-01T12:30:05Z');
+    system(:sleep)(1);
+    system(:date)(d(:unix) + 86400)(:day)
+}
+"#;
+    let code = compile_source_to_code(source_code);
+    code.run_map("main", vec![Value::StaticFunc(system)], |v| assert_eq!(v, Value::Int(2))).unwrap();
+}
+
+//the working directory and environment are shared by every test thread, tests that change them
+//or resolve relative paths take this first
+static PROCESS_STATE: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+fn process_state() -> std::sync::MutexGuard<'static, ()> {
+    PROCESS_STATE.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[test]
+fn environment() {
+    let source_code = r#"
+def main(system) {
+    vars = system(:env_vars)();
+    home = system(:env)('FAEYNE_TEST_HOME');
+    cwd = system(:cwd)();
+    home == '/home/faeyne' && vars('FAEYNE_TEST_HOME') == home && system(:env)('FAEYNE_TEST_MISSING') == nil
+    && system(:set_cwd)(cwd) == :ok && system(:set_cwd)(cwd + '/no/such/dir')(1) == :not_found && system(:cwd)() == cwd
+}
+"#;
+    let _lock = process_state();
+    std::env::set_var("FAEYNE_TEST_HOME", "/home/faeyne");
+    let code = compile_source_to_code(source_code);
+
+    let ans = code.run_compare("main", vec![Value::StaticFunc(system)], Value::Bool(true));
+    assert!(ans.unwrap());
+    assert_eq!(std::env::current_dir().unwrap().to_str().unwrap(), env!("CARGO_MANIFEST_DIR"));
+}
+
+//the file functions themselves are tested in system.rs on a MemoryFs
+#[test]
+fn file_system() {
+
+    let source_code = r#"
+def main(system, dir) {
+    file = dir + '/a.txt';
+    system(:write_file)(file, 'short');
+    system(:append_file)(file, '!');
+    contents = system(:read_file)(file);
+    system(:delete_file)(file);
+    contents
+}
+"#;
+    let _lock = process_state();
+    let dir = std::env::temp_dir().join(format!("faeyne_fs_test_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let code = compile_source_to_code(source_code);
+
+    let args = vec![Value::StaticFunc(system), Value::String(Arc::new(dir.to_str().unwrap().into()))];
+    code.run_map("main", args, |v| assert_eq!(v, Value::String(Arc::new("short!".into())))).unwrap();
+    assert!(!dir.join("a.txt").exists());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+//the module itself is tested in bytes.rs and reading and writing in system.rs
+#[test]
+fn bytes_values() {
+    let source_code = r#"
+def joined(system) {
+    system(:bytes)(:from_hex)('00ff') + system(:bytes)(:from_utf8)('!')
+}
+
+def indexed(system) {
+    both = joined(system);
+    both(1) + both(-1) * 1000
+}
+
+def out_of_bounds(system) {
+    joined(system)(3)
+}
+"#;
+    let code = compile_source_to_code(source_code);
+    let args = || vec![Value::StaticFunc(system)];
+    code.run_map("joined", args(), |v| assert_eq!(v, Value::Bytes([0x00, 0xff, b'!'].into()))).unwrap();
+    code.run_map("indexed", args(), |v| assert_eq!(v, Value::Int(255 + 33 * 1000))).unwrap();
+    let out = Value::Atom(code.table.write().unwrap().get_id(":bytes_out_of_bounds"));
+    code.run_map("out_of_bounds", args(), |v| assert_eq!(v, out)).unwrap();
+}
+
+//parsing and writing are tested in json.rs
+#[test]
+fn json() {
+    use crate::reporting::{Error, SigError};
+
+    let source_code = r#"
+def round_trip(system) {
+    v = system(:json_parse)('{"a": [1, 2.5], "b": {"c": null}}');
+    system(:json_stringify)(v)
+}
+
+def functions(system) {
+    system(:json_stringify)(system)
+}
+"#;
+    let code = compile_source_to_code(source_code);
+    let expected = Value::String(Arc::new(r#"{"a":[1,2.5],"b":{"c":null}}"#.into()));
+    code.run_map("round_trip", vec![Value::StaticFunc(system)], |v| assert_eq!(v, expected)).unwrap();
+
+    let err = code.run("functions", vec![Value::StaticFunc(system)]).unwrap_err();
+    let Some(Error::Stacked(e)) = err.front() else { panic!("expected a stacked error got {:?}", err) };
+    assert_eq!(e.err.front(), Some(&Error::Sig(SigError {})));
+    assert_eq!(&source_code[e.span.start().to_usize()..e.span.end().to_usize()], "system(:json_stringify)(system)");
+}
+
+#[test]
+fn sandboxed_system() {
+    use crate::sandbox::SystemBuilder;
+
+    let source_code = r#"
+def confined(system, dir) {
+    inside = dir + '/inside';
+    system(:write_file)(inside + '/a.txt', 'hi') == :ok && system(:read_file)(inside + '/a.txt') == 'hi'
+    && system(:make_dir_all)(inside + '/x/y') == :ok && system(:read_dir)(inside)(:len) == 3
+    && system(:write_file)(dir + '/outside/a.txt', 'hi')(1) == :permission_denied
+    && system(:read_file)(inside + '/../outside/b.txt')(1) == :permission_denied
+    && system(:write_file)(inside + '/new/../../outside/c.txt', 'hi')(1) == :permission_denied
+    && system(:copy_file)(inside + '/a.txt', dir + '/outside/a.txt')(1) == :permission_denied
+    && system(:read_file)(inside + '/link/b.txt')(1) == :permission_denied
+    && system(:math)(:abs)(-1) == 1
+}
+
+def denied(system, dir) {
+    system(:println)('hello') == 'hello' && system(:exists)(dir + '/inside')(1) == :permission_denied
+}
+
+def host_state(system, name, arg) {
+    system(name)(arg)(1)
+}
+"#;
+    let _lock = process_state();
+    let dir = std::env::temp_dir().join(format!("faeyne_sandbox_test_{}", std::process::id()));
+    let (inside, outside) = (dir.join("inside"), dir.join("outside"));
+    std::fs::create_dir_all(&inside).unwrap();
+    std::fs::create_dir_all(&outside).unwrap();
+    std::fs::write(outside.join("b.txt"), "secret").unwrap();
+    #[cfg(unix)]
+    std::os::unix::fs::symlink(&outside, inside.join("link")).unwrap();
+    #[cfg(not(unix))]
+    std::fs::create_dir_all(inside.join("link")).unwrap();
+
+    let code = compile_source_to_code(source_code);
+    let dir_value = Value::String(Arc::new(dir.to_str().unwrap().into()));
+
+    let system = SystemBuilder::new().allow_read(&inside).allow_write(&inside).build();
+    let args = vec![Value::DataFunc(system), dir_value.clone()];
+    assert!(code.run_compare("confined", args, Value::Bool(true)).unwrap());
+    assert!(!outside.join("a.txt").exists() && !outside.join("c.txt").exists());
+
+    let lines = Lines::default();
+    let system = SystemBuilder::new().allow_read(&inside).deny_fs().with_println_sink(lines.clone()).build();
+    assert!(code.run_compare("denied", vec![Value::DataFunc(system), dir_value.clone()], Value::Bool(true)).unwrap());
+    assert_eq!(lines.text(), "hello\n");
+
+    //the working directory and environment are the host's, confined scripts can not see or move them
+    let cwd = std::env::current_dir().unwrap();
+    let denied = Value::Atom(code.table.write().unwrap().get_id(":permission_denied"));
+    for system in [
+        SystemBuilder::new().allow_read(&inside).allow_write(&inside).build(),
+        SystemBuilder::new().deny_fs().build(),
+    ] {
+        for name in [":set_cwd", ":cwd", ":env", ":env_vars"] {
+            let name_value = Value::Atom(code.table.write().unwrap().get_id(name));
+            let args = vec![Value::DataFunc(system.clone()), name_value, dir_value.clone()];
+            assert!(code.run_map("host_state", args, |v| v == denied).unwrap(), "{} was allowed", name);
+        }
+    }
+    assert_eq!(std::env::current_dir().unwrap(), cwd);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+//captures println output for the tests that need to look at it
+#[derive(Clone, Default)]
+struct Lines(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+impl Lines {
+    fn text(&self) -> String {
+        String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+    }
+}
+
+impl std::io::Write for Lines {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn memory_file_system() {
+    use crate::filesystem::MemoryFs;
+    use crate::sandbox::SystemBuilder;
+
+    let source_code = r#"
+def main(system) {
+    config = system(:read_file)('config/settings.txt');
+    system(:make_dir_all)('out/logs');
+    system(:write_file)('out/result.txt', 'read ' + config);
+    system(:append_file)('out/logs/run.log', 'done');
+    system(:rename)('config/old.txt', 'out/old.txt');
+    system(:delete_dir)('config')(1) == :directory_not_empty && system(:read_file)('missing')(1) == :not_found
+    && system(:read_dir)('out')(:len) == 3 && system(:println)(config) == config
+}
+"#;
+    let fs = Arc::new(MemoryFs::new());
+    fs.insert_file("config/settings.txt", "fast");
+    fs.insert_file("config/old.txt", "");
+    let lines = Lines::default();
+    let system = SystemBuilder::new().with_fs(fs.clone()).with_println_sink(lines.clone()).build();
+
+    let code = compile_source_to_code(source_code);
+    assert!(code.run_compare("main", vec![Value::DataFunc(system)], Value::Bool(true)).unwrap());
+
+    assert_eq!(fs.paths(), [
+        "/config/", "/config/settings.txt", "/out/", "/out/logs/", "/out/logs/run.log", "/out/old.txt", "/out/result.txt",
+    ]);
+    assert_eq!(fs.file("out/result.txt").unwrap(), b"read fast");
+    assert_eq!(lines.text(), "fast\n");
+
+    //confinement works the same on a fake tree
+    let system = SystemBuilder::new().with_fs(fs.clone()).allow_read("/out").build();
+    let source_code = r#"
+def main(system) {
+    system(:read_file)('/out/result.txt') == 'read fast' && system(:read_file)('/out/../config/settings.txt')(1) == :permission_denied
+    && system(:write_file)('/out/x.txt', '')(1) == :permission_denied
+}
+"#;
+    let code = compile_source_to_code(source_code);
+    assert!(code.run_compare("main", vec![Value::DataFunc(system)], Value::Bool(true)).unwrap());
+}
+
+#[test]
+fn streams() {
+    use crate::filesystem::MemoryFs;
+    use crate::sandbox::SystemBuilder;
+
+    let source_code = r#"
+def count_from(emit, n) {
+    emit(n);
+    self(emit, n + 1)
+}
+
+def main(system) {
+    naturals = system(:stream)(fn(emit) -> {count_from(emit, 0)});
+    evens = naturals(:filter, fn(x) -> {x % 2 == 0})(:map, fn(x) -> {x * 10})(:take, 3);
+    first = evens(:next);
+    rest = evens(:fold, 0, fn(acc, x) -> {acc + x});
+
+    big = system(:stream)(fn(emit) -> {count_from(emit, 1)})(:take, 100000)(:fold, 0, fn(acc, x) -> {acc + x});
+
+    short = system(:stream)(fn(emit) -> {emit(:a); emit(:b)});
+    a = short(:next);
+    b = short(:next);
+
+    lines = system(:read_lines)('log.txt')(:filter, fn(l) -> {l != ''});
+    line_count = lines(:fold, 0, fn(acc, l) -> {acc + 1});
+
+    first == 0 && rest == 60 && evens(:next) == nil && big == 5000050000
+    && a == :a && b == :b && short(:next) == nil && short(:next) == nil
+    && line_count == 2 && system(:read_lines)('missing')(1) == :not_found
+}
+"#;
+    let fs = Arc::new(MemoryFs::new());
+    fs.insert_file("log.txt", "first\r\n\nsecond");
+    let system = SystemBuilder::new().with_fs(fs).build();
+
+    let code = compile_source_to_code(source_code);
+    assert!(code.run_compare("main", vec![Value::DataFunc(system)], Value::Bool(true)).unwrap());
+}
+
+#[test]
+fn print_sinks() {
+    use crate::sandbox::SystemBuilder;
+
+    let source_code = r#"
+def main(system) {
+    system(:println)('plain text');
+    system(:print)(1);
+    system(:print)(:atom);
+    system(:println)(' and a "quote"');
+    system(:eprintln)('oops') == 'oops'
+}
+"#;
+    let (out, err) = (Lines::default(), Lines::default());
+    let system = SystemBuilder::new().with_println_sink(out.clone()).with_eprintln_sink(err.clone()).build();
+
+    let code = compile_source_to_code(source_code);
+    assert!(code.run_compare("main", vec![Value::DataFunc(system)], Value::Bool(true)).unwrap());
+    assert_eq!(out.text(), "plain text\n1:atom and a \"quote\"\n");
+    assert_eq!(err.text(), "oops\n");
+}
+
+#[cfg(unix)]
+//running and talking to programs is tested in process.rs
+#[test]
+fn subprocesses() {
+    use crate::sandbox::SystemBuilder;
+
+    let source_code = r#"
+def args(system, s) {
+    system(:string)(:split)(s, '|')
+}
+
+def run(system) {
+    system(:run)('sh', args(system, '-c|echo hi'))(:stdout)
+}
+
+def confined_run(system) {
+    system(:run)('sh', args(system, '-c|true'))(1)
+}
+
+def confined_start(system) {
+    system(:start_process)('cat', args(system, 'x'))(1)
+}
+"#;
+    let code = compile_source_to_code(source_code);
+    code.run_map("run", vec![Value::StaticFunc(system)], |v| assert_eq!(v, Value::String(Arc::new("hi\n".into())))).unwrap();
+
+    let denied = Value::Atom(code.table.write().unwrap().get_id(":permission_denied"));
+    let system = SystemBuilder::new().deny_fs().build();
+    for name in ["confined_run", "confined_start"] {
+        code.run_map(name, vec![Value::DataFunc(system.clone())], |v| assert_eq!(v, denied, "{}", name)).unwrap();
+    }
+}
+
+//messages and scheduling are tested in scheduler.rs
+#[test]
+fn processes() {
+    use crate::scheduler::Mode;
+    use crate::reporting::Error;
+
+    let source_code = r#"
+def no_args(system) {
+    system(:string)(:split)('', '')
+}
+
+def main(system) {
+    me = system(:self_pid)();
+    echoer = system(:spawn)(fn() -> {system(:send)(me, system(:receive)(nil) + '!')}, no_args(system));
+    system(:send)(echoer, 'hi');
+    system(:receive)(nil)
+}
+
+def stuck(system) {
+    system(:receive)(nil)
+}
+
+def alone(system) {
+    system(:self_pid)()
+}
+"#;
+    let code = compile_source_to_code(source_code);
+    for mode in [Mode::RoundRobin, Mode::Threads(4)] {
+        let expected = Value::String(Arc::new("hi!".into()));
+        code.run_processes("main", vec![Value::StaticFunc(system)], mode, |v| assert_eq!(v, expected, "{:?}", mode)).unwrap();
+    }
+
+    let err = code.run_processes("stuck", vec![Value::StaticFunc(system)], Mode::RoundRobin, |_| ()).unwrap_err();
+    assert!(matches!(err.front(), Some(Error::Deadlock)));
+
+    //without a scheduler the yield comes back as an error
+    let err = code.run("alone", vec![Value::StaticFunc(system)]).unwrap_err();
+    assert!(matches!(err.front(), Some(Error::Yield(_))));
+}
+
+//links, monitors and restarts are tested in scheduler.rs
+#[test]
+fn supervision() {
+    use crate::scheduler::Mode;
+
+    let source_code = r#"
+def give_up(system) {
+    system(:supervise)(:one_for_one, 2, fn() -> {1 / 0});
+    system(:receive)(nil)(2)
+}
+"#;
+    let code = compile_source_to_code(source_code);
+    let shutdown = Value::Atom(code.table.write().unwrap().get_id(":shutdown"));
+    code.run_processes("give_up", vec![Value::StaticFunc(system)], Mode::RoundRobin, |v| assert_eq!(v, shutdown)).unwrap();
+}
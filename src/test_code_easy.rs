@@ -80,7 +80,7 @@ fn string_escaping() {
     // Step 2: Compile the source code to a `Code` object
     let code = compile_source_to_code(source_code);
 
-    let s = Arc::new("string\n".to_string());
+    let s = Arc::new("string\n".into());
 
     // Step 3: Run the translated code and call the "main" function with the arguments
     assert!(code.run_compare("main", vec![],Value::String(s)).unwrap());
@@ -504,7 +504,7 @@ fn test_recursive_lambda_string_accumulation() {
     "#;
     let code = compile_source_to_code(source_code);
 
-    assert!(code.run_compare("main", vec![], Value::String(Arc::new("54321".to_string()))).unwrap());
+    assert!(code.run_compare("main", vec![], Value::String(Arc::new("54321".into()))).unwrap());
 }
 
 #[test]
@@ -553,5 +553,97 @@ fn bug_main_rev() {
 
     println!("\n\n");
 
-    code.run("reverse_string",vec![Value::String("hey there".to_string().into())]).unwrap();
-}
\ No newline at end of file
+    code.run("reverse_string",vec![Value::String(Arc::new("hey there".into()))]).unwrap();
+}
+#[test]
+fn suspended_host_calls() {
+    use std::sync::Mutex;
+    use crate::basic_ops::{pop_args,push};
+    use crate::reporting::Error;
+    use crate::vm::{DataFunc,Run};
+
+    let source_code = "
+        def main(fetch, x) {
+            fetch(x) + fetch(x + 1)
+        }
+
+        def in_stream(fetch, system) {
+            system(:stream)(fn(emit) -> {emit(fetch(1))})(:next)
+        }
+    ";
+    let code = compile_source_to_code(source_code);
+    let global = code.get_global();
+    let main = *code.name_map.get("main").unwrap();
+    let Some(Value::Func(main)) = global.get(main) else { todo!() };
+    let table = &*code.table.read().unwrap();
+
+    //fetch only records what was asked for, the host answers later
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let asked = requests.clone();
+    let fetch = DataFunc::new(move |stack, _table| {
+        let [Value::Int(x)] = pop_args(stack)? else { return Err(sig_error()) };
+        let mut asked = asked.lock().unwrap();
+        let token = asked.len() as u64;
+        asked.push(x);
+        push(stack,Value::DataFunc(DataFunc::pending(token)))
+    });
+
+    //two scripts waiting on the same thread (boxed since a context holds its whole call stack)
+    let mut contexts:Vec<Box<Context>> = [1,10].into_iter().map(|x| {
+        let mut context = Box::new(Context::new(main.clone(),&global,table));
+        context.stack.push_value(Value::DataFunc(fetch.clone())).unwrap();
+        context.stack.push_int(x).unwrap();
+        context
+    }).collect();
+
+    let mut waiting:Vec<u64> = contexts.iter_mut().map(|c| match c.run_suspendable().unwrap() {
+        Run::Suspended(token) => token,
+        Run::Done(v) => panic!("finished early with {:?}",v),
+    }).collect();
+    assert_eq!(waiting, [0,1]);
+
+    let mut answers = Vec::new();
+    while !contexts.is_empty() {
+        let token = waiting.remove(0);
+        let mut context = contexts.remove(0);
+        let x = requests.lock().unwrap()[token as usize];
+        match context.resume(token,Value::Int(x * 100)).unwrap() {
+            Run::Suspended(token) => {
+                waiting.push(token);
+                contexts.push(context);
+            },
+            Run::Done(v) => answers.push(v),
+        }
+    }
+    assert_eq!(answers, [Value::Int(300),Value::Int(2100)]);
+    assert_eq!(*requests.lock().unwrap(), [1,10,2,11]);
+
+    //a wrong token leaves the call waiting
+    let mut context = Box::new(Context::new(main.clone(),&global,table));
+    context.stack.push_value(Value::DataFunc(fetch.clone())).unwrap();
+    context.stack.push_int(5).unwrap();
+    let Run::Suspended(token) = context.run_suspendable().unwrap() else { panic!() };
+    assert_eq!(context.resume(token + 1,Value::Nil).unwrap_err().front(), Some(&Error::NotSuspended(token + 1)));
+    assert!(matches!(context.resume(token,Value::Int(1)), Ok(Run::Suspended(_))));
+
+    //plain run has no one to resume it
+    let mut context = Box::new(Context::new(main,&global,table));
+    context.stack.push_value(Value::DataFunc(fetch.clone())).unwrap();
+    context.stack.push_int(5).unwrap();
+    assert!(matches!(context.run().unwrap_err().front(), Some(Error::Suspend(_))));
+
+    //neither does a generator, which runs inside the op pulling it
+    let in_stream = *code.name_map.get("in_stream").unwrap();
+    let Some(Value::Func(in_stream)) = global.get(in_stream) else { todo!() };
+    let mut context = Box::new(Context::new(in_stream,&global,table));
+    context.stack.push_value(Value::DataFunc(fetch)).unwrap();
+    context.stack.push_value(Value::StaticFunc(crate::system::system)).unwrap();
+    let mut err = context.run_suspendable().unwrap_err();
+    let cause = loop {
+        match err.pop_front() {
+            Some(Error::Stacked(inner) | Error::StackedTail(inner)) => err = inner.err,
+            other => break other,
+        }
+    };
+    assert!(matches!(cause, Some(Error::Suspend(_))), "{:?}", cause);
+}
@@ -0,0 +1,129 @@
+// Runs the `def test_*()` functions of a program, each in its own fresh context.
+// A test passes when it returns true or :ok. Tests that take an argument get system.
+
+use ast::id::OK_ID;
+
+use crate::basic_ops::to_string_debug;
+use crate::reporting::{report_err_list, ErrList};
+use crate::runtime::Code;
+use crate::translate::TEST_PREFIX;
+use crate::system::system;
+use crate::value::Value;
+
+#[derive(Debug, PartialEq)]
+pub enum TestOutcome {
+    Passed,
+    Returned(String),
+    Errored(ErrList),
+    BadSignature(usize),
+}
+
+#[derive(Debug, PartialEq, Default, Clone, Copy)]
+pub struct TestSummary {
+    pub passed: usize,
+    pub failed: usize,
+    pub filtered: usize,
+}
+
+impl TestSummary {
+    pub fn add(&mut self, other: TestSummary) {
+        self.passed += other.passed;
+        self.failed += other.failed;
+        self.filtered += other.filtered;
+    }
+}
+
+// test names in the order they are defined
+pub fn test_names(code: &Code) -> Vec<String> {
+    let table = code.table.read().unwrap();
+    code.names
+        .iter()
+        .map(|id| table.get_raw_str(*id))
+        .filter(|name| name.starts_with(TEST_PREFIX))
+        .map(|name| name.to_string())
+        .collect()
+}
+
+pub fn run_test(code: &Code, name: &str) -> TestOutcome {
+    let num_args = code.funcs[code.name_map[name]].num_args;
+    let args = match num_args {
+        0 => vec![],
+        1 => vec![Value::StaticFunc(system)],
+        n => return TestOutcome::BadSignature(n),
+    };
+
+    let table = code.table.read().unwrap();
+    let ans = code.run_map(name, args, |value| match value {
+        Value::Bool(true) | Value::Atom(OK_ID) => TestOutcome::Passed,
+        other => TestOutcome::Returned(to_string_debug(&other, &table)),
+    });
+    match ans {
+        Ok(outcome) => outcome,
+        Err(err) => TestOutcome::Errored(err),
+    }
+}
+
+// runs every test whose name contains the filter and prints the results
+pub fn run_tests(code: &Code, source: &str, filter: Option<&str>) -> TestSummary {
+    let mut summary = TestSummary::default();
+    let names = test_names(code);
+    let (names, filtered): (Vec<_>, Vec<_>) = names
+        .into_iter()
+        .partition(|name| filter.is_none_or(|f| name.contains(f)));
+    summary.filtered = filtered.len();
+
+    println!("running {} tests", names.len());
+    for name in names {
+        match run_test(code, &name) {
+            TestOutcome::Passed => {
+                println!("test {} ... ok", name);
+                summary.passed += 1;
+                continue;
+            }
+            TestOutcome::Returned(value) => {
+                println!("test {} ... FAILED (returned {})", name, value);
+            }
+            TestOutcome::Errored(err) => {
+                println!("test {} ... FAILED", name);
+                report_err_list(&err, source, &code.table.read().unwrap());
+            }
+            TestOutcome::BadSignature(n) => {
+                println!("test {} ... FAILED (tests take no arguments or just system, this takes {})", name, n);
+            }
+        }
+        summary.failed += 1;
+    }
+    summary
+}
+
+#[test]
+fn runs_tests_in_order() {
+    use crate::translate::compile_source_to_code;
+
+    let source = "
+        def helper(x) { x + 1 }
+        def test_bool() { helper(1) == 2 }
+        def test_atom() { :ok }
+        def test_wrong() { helper(1) }
+        def test_error() { helper(:a) }
+        def test_system(system) { system(:type)(1) == :int }
+        def test_args(a, b) { true }
+        def main(system) { helper(1) }
+    ";
+    let code = compile_source_to_code(source);
+
+    let names = test_names(&code);
+    assert_eq!(names, ["test_bool", "test_atom", "test_wrong", "test_error", "test_system", "test_args"]);
+
+    assert_eq!(run_test(&code, "test_bool"), TestOutcome::Passed);
+    assert_eq!(run_test(&code, "test_atom"), TestOutcome::Passed);
+    assert_eq!(run_test(&code, "test_wrong"), TestOutcome::Returned("int(2)".to_string()));
+    assert!(matches!(run_test(&code, "test_error"), TestOutcome::Errored(_)));
+    assert_eq!(run_test(&code, "test_system"), TestOutcome::Passed);
+    assert_eq!(run_test(&code, "test_args"), TestOutcome::BadSignature(2));
+
+    let summary = run_tests(&code, source, Some("_b"));
+    assert_eq!(summary, TestSummary { passed: 1, failed: 0, filtered: 5 });
+    let summary = run_tests(&code, source, None);
+    assert_eq!(summary, TestSummary { passed: 3, failed: 3, filtered: 0 });
+}
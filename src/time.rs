@@ -0,0 +1,252 @@
+// Clocks and dates, reached with system(:now) and friends.
+// Dates are always UTC, the calendar math is the days_from_civil/civil_from_days pair
+// from http://howardhinnant.github.io/date_algorithms.html
+
+use std::sync::{Arc, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use ast::ast::StringTable;
+use ast::get_id;
+use ast::id::*;
+
+use crate::basic_ops::{pop_args, push};
+use crate::reporting::*;
+use crate::stack::ValueStack;
+use crate::system::make_map;
+use crate::value::Value;
+
+// monotonic nanoseconds, only useful for measuring the time between two calls
+pub fn now_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+    static START: OnceLock<Instant> = OnceLock::new();
+    let [] = pop_args(stack)?;
+    let nanos = START.get_or_init(Instant::now).elapsed().as_nanos();
+    push(stack, Value::Int(nanos as i64))
+}
+
+// whole seconds since 1970-01-01T00:00:00Z
+pub fn unix_time_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+    let [] = pop_args(stack)?;
+    let secs = match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(d) => d.as_secs() as i64,
+        Err(e) => -(e.duration().as_secs_f64().ceil() as i64),
+    };
+    push(stack, Value::Int(secs))
+}
+
+pub fn sleep_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+    let [Value::Int(ms)] = pop_args(stack)? else {
+        return Err(sig_error());
+    };
+    let Ok(ms) = u64::try_from(ms) else {
+        return Err(sig_error());
+    };
+    thread::sleep(Duration::from_millis(ms));
+    push(stack, Value::Atom(get_id!(":ok")))
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct DateTime {
+    pub year: i64,
+    pub month: i64,
+    pub day: i64,
+    pub hour: i64,
+    pub minute: i64,
+    pub second: i64,
+}
+
+fn days_from_civil(y: i64, m: i64, d: i64) -> i64 {
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * ((m + 9) % 12) + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+fn civil_from_days(z: i64) -> (i64, i64, i64) {
+    let z = z + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    let y = yoe + era * 400;
+    (if m <= 2 { y + 1 } else { y }, m, d)
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// fields of a fixed width all made of digits, split by sep
+fn fixed_fields<const N: usize>(s: &str, sep: char, widths: [usize; N]) -> Option<[i64; N]> {
+    let mut parts = s.split(sep);
+    let mut ans = [0; N];
+    for (field, width) in ans.iter_mut().zip(widths) {
+        let part = parts.next()?;
+        if part.len() != width || !part.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        *field = part.parse().ok()?;
+    }
+    parts.next().is_none().then_some(ans)
+}
+
+impl DateTime {
+    pub fn from_unix(secs: i64) -> Self {
+        let (year, month, day) = civil_from_days(secs.div_euclid(86400));
+        let rem = secs.rem_euclid(86400);
+        DateTime { year, month, day, hour: rem / 3600, minute: rem % 3600 / 60, second: rem % 60 }
+    }
+
+    // None when the seconds do not fit in an i64
+    pub fn to_unix(&self) -> Option<i64> {
+        let secs = self.hour * 3600 + self.minute * 60 + self.second;
+        days_from_civil(self.year, self.month, self.day).checked_mul(86400)?.checked_add(secs)
+    }
+
+    // accepts YYYY-MM-DD with an optional THH:MM:SS[.fraction] and an optional Z or +00:00
+    // the fraction is dropped since dates only keep whole seconds
+    pub fn parse(s: &str) -> Option<Self> {
+        let s = s.trim();
+        let s = s.strip_suffix('Z').or_else(|| s.strip_suffix("+00:00")).unwrap_or(s);
+        let (date, time) = match s.split_once('T') {
+            Some((date, time)) => (date, Some(time)),
+            None => (s, None),
+        };
+
+        let [year, month, day] = fixed_fields(date, '-', [4, 2, 2])?;
+        let [hour, minute, second] = match time {
+            None => [0, 0, 0],
+            Some(time) => {
+                let time = match time.split_once('.') {
+                    Some((time, frac)) if !frac.is_empty() && frac.bytes().all(|b| b.is_ascii_digit()) => time,
+                    Some(_) => return None,
+                    None => time,
+                };
+                fixed_fields(time, ':', [2, 2, 2])?
+            }
+        };
+
+        let valid = (1..=12).contains(&month)
+            && (1..=days_in_month(year, month)).contains(&day)
+            && hour < 24
+            && minute < 60
+            && second < 60;
+        valid.then_some(DateTime { year, month, day, hour, minute, second })
+    }
+
+    pub fn to_iso(&self) -> String {
+        format!(
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )
+    }
+
+    fn to_map(self) -> Option<Value<'static>> {
+        let entries = vec![
+            (Value::Atom(get_id!(":year")), Value::Int(self.year)),
+            (Value::Atom(get_id!(":month")), Value::Int(self.month)),
+            (Value::Atom(get_id!(":day")), Value::Int(self.day)),
+            (Value::Atom(get_id!(":hour")), Value::Int(self.hour)),
+            (Value::Atom(get_id!(":minute")), Value::Int(self.minute)),
+            (Value::Atom(get_id!(":second")), Value::Int(self.second)),
+            (Value::Atom(get_id!(":unix")), Value::Int(self.to_unix()?)),
+        ];
+        Some(Value::DataFunc(make_map(entries)))
+    }
+}
+
+// format_time(unix) gives an ISO-8601 string like 2024-03-01T12:00:00Z or :err when the date is out of range
+pub fn format_time_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+    let [Value::Int(secs)] = pop_args(stack)? else {
+        return Err(sig_error());
+    };
+    let date = DateTime::from_unix(secs);
+    match date.to_unix() {
+        Some(_) => stack.push_string(Arc::new(date.to_iso().into())).map_err(|_| overflow_error()),
+        None => push(stack, Value::Atom(get_id!(":err"))),
+    }
+}
+
+// parse_time(s) gives the same map as date or :err
+pub fn parse_time_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+    let [Value::String(s)] = pop_args(stack)? else {
+        return Err(sig_error());
+    };
+    match DateTime::parse(&s).and_then(DateTime::to_map) {
+        Some(date) => push(stack, date),
+        None => push(stack, Value::Atom(get_id!(":err"))),
+    }
+}
+
+// date(unix) gives a map with :year :month :day :hour :minute :second and :unix or :err when out of range
+pub fn date_fn<'code>(stack: &mut ValueStack<'code>, _table: &StringTable<'code>) -> Result<(), ErrList> {
+    let [Value::Int(secs)] = pop_args(stack)? else {
+        return Err(sig_error());
+    };
+    match DateTime::from_unix(secs).to_map() {
+        Some(date) => push(stack, date),
+        None => push(stack, Value::Atom(get_id!(":err"))),
+    }
+}
+
+#[test]
+fn test_dates() {
+    let date = DateTime::parse("2024-02-29T23:59:58Z").unwrap();
+    assert_eq!(date, DateTime { year: 2024, month: 2, day: 29, hour: 23, minute: 59, second: 58 });
+    assert_eq!(date.to_unix(), Some(1709251198));
+    assert_eq!(DateTime::from_unix(1709251198), date);
+    assert_eq!(date.to_iso(), "2024-02-29T23:59:58Z");
+
+    assert_eq!(DateTime::from_unix(0).to_iso(), "1970-01-01T00:00:00Z");
+    assert_eq!(DateTime::from_unix(-1).to_iso(), "1969-12-31T23:59:59Z");
+    assert_eq!(DateTime::parse("2000-01-01").unwrap().to_unix(), Some(946684800));
+    assert_eq!(DateTime::parse("2000-01-01T00:00:01.250+00:00").unwrap().to_unix(), Some(946684801));
+
+    for bad in ["2023-02-29", "2024-13-01", "2024-1-01", "2024-01-01T24:00:00Z", "2024-01-01T10:00:00+02:00", "2024-01-01T10:00:00."] {
+        assert_eq!(DateTime::parse(bad), None, "{}", bad);
+    }
+}
+
+#[cfg(test)]
+use crate::basic_ops::{call_native, text};
+
+#[test]
+fn test_clock() {
+    let start = call_native(now_fn, []).unwrap();
+    assert_eq!(call_native(sleep_fn, [Value::Int(2)]).unwrap(), Value::Atom(get_id!(":ok")));
+    let (Value::Int(start), Value::Int(end)) = (start, call_native(now_fn, []).unwrap()) else { panic!() };
+    assert!(end - start >= 2_000_000);
+    assert!(call_native(sleep_fn, [Value::Int(-1)]).is_err());
+    let Value::Int(unix) = call_native(unix_time_fn, []).unwrap() else { panic!() };
+    assert!(unix > 1700000000);
+}
+
+// the values of a date map in order
+#[cfg(test)]
+fn fields(date: &Value) -> Vec<Value<'static>> {
+    let Value::DataFunc(map) = date else { panic!("expected a map got {:?}", date) };
+    let crate::vm::DataKind::Map(entries) = map.kind() else { panic!("expected a map") };
+    entries.iter().map(|(_, v)| v.clone()).collect()
+}
+
+#[test]
+fn test_date_natives() {
+    let expected = [2024, 3, 1, 12, 30, 5, 1709296205].map(Value::Int);
+    assert_eq!(fields(&call_native(parse_time_fn, [text("2024-03-01T12:30:05Z")]).unwrap()), expected);
+    assert_eq!(fields(&call_native(date_fn, [Value::Int(1709296205)]).unwrap()), expected);
+    assert_eq!(call_native(format_time_fn, [Value::Int(1709296205 + 86400)]).unwrap(), text("2024-03-02T12:30:05Z"));
+    assert_eq!(call_native(parse_time_fn, [text("2024-02-30")]).unwrap(), Value::Atom(get_id!(":err")));
+    assert_eq!(call_native(date_fn, [Value::Int(i64::MIN)]).unwrap(), Value::Atom(get_id!(":err")));
+    assert_eq!(call_native(format_time_fn, [Value::Int(i64::MIN)]).unwrap(), Value::Atom(get_id!(":err")));
+    assert_eq!(fields(&call_native(date_fn, [Value::Int(i64::MAX)]).unwrap())[6], Value::Int(i64::MAX));
+}
@@ -0,0 +1,163 @@
+// Moving values between programs compiled with different StringTables.
+// Atoms are looked up by name in the destination table (new names get added), native arrays and maps
+// are rebuilt with their contents moved, other host functions do not move and closures move only when
+// nothing in their code belongs to the program they came from. strings and bytes do not depend on the table so they are shared.
+
+use std::sync::Arc;
+
+use ast::ast::StringTable;
+
+use crate::reporting::*;
+use crate::system::{make_array, make_map};
+use crate::value::{Value, VarTable};
+use crate::vm::{DataFunc, DataKind, FuncData, Operation};
+
+// value as the program using the `to` table would have written it.
+// the result still borrows the code of a closure it holds, the tables are only read for names.
+pub fn transfer<'v, 't, 'src: 't>(
+    value: &Value<'v>,
+    from: &StringTable<'src>,
+    to: &mut StringTable<'t>,
+) -> Result<Value<'v>, ErrList> {
+    match value {
+        Value::Func(f) => transfer_func(f, from, to),
+        Value::WeakFunc(f) => transfer_func(&f.upgrade().ok_or_else(|| bug_error("weak function failed to upgrade"))?, from, to),
+        _ => transfer_data(value, from, to),
+    }
+}
+
+fn transfer_func<'v, 't, 'src: 't>(
+    f: &Arc<FuncData<'v>>,
+    from: &StringTable<'src>,
+    to: &mut StringTable<'t>,
+) -> Result<Value<'v>, ErrList> {
+    if !portable(f.code) {
+        return Err(transfer_error("the closure uses atoms or globals of its own program"));
+    }
+    let mut vars = VarTable {
+        data: Vec::with_capacity(f.vars.data.len()),
+        names: f.vars.names.iter().map(|id| to.get_id(from.get_raw_str(*id))).collect(),
+    };
+    for v in f.vars.data.iter() {
+        let v = match v {
+            Some(v) => Some(transfer(v, from, to)?),
+            None => None,
+        };
+        vars.data.push(v);
+    }
+    //the template only holds the names of locals, used for error messages
+    Ok(Value::Func(Arc::new(FuncData::new(vars, f.mut_vars_template, f.code, f.num_args))))
+}
+
+//the code holds atom ids and global slots as numbers so none can be in it (or in a closure it makes)
+fn portable(code: &[Operation]) -> bool {
+    code.iter().all(|op| match op {
+        Operation::PushGlobal(_) | Operation::PushAtom(_) => false,
+        Operation::MatchJump(m) => !m.map.keys().any(|k| matches!(k, Value::Atom(_))),
+        Operation::CaptureClosure(maker) => {
+            portable(&maker.holder.code) && !maker.holder.vars.data.iter().flatten().any(|v| matches!(v, Value::Atom(_)))
+        }
+        _ => true,
+    })
+}
+
+//everything but script closures, the result borrows nothing so a native collection can hold it
+fn transfer_data<'t, 'src: 't>(value: &Value<'_>, from: &StringTable<'src>, to: &mut StringTable<'t>) -> Result<Value<'static>, ErrList> {
+    let value = match value {
+        Value::Nil => Value::Nil,
+        Value::Bool(b) => Value::Bool(*b),
+        Value::Int(i) => Value::Int(*i),
+        Value::Float(f) => Value::Float(*f),
+        Value::Atom(id) => Value::Atom(to.get_id(from.get_raw_str(*id))),
+        Value::String(s) => Value::String(s.clone()),
+        Value::Bytes(b) => Value::Bytes(b.clone()),
+        Value::StaticFunc(f) => Value::StaticFunc(*f),
+        Value::DataFunc(f) => transfer_collection(f, from, to)?,
+        Value::Func(_) | Value::WeakFunc(_) => return Err(transfer_error("a collection holds a closure")),
+        Value::Stream(_) => return Err(transfer_error("a stream is tied to the program pulling it")),
+    };
+    Ok(value)
+}
+
+//arrays and maps are rebuilt, any other data func is host code that can not be checked for atoms
+fn transfer_collection<'t, 'src: 't>(f: &DataFunc, from: &StringTable<'src>, to: &mut StringTable<'t>) -> Result<Value<'static>, ErrList> {
+    match f.kind() {
+        DataKind::Map(entries) => {
+            let entries = entries
+                .iter()
+                .map(|(k, v)| Ok((transfer_data(k, from, to)?, transfer_data(v, from, to)?)))
+                .collect::<Result<_, ErrList>>()?;
+            Ok(Value::DataFunc(make_map(entries)))
+        }
+        DataKind::Array(items) => {
+            let items = items.iter().map(|v| transfer_data(v, from, to)).collect::<Result<_, _>>()?;
+            Ok(Value::DataFunc(make_array(items)))
+        }
+        DataKind::Host | DataKind::Pending(_) => Err(transfer_error("a host function may hold atoms of its own program")),
+    }
+}
+
+#[test]
+fn test_transfer() {
+    use crate::translate::compile_source_to_code;
+
+    let source = r#"
+def values() {
+    :apple
+}
+
+def adder(n) {
+    fn(x) -> {x + n}
+}
+
+def named() {
+    fn() -> {:apple}
+}
+
+def calls_global() {
+    fn() -> {values()}
+}
+"#;
+    let dest = r#"
+def check(a, f, items) {
+    a == :apple && f(1) == 6 && items(0) == :pear && items(1) == 'x'
+}
+
+def other() {
+    :zzz
+}
+"#;
+    let src = compile_source_to_code(source);
+    let dst = compile_source_to_code(dest);
+    let pear = Value::Atom(src.table.write().unwrap().get_id(":pear"));
+    let apple = Value::Atom(src.run_map("values", vec![], |v| match v {
+        Value::Atom(a) => a,
+        _ => panic!("expected an atom"),
+    }).unwrap());
+    assert_ne!(apple, Value::Atom(dst.table.write().unwrap().get_id(":apple")), "the tables should disagree");
+
+    let from = &*src.table.read().unwrap();
+    let passed = src.run_map("adder", vec![Value::Int(5)], |f| {
+        let items = Value::DataFunc(make_array(vec![pear, Value::String(Arc::new(String::from("x").into()))]));
+        let args = {
+            let to = &mut *dst.table.write().unwrap();
+            [transfer(&apple, from, to), transfer(&f, from, to), transfer(&items, from, to)]
+        };
+        let args = args.into_iter().collect::<Result<Vec<_>, _>>().unwrap();
+        dst.run_compare("check", args, Value::Bool(true)).unwrap()
+    });
+    assert!(passed.unwrap());
+
+    for name in ["named", "calls_global"] {
+        src.run_map(name, vec![], |f| {
+            let to = &mut *dst.table.write().unwrap();
+            assert!(matches!(transfer(&f, from, to).unwrap_err().front(), Some(Error::Transfer(_))), "{} moved", name);
+        }).unwrap();
+    }
+
+    let host = Value::DataFunc(DataFunc::new(crate::system::print_fn));
+    let to = &mut *dst.table.write().unwrap();
+    assert!(matches!(transfer(&host, from, to).unwrap_err().front(), Some(Error::Transfer(_))));
+    let nested = Value::DataFunc(make_array(vec![host]));
+    assert!(matches!(transfer(&nested, from, to).unwrap_err().front(), Some(Error::Transfer(_))));
+}
@@ -13,7 +13,8 @@ use ast::ast::{
 };
 
 
-use crate::reporting::{report_parse_error,report_err_list,ErrList,stacked_error,sig_error,missing_error,unreachable_func_error};
+use crate::reporting::{report_parse_error,report_err_list,ErrList,Warning,NameWarning,stacked_error,sig_error,missing_error,unreachable_func_error};
+use ast::id::MAIN_ID;
 use crate::value::VarTable;
 use crate::value::Value as IRValue;
 use crate::vm::{Operation,StaticMatch};
@@ -23,6 +24,9 @@ use std::collections::hash_map::Entry;
 use std::sync::{RwLock,Arc};
 use crate::runtime::{Code,FuncHolder};
 
+//defs named like this are tests, run by the test runner and always counted as used
+pub const TEST_PREFIX: &str = "test_";
+
 #[derive(Debug,PartialEq,Clone,Copy)]
 enum CallType{
 	TailCall,
@@ -35,16 +39,35 @@ struct TransHandle<'a> {
 	mut_vars:&'a mut VarTable<'static>,
 	vars:&'a mut VarTable<'static>,
 	table:&'a StringTable<'a>,
+	errors:&'a mut ErrList,
+	warnings:&'a mut Vec<Warning>,
+	refs:&'a mut Vec<NameRef>,
+}
+
+//a use of a name and where it was defined (None for globals, which are found by name)
+//this is only used by tooling like the language server
+#[derive(Debug,PartialEq,Clone,Copy)]
+pub struct NameRef {
+	pub id:u32,
+	pub span:Span,
+	pub def:Option<Span>,
 }
 
 trait NameSpace {
-	fn set(&mut self,handle:&mut TransHandle,name:u32);
+	fn set(&mut self,handle:&mut TransHandle,name:u32,span:Span);
 
 	//called by child which DOES mutate the parent scope. but does so in a safe way
-	fn get(&mut self,handle:&mut TransHandle,name:u32) -> Result<(),ErrList>;
+	//returns false (and pushes nothing) when the name is not defined
+	fn get(&mut self,handle:&mut TransHandle,name:u32) -> bool;
 
 	//called by lambda and does NOT mutate anything. does not rely on handle in any way 
-	fn capture(&self,name:u32) -> Result<CaptureType,ErrList>;
+	fn capture(&self,name:u32) -> Option<CaptureType>;
+
+	//every name a lookup from this scope could find, used for "did you mean" suggestions
+	fn visible_names(&self,names:&mut Vec<u32>);
+
+	//where a local name was first assigned (args point at their function)
+	fn def_span(&self,name:u32) -> Option<Span>;
 }
 
 enum CaptureType {
@@ -52,42 +75,71 @@ enum CaptureType {
 	Local
 }
 
+//names that were assigned but not read yet (with the span of the first assignment)
+type Unread = HashMap<u32,Span>;
+
+fn warn_unread(unread:Unread,table:&StringTable,warnings:&mut Vec<Warning>) {
+	let mut unread :Vec<(u32,Span)> = unread.into_iter()
+		.filter(|(name,_)| !table.get_raw_str(*name).starts_with('_'))
+		.collect();
+	unread.sort_by_key(|(_,span)| span.start());
+
+	for (id,span) in unread {
+		warnings.push(Warning::UnusedVariable(NameWarning{id,span}));
+	}
+}
+
 struct FuncScope<'a> {
 	global_vars: &'a HashMap<u32,usize>,
 	assigns: HashMap<u32,usize>,
+	defs: HashMap<u32,Span>,
+	unread: Unread,
 }
 
 impl<'a> FuncScope<'a> {
-	fn start(global_vars: &'a HashMap<u32,usize>,args:&[u32]) -> Self {
+	fn start(global_vars: &'a HashMap<u32,usize>,args:&[u32],span:Span) -> Self {
 		let mut assigns = HashMap::new();
+		let mut defs = HashMap::new();
 		for (i,name) in args.iter().enumerate() {
 			assigns.insert(*name,i);
+			defs.insert(*name,span);
 		}
 		FuncScope{
 			global_vars,
-			assigns
+			assigns,
+			defs,
+			unread:HashMap::new(),
 		}
 	}
+
+	fn finish(self,table:&StringTable,warnings:&mut Vec<Warning>) {
+		warn_unread(self.unread,table,warnings);
+	}
 }
 
 impl NameSpace for FuncScope<'_> {
-	fn get(&mut self,handle: &mut TransHandle, name: u32) -> Result<(),ErrList> {
+	fn get(&mut self,handle: &mut TransHandle, name: u32) -> bool {
 		let op = match self.assigns.get(&name) {
-		    Some(id) => Operation::PushFrom(*id),
-		    None => {
-		    	let id = self.global_vars.get(&name).ok_or_else(|| missing_error(name))?;
-		    	Operation::PushGlobal(*id)
+		    Some(id) => {
+		    	self.unread.remove(&name);
+		    	Operation::PushFrom(*id)
+		    },
+		    None => match self.global_vars.get(&name) {
+		    	Some(id) => Operation::PushGlobal(*id),
+		    	None => return false,
 		    }
 		};
 		handle.code.push(op);
-		Ok(())
+		true
 	}
-	fn set(&mut self, handle: &mut TransHandle, name: u32) {
+	fn set(&mut self, handle: &mut TransHandle, name: u32, span: Span) {
 	    let op = match self.assigns.entry(name) {
 	        Entry::Occupied(entry) => {
 	            Operation::PopTo(*entry.get())
 	        }
 	        Entry::Vacant(spot) => {
+	            self.unread.insert(name,span);
+	            self.defs.insert(name,span);
 
 	            let id = handle.mut_vars.len();
 	            handle.mut_vars.add_ids(&[name]);
@@ -98,45 +150,70 @@ impl NameSpace for FuncScope<'_> {
 	    handle.code.push(op);
 	}
 
-	fn capture(&self,name:u32) -> Result<CaptureType,ErrList> {
+	fn capture(&self,name:u32) -> Option<CaptureType> {
 		match self.assigns.get(&name) {
-		    Some(_id) => Ok(CaptureType::Local),
-		    None => {
-		    	let id = self.global_vars.get(&name).ok_or_else(|| missing_error(name))?;
-		    	Ok(CaptureType::Global(*id))
-		    }
+		    Some(_id) => Some(CaptureType::Local),
+		    None => self.global_vars.get(&name).map(|id| CaptureType::Global(*id)),
 		}
 	}
+
+	fn visible_names(&self,names:&mut Vec<u32>) {
+		names.extend(self.assigns.keys());
+		names.extend(self.global_vars.keys());
+	}
+
+	fn def_span(&self,name:u32) -> Option<Span> {
+		self.defs.get(&name).copied()
+	}
 }
 
 struct ChildScope<'a> {
 	parent: &'a mut dyn NameSpace,
 	assigns: HashMap<u32,usize>,
+	defs: HashMap<u32,Span>,
+	unread: Unread,
 }
 
 impl<'a> ChildScope<'a> {
 	fn new(parent: &'a mut dyn NameSpace,) -> Self {
 		ChildScope{
 			parent,
-			assigns:HashMap::new()
+			assigns:HashMap::new(),
+			defs:HashMap::new(),
+			unread:HashMap::new(),
 		}
 	}
+
+	fn finish(self,table:&StringTable,warnings:&mut Vec<Warning>) {
+		warn_unread(self.unread,table,warnings);
+	}
 }
 
 impl NameSpace for ChildScope<'_> {
-	fn get(&mut self,handle: &mut TransHandle, name: u32) -> Result<(),ErrList> {
+	fn get(&mut self,handle: &mut TransHandle, name: u32) -> bool {
 		match self.assigns.get(&name) {
-		    Some(id) => handle.code.push(Operation::PushFrom(*id)),
-		    None => self.parent.get(handle,name)?,
+		    Some(id) => {
+		    	self.unread.remove(&name);
+		    	handle.code.push(Operation::PushFrom(*id))
+		    },
+		    None => return self.parent.get(handle,name),
 		};
-		Ok(())
+		true
 	}
-	fn set(&mut self, handle: &mut TransHandle, name: u32) {
+	fn set(&mut self, handle: &mut TransHandle, name: u32, span: Span) {
 	    let op = match self.assigns.entry(name) {
 	        Entry::Occupied(entry) => {
 	            Operation::PopTo(*entry.get())
 	        }
 	        Entry::Vacant(spot) => {
+	            //assigning in a child never changes the parent so this is easy to get wrong
+	            if let Some(CaptureType::Local) = self.parent.capture(name) {
+	            	if !handle.table.get_raw_str(name).starts_with('_') {
+	            		handle.warnings.push(Warning::Shadowing(NameWarning{id:name,span}));
+	            	}
+	            }
+	            self.unread.insert(name,span);
+	            self.defs.insert(name,span);
 
 	            let id = handle.mut_vars.len();
 	            handle.mut_vars.add_ids(&[name]);
@@ -147,12 +224,24 @@ impl NameSpace for ChildScope<'_> {
 	    handle.code.push(op);
 	}
 
-	fn capture(&self,name:u32) -> Result<CaptureType,ErrList> {
+	fn capture(&self,name:u32) -> Option<CaptureType> {
 		match self.assigns.get(&name) {
-		    Some(_id) => Ok(CaptureType::Local),
+		    Some(_id) => Some(CaptureType::Local),
 		    None => self.parent.capture(name),
 		}
 	}
+
+	fn visible_names(&self,names:&mut Vec<u32>) {
+		names.extend(self.assigns.keys());
+		self.parent.visible_names(names);
+	}
+
+	fn def_span(&self,name:u32) -> Option<Span> {
+		match self.defs.get(&name) {
+			Some(span) => Some(*span),
+			None => self.parent.def_span(name),
+		}
+	}
 }
 
 enum LambdaVar {
@@ -174,27 +263,43 @@ impl LambdaVar {
 struct LambdaScope<'a> {
     parent: &'a mut dyn NameSpace,
     map: HashMap<u32, LambdaVar>,
+    defs: HashMap<u32,Span>,
+    unread: Unread,
 }
 
 impl<'a> LambdaScope<'a> {
-    fn start(parent: &'a mut dyn NameSpace,args:&[u32]) -> Self {
+    fn start(parent: &'a mut dyn NameSpace,args:&[u32],span:Span) -> Self {
         let mut map = HashMap::new();
+        let mut defs = HashMap::new();
 		for (i,name) in args.iter().enumerate() {
 			map.insert(*name,LambdaVar::Mut(i));
+			defs.insert(*name,span);
 		}
         LambdaScope {
             parent,
-            map
+            map,
+            defs,
+            unread:HashMap::new(),
         }
     }
+
+    fn finish(self,table:&StringTable,warnings:&mut Vec<Warning>) {
+    	warn_unread(self.unread,table,warnings);
+    }
 }
 
 impl NameSpace for LambdaScope<'_> {
-	fn get(&mut self,handle:&mut TransHandle,name:u32) -> Result<(),ErrList> {
+	fn get(&mut self,handle:&mut TransHandle,name:u32) -> bool {
 		let op = match self.map.entry(name) {
-		    Entry::Occupied(entry) => entry.get().get_op(),
+		    Entry::Occupied(entry) => {
+		    	self.unread.remove(&name);
+		    	entry.get().get_op()
+		    },
 		    Entry::Vacant(spot) => {
-		    	let value = match self.parent.capture(name)?{
+		    	let Some(capture) = self.parent.capture(name) else {
+		    		return false;
+		    	};
+		    	let value = match capture {
 		    		CaptureType::Global(id) => LambdaVar::Global(id),
 		    		CaptureType::Local => {
 		    			let id = handle.vars.names.len();
@@ -210,14 +315,16 @@ impl NameSpace for LambdaScope<'_> {
 		};
 
 		handle.code.push(op);
-		Ok(())
+		true
 	}
 
-    fn set(&mut self,handle:&mut TransHandle,name:u32)  {
+    fn set(&mut self,handle:&mut TransHandle,name:u32,span:Span)  {
     	let op = match self.map.entry(name) {
     	   Entry::Occupied(mut entry) => match entry.get() {
     	       LambdaVar::Mut(id) => Operation::PopTo(*id),
     	       _ => {
+    	       	 self.unread.insert(name,span);
+    	       	 self.defs.insert(name,span);
     	       	 let id = handle.mut_vars.len();
 	             handle.mut_vars.add_ids(&[name]);
     	       	 entry.insert(LambdaVar::Mut(id));
@@ -226,6 +333,8 @@ impl NameSpace for LambdaScope<'_> {
     	   },
     	   Entry::Vacant(spot) => {
 	            // If the key doesn't exist, insert the new id
+	            self.unread.insert(name,span);
+	            self.defs.insert(name,span);
 	            let id = handle.mut_vars.len();
 	            handle.mut_vars.add_ids(&[name]);
 	            spot.insert(LambdaVar::Mut(id)); 
@@ -236,25 +345,112 @@ impl NameSpace for LambdaScope<'_> {
     	handle.code.push(op);
 
     }
-	fn capture(&self,name:u32) -> Result<CaptureType,ErrList> {
+	fn capture(&self,name:u32) -> Option<CaptureType> {
 		match self.map.get(&name) {
 		    None => self.parent.capture(name), 
 		    Some(v) => match v {
-		    	LambdaVar::Global(id) => Ok(CaptureType::Global(*id)),
-		    	LambdaVar::Mut(_) | LambdaVar::Captured(_) => Ok(CaptureType::Local),
+		    	LambdaVar::Global(id) => Some(CaptureType::Global(*id)),
+		    	LambdaVar::Mut(_) | LambdaVar::Captured(_) => Some(CaptureType::Local),
 		    }
 		}
 	}
+
+	fn visible_names(&self,names:&mut Vec<u32>) {
+		names.extend(self.map.keys());
+		self.parent.visible_names(names);
+	}
+
+	fn def_span(&self,name:u32) -> Option<Span> {
+		match self.defs.get(&name) {
+			Some(span) => Some(*span),
+			None => self.parent.def_span(name),
+		}
+	}
+}
+
+//pushes the value of a name. an undefined name is recorded as an error (with a suggestion)
+//and a placeholder is pushed so translation can keep going and find the rest of the errors
+fn push_name(name_space:&mut dyn NameSpace,handle:&mut TransHandle,name:u32,span:Span) {
+	if name_space.get(handle,name) {
+		handle.refs.push(NameRef{id:name,span,def:name_space.def_span(name)});
+		return;
+	}
+
+	let mut names = Vec::new();
+	name_space.visible_names(&mut names);
+	let suggestion = closest_name(name,&names,handle.table);
+
+	handle.errors.append(&mut missing_error(name,span,suggestion));
+	handle.code.push(Operation::PushNil);
+}
+
+//the values a closure captures, these uses were already recorded inside of it
+fn push_captures(names:&[u32],name_space:&mut dyn NameSpace,handle:&mut TransHandle,span:Span) {
+	for name in names {
+		if !name_space.get(handle,*name) {
+			handle.errors.append(&mut missing_error(*name,span,None));
+			handle.code.push(Operation::PushNil);
+		}
+	}
+}
+
+fn assign_name(name_space:&mut dyn NameSpace,handle:&mut TransHandle,name:u32,span:Span) {
+	name_space.set(handle,name,span);
+	handle.refs.push(NameRef{id:name,span,def:name_space.def_span(name)});
+}
+
+fn closest_name(name:u32,names:&[u32],table:&StringTable) -> Option<u32> {
+	let target = table.get_raw_str(name);
+	let max_dist = (target.chars().count()/3).max(1);
+
+	names.iter()
+		//0 is the hidden argument of match lambdas and is never a real name
+		.filter(|&&n| n!=0 && n!=name)
+		.map(|&n| (edit_distance(target,table.get_raw_str(n)),table.get_raw_str(n),n))
+		.filter(|(dist,_,_)| *dist<=max_dist)
+		.min()
+		.map(|(_,_,n)| n)
+}
+
+//edit distance that also counts swapping 2 neighbouring chars as 1 edit (a very common typo)
+fn edit_distance(a:&str,b:&str) -> usize {
+	let a :Vec<char> = a.chars().collect();
+	let b :Vec<char> = b.chars().collect();
+
+	let mut dist = vec![vec![0;b.len()+1];a.len()+1];
+	for (i,row) in dist.iter_mut().enumerate() {
+		row[0] = i;
+	}
+	dist[0] = (0..=b.len()).collect();
+
+	for i in 1..=a.len() {
+		for j in 1..=b.len() {
+			let cost = (a[i-1]!=b[j-1]) as usize;
+			let mut best = (dist[i-1][j-1]+cost).min(dist[i-1][j]+1).min(dist[i][j-1]+1);
+			if i>1 && j>1 && a[i-1]==b[j-2] && a[i-2]==b[j-1] {
+				best = best.min(dist[i-2][j-2]+1);
+			}
+			dist[i][j] = best;
+		}
+	}
+	dist[a.len()][b.len()]
 }
 
 
 
-pub fn translate_program<'a>(outer:&[OuterExp],table:Arc<RwLock<StringTable<'a>>>) ->Result<Code<'a>,ErrList> {
+pub fn translate_program<'a>(outer:&[OuterExp],table:Arc<RwLock<StringTable<'a>>>) ->Result<(Code<'a>,Vec<Warning>),ErrList> {
+	translate_program_with_refs(outer,table,&mut Vec::new())
+}
+
+//same as translate_program but also records every name use (even when translation fails)
+pub fn translate_program_with_refs<'a>(outer:&[OuterExp],table:Arc<RwLock<StringTable<'a>>>,refs:&mut Vec<NameRef>) ->Result<(Code<'a>,Vec<Warning>),ErrList> {
 	let mut funcs = Vec::with_capacity(outer.len());
 	let mut names = Vec::with_capacity(outer.len());
 	let mut name_map = HashMap::new();
 
 	let table_ref = table.read().unwrap();
+	let mut errors = ErrList::new();
+	let mut warnings = Vec::new();
 
 	//set up allow vars
 	let mut global_vars = HashMap::<u32,usize>::new();
@@ -264,7 +460,7 @@ pub fn translate_program<'a>(outer:&[OuterExp],table:Arc<RwLock<StringTable<'a>>
 			OuterExp::FuncDec(func) => {
 				match global_vars.entry(func.sig.name) {
 				    Entry::Occupied(_) => {
-				    	return Err(unreachable_func_error(func.sig.clone()));
+				    	errors.append(&mut unreachable_func_error(func.sig.clone()));
 				    },
 				    Entry::Vacant(v) => {
 				    	v.insert(i);
@@ -285,27 +481,76 @@ pub fn translate_program<'a>(outer:&[OuterExp],table:Arc<RwLock<StringTable<'a>>
 				let s = table_ref.get_raw_str(name);
 				name_map.insert(s.into(),index);
 
-				let mut scope = FuncScope::start(&global_vars,&func.sig.args);
-				funcs.push(translate_func(&func.sig.args,&func.body,&mut scope,&table_ref)?);
+				let mut scope = FuncScope::start(&global_vars,&func.sig.args,func.debug_span);
+				match translate_func(&func.sig.args,&func.body,&mut scope,&table_ref,&mut errors,&mut warnings,refs) {
+					Ok(holder) => funcs.push(holder),
+					//keep going so every broken function gets reported
+					Err(mut e) => errors.append(&mut e),
+				}
+				scope.finish(&table_ref,&mut warnings);
 			},
 		}
 	}
 
+	if !errors.is_empty() {
+		return Err(errors);
+	}
+
+	warn_unused_functions(outer,&funcs,&global_vars,&table_ref,&mut warnings);
 	std::mem::drop(table_ref);
 
-	Ok(Code{
+	Ok((Code{
 		funcs,
 		names,
 		table,
 		name_map,
-	})
+	},warnings))
 }
 
+//a def is used if main (or a test) can reach it through the globals its code pushes (including nested lambdas)
+fn warn_unused_functions(outer:&[OuterExp],funcs:&[FuncHolder],global_vars:&HashMap<u32,usize>,table:&StringTable,warnings:&mut Vec<Warning>) {
+	let Some(&main) = global_vars.get(&MAIN_ID) else {
+		//without an entry point there is nothing to be reachable from
+		return;
+	};
 
-fn translate_func<'a>(args:&[u32],body:&FuncBlock,name_space:&mut dyn NameSpace,table:&StringTable<'a>) -> Result<FuncHolder<'static>,ErrList> {
+	let mut reached = vec![false;funcs.len()];
+	let mut todo = vec![main];
+	todo.extend(global_vars.iter()
+		.filter(|(name,_)| table.get_raw_str(**name).starts_with(TEST_PREFIX))
+		.map(|(_,id)| *id));
+	while let Some(id) = todo.pop() {
+		if reached[id] {
+			continue;
+		}
+		reached[id] = true;
+		collect_globals(&funcs[id].code,&mut todo);
+	}
+
+	for (exp,reached) in outer.iter().zip(reached) {
+		if let OuterExp::FuncDec(func) = exp {
+			if !reached {
+				warnings.push(Warning::UnusedFunction(NameWarning{id:func.sig.name,span:func.debug_span}));
+			}
+		}
+	}
+}
+
+fn collect_globals(code:&[Operation],found:&mut Vec<usize>) {
+	for op in code {
+		match op {
+			Operation::PushGlobal(id) => found.push(*id),
+			Operation::CaptureClosure(maker) => collect_globals(&maker.holder.code,found),
+			_ => {},
+		}
+	}
+}
+
+
+fn translate_func<'a>(args:&[u32],body:&FuncBlock,name_space:&mut dyn NameSpace,table:&StringTable<'a>,errors:&mut ErrList,warnings:&mut Vec<Warning>,refs:&mut Vec<NameRef>) -> Result<FuncHolder<'static>,ErrList> {
 	let mut vars = VarTable::default();
 	let mut mut_vars = VarTable::default();
-	mut_vars.add_ids(&args);
+	mut_vars.add_ids(args);
 
 	let mut code = Vec::default();
 
@@ -313,7 +558,10 @@ fn translate_func<'a>(args:&[u32],body:&FuncBlock,name_space:&mut dyn NameSpace,
 		code:&mut code,
 		vars:&mut vars,
 		mut_vars:&mut mut_vars,
-		table
+		table,
+		errors,
+		warnings,
+		refs,
 	};
 
 	for x in body.body.iter() {
@@ -323,9 +571,9 @@ fn translate_func<'a>(args:&[u32],body:&FuncBlock,name_space:&mut dyn NameSpace,
 				handle.code.push(Operation::PopDump);
 				// handle.code.push(Operation::PushNil);
 			},
-			Statment::Assign(id, val) => {
+			Statment::Assign(id, span, val) => {
 				translate_value(val,name_space,&mut handle,FullCall)?;
-				name_space.set(&mut handle,*id);
+				assign_name(name_space,&mut handle,*id,*span);
 			},
 			Statment::Call(call) => {
 				translate_call_raw(call,name_space,&mut handle,FullCall)?;
@@ -376,7 +624,7 @@ fn translate_value(v:&AstValue,name_space:&mut dyn NameSpace,handle: &mut TransH
 		AstValue::Float(f) => handle.code.push(Operation::PushFloat(*f)),
 		AstValue::Int(i) => handle.code.push(Operation::PushInt(*i)),
 		AstValue::String(id) => {
-			let s = Arc::new(handle.table.get_escaped_string(*id));
+			let s = Arc::new(handle.table.get_escaped_string(*id).into());
 			handle.code.push(Operation::PushString(s))
 		},
 
@@ -384,7 +632,7 @@ fn translate_value(v:&AstValue,name_space:&mut dyn NameSpace,handle: &mut TransH
 		AstValue::FuncCall(call) => translate_call_raw(call,name_space,handle,tail)?,
 		AstValue::Match(m) => translate_match(m,name_space,handle,tail)?,
 
-		AstValue::Variable(id) => name_space.get(handle,*id)?,
+		AstValue::Variable(id,span) => push_name(name_space,handle,*id,*span),
 		AstValue::BuildIn(_) => unreachable!("build in op should never be made as a value in the ast"),
 		AstValue::Lambda(l) => translate_lambda(l,name_space,handle,tail)?, 
 		AstValue::MatchLambda(ml) => translate_match_lambda(ml,name_space,handle,tail)?, 
@@ -413,8 +661,8 @@ fn translate_call_raw(call:&FunctionCall,name_space:&mut dyn NameSpace,handle: &
 				handle.code.push(Operation::Call(call.debug_span));
 			}
 		},	
-		FValue::Name(id) => {
-			name_space.get(handle,*id)?;
+		FValue::Name(id,span) => {
+			push_name(name_space,handle,*id,*span);
 			match tail {
 				TailCall => handle.code.push(Operation::TailCall(call.debug_span)),
 				CallType::FullCall => handle.code.push(Operation::Call(call.debug_span)),
@@ -542,6 +790,7 @@ fn translate_match_internal(arms:&[MatchArm],span:Span,name_space:&mut dyn NameS
 	for r in return_spots {
 		handle.code[r] = Operation::Jump(handle.code.len());
 	}
+	scope.finish(handle.table,handle.warnings);
 
 
 	handle.code[in_id]=Operation::MatchJump(ans);
@@ -559,9 +808,9 @@ fn translate_block(block:&FuncBlock,parent_name_space:&mut dyn NameSpace,handle:
 				handle.code.push(Operation::PopDump);
 				// handle.code.push(Operation::PushNil);
 			},
-			Statment::Assign(id, val) => {
+			Statment::Assign(id, span, val) => {
 				translate_value(val,name_space,handle,FullCall)?;
-				name_space.set(handle,*id);
+				assign_name(name_space,handle,*id,*span);
 			},
 			Statment::Call(call) => {
 				translate_call_raw(call,name_space,handle,FullCall)?;
@@ -582,6 +831,7 @@ fn translate_block(block:&FuncBlock,parent_name_space:&mut dyn NameSpace,handle:
 	    	Ret::Imp(val) => translate_value(val,name_space,handle,tail)?
 	    },
 	}
+	scope.finish(handle.table,handle.warnings);
 
 	Ok(())
 }
@@ -592,24 +842,22 @@ fn literal_to_ir_value(l: &Literal,table:&StringTable) -> IRValue<'static> {
 		Literal::Int(i) => IRValue::Int(*i),
         Literal::Float(f) => IRValue::Float(*f),
         Literal::Atom(a) => IRValue::Atom(*a),
-        Literal::String(s) => IRValue::String(Arc::new(table.get_escaped_string(*s))),
+        Literal::String(s) => IRValue::String(Arc::new(table.get_escaped_string(*s).into())),
         Literal::Bool(b) => IRValue::Bool(*b),
         Literal::Nil => IRValue::Nil,
 	}
 }
 
 fn translate_lambda(l:&Lambda,name_space:&mut dyn NameSpace,handle:&mut TransHandle,_tail:CallType) -> Result<(),ErrList> {
-	let mut scope = LambdaScope::start(name_space,&l.sig);
-	let holder = translate_func(&l.sig,&l.body,&mut scope,handle.table)
+	let mut scope = LambdaScope::start(name_space,&l.sig,l.debug_span);
+	let holder = translate_func(&l.sig,&l.body,&mut scope,handle.table,handle.errors,handle.warnings,handle.refs)
 		.map_err(|e| stacked_error("while defining lambda",e,l.debug_span))?;
-	std::mem::drop(scope);
+	scope.finish(handle.table,handle.warnings);
         
     #[cfg(feature = "debug_terminators")]
     handle.code.push(Operation::PushTerminator);
 
-	for name in holder.vars.names.iter(){
-		name_space.get(handle,*name)?;
-	}
+	push_captures(&holder.vars.names,name_space,handle,l.debug_span);
 
 	let maker = Box::new(FuncMaker{
 		holder,
@@ -622,7 +870,7 @@ fn translate_lambda(l:&Lambda,name_space:&mut dyn NameSpace,handle:&mut TransHan
 
 fn translate_match_lambda(ml:&MatchLambda,name_space:&mut dyn NameSpace,handle:&mut TransHandle,_tail:CallType) -> Result<(),ErrList> {
 	//use the hack that 0 would NEVER be a name used by an identifier
-	let mut scope = LambdaScope::start(name_space,&[0]);
+	let mut scope = LambdaScope::start(name_space,&[0],ml.debug_span);
 
 	let mut vars = VarTable::default();
 	let mut mut_vars = VarTable::default();
@@ -634,13 +882,16 @@ fn translate_match_lambda(ml:&MatchLambda,name_space:&mut dyn NameSpace,handle:&
 		code:&mut code,
 		vars:&mut vars,
 		mut_vars:&mut mut_vars,
-		table: handle.table
+		table: handle.table,
+		errors: handle.errors,
+		warnings: handle.warnings,
+		refs: handle.refs,
 	};
 
 
 	translate_match_internal(&ml.arms,ml.debug_span,&mut scope,&mut child_handle,TailCall)?;
 	code.push(Operation::Return);
-	std::mem::drop(scope);
+	scope.finish(handle.table,handle.warnings);
     
     let holder = FuncHolder{
     	code: code.into(),vars,mut_vars_template:mut_vars,num_args:1
@@ -649,9 +900,7 @@ fn translate_match_lambda(ml:&MatchLambda,name_space:&mut dyn NameSpace,handle:&
     #[cfg(feature = "debug_terminators")]
     handle.code.push(Operation::PushTerminator);
 
-	for name in &holder.vars.names{
-		name_space.get(handle,*name)?;
-	}
+	push_captures(&holder.vars.names,name_space,handle,ml.debug_span);
 
 	let maker = Box::new(FuncMaker{
 		holder,
@@ -663,7 +912,21 @@ fn translate_match_lambda(ml:&MatchLambda,name_space:&mut dyn NameSpace,handle:&
 }
 
 // This function handles the process of taking source code and returning a `Code` object.
+// warnings are dropped, use try_compile_source to look at them
 pub fn compile_source_to_code(source_code: &str) -> Code<'_> {
+    compile_source(source_code, false)
+}
+
+// Same as `compile_source_to_code` but warnings can be turned into a failure
+pub fn compile_source(source_code: &str, deny_warnings: bool) -> Code<'_> {
+    let (code, warnings) = try_compile_source(source_code).expect("Failed to compile the program");
+    assert!(!deny_warnings || warnings.is_empty(), "the program has {} warnings", warnings.len());
+    code
+}
+
+// Reports any errors, returning None instead of panicking when compilation fails.
+// warnings are handed back for the caller to show (see report_warnings)
+pub fn try_compile_source(source_code: &str) -> Option<(Code<'_>, Vec<Warning>)> {
     // Step 1: Setup the StringTable (we will use Arc<RwLock<StringTable>> as required)
     let string_table = Arc::new(RwLock::new(StringTable::new()));
 
@@ -676,19 +939,196 @@ pub fn compile_source_to_code(source_code: &str) -> Code<'_> {
         Ok(ast) => ast,
         Err(e) => {
             report_parse_error(e, source_code, &write_table);
-            panic!("Failed to parse input");
+            return None;
         }
     };
     std::mem::drop(write_table);
 
     // Step 3: Translate the AST into the VM's bytecode using `translate_program`
     match translate_program(&parsed_ast, string_table.clone()) {
-        Ok(code) => code,
+        Ok(ans) => Some(ans),
         Err(e) => {
             report_err_list(&e, source_code, &string_table.try_read().unwrap());
-            panic!("Failed to translate the program");
+            None
         }
     }
 }
 
 
+#[cfg(test)]
+use crate::reporting::{Error,UndefinedName};
+
+#[cfg(test)]
+fn translate_source(source_code: &str) -> Result<(Code<'_>,Vec<Warning>),ErrList> {
+	let string_table = Arc::new(RwLock::new(StringTable::new()));
+	let lexer = Lexer::new(source_code);
+	let parsed_ast = ProgramParser::new()
+		.parse(source_code, &mut string_table.try_write().unwrap(), lexer)
+		.unwrap();
+	translate_program(&parsed_ast, string_table)
+}
+
+#[test]
+fn test_edit_distance() {
+	assert_eq!(edit_distance("",""),0);
+	assert_eq!(edit_distance("abc",""),3);
+	assert_eq!(edit_distance("sytem","system"),1);
+	assert_eq!(edit_distance("valeu","value"),1);
+	assert_eq!(edit_distance("kitten","sitting"),3);
+}
+
+#[test]
+fn collects_all_undefined_names() {
+	let source_code = "
+		def helper(x) { x }
+
+		def main(system) {
+			value = 2;
+			a = valeu + 1;
+			system(:println)(helpr(a));
+			f = fn(y) -> { y + valeu + zzz };
+			broken(f)
+		}
+
+		def other() { sytem }
+	";
+
+	let Err(errors) = translate_source(source_code) else {
+		panic!("translation should fail");
+	};
+
+	let missing :Vec<(&str,Option<u32>)> = errors.iter().map(|e| match e {
+		Error::Missing(UndefinedName{span,suggestion,..}) => (
+			&source_code[span.start().to_usize()..span.end().to_usize()],
+			*suggestion
+		),
+		e => panic!("unexpected error {:?}",e),
+	}).collect();
+
+	let names :Vec<&str> = missing.iter().map(|(name,_)| *name).collect();
+	assert_eq!(names,vec!["valeu","helpr","valeu","zzz","broken","sytem"]);
+
+	//the suggestions only make sense with the table used for parsing so just check which exist
+	let has_suggestion :Vec<bool> = missing.iter().map(|(_,s)| s.is_some()).collect();
+	assert_eq!(has_suggestion,vec![true,true,true,false,false,false]);
+}
+
+#[test]
+fn suggestions_come_from_scope_and_globals() {
+	let source_code = "
+		def helper(x) { x }
+		def main(system) {
+			value = 2;
+			match value {
+				2 => {inner = 1; helpr(innr + valeu)},
+				_ => 3
+			}
+		}
+	";
+
+	let string_table = Arc::new(RwLock::new(StringTable::new()));
+	let lexer = Lexer::new(source_code);
+	let parsed_ast = ProgramParser::new()
+		.parse(source_code, &mut string_table.try_write().unwrap(), lexer)
+		.unwrap();
+	let Err(errors) = translate_program(&parsed_ast, string_table.clone()) else {
+		panic!("translation should fail");
+	};
+
+	let table = string_table.read().unwrap();
+	let suggestions :Vec<&str> = errors.iter().map(|e| match e {
+		Error::Missing(UndefinedName{suggestion:Some(id),..}) => table.get_raw_str(*id),
+		e => panic!("unexpected error {:?}",e),
+	}).collect();
+	//arguments are translated before the function being called
+	assert_eq!(suggestions,vec!["inner","value","helper"]);
+}
+
+#[test]
+fn warns_on_unused_and_shadowed_names() {
+	let source_code = "
+		def used(x) { x }
+		def unused() { used(1) }
+
+		def main(system) {
+			never = 1;
+			_ignored = 2;
+			total = 0;
+			match total {
+				0 => {total = 1; total},
+				_ => {inner = 3; 4}
+			};
+			f = fn() -> { lost = 5; used(total) };
+			f()
+		}
+	";
+
+	let (code,warnings) = translate_source(source_code).unwrap();
+	let table = code.table.read().unwrap();
+
+	let found :Vec<(&str,&str)> = warnings.iter().map(|w| {
+		let (kind,NameWarning{id,span}) = match w {
+			Warning::UnusedVariable(n) => ("unused",n),
+			Warning::Shadowing(n) => ("shadow",n),
+			Warning::UnusedFunction(n) => ("function",n),
+		};
+		assert!(source_code[span.start().to_usize()..].starts_with(table.get_raw_str(*id)));
+		(kind,table.get_raw_str(*id))
+	}).collect();
+
+	assert_eq!(found,vec![
+		("shadow","total"),
+		("unused","inner"),
+		("unused","lost"),
+		("unused","never"),
+		("function","unused"),
+	]);
+}
+
+#[test]
+fn no_unused_functions_without_main() {
+	let source_code = "
+		def a() { b() }
+		def b() { 1 }
+	";
+	let (_code,warnings) = translate_source(source_code).unwrap();
+	assert!(warnings.is_empty());
+}
+
+#[test]
+fn records_name_refs_with_definitions() {
+	let source_code = "
+		def helper(a) { b = a; fn() -> {b + a} }
+		def main(system) { helper(1) }
+	";
+	let string_table = Arc::new(RwLock::new(StringTable::new()));
+	let parsed_ast = ProgramParser::new()
+		.parse(source_code, &mut string_table.try_write().unwrap(), Lexer::new(source_code))
+		.unwrap();
+	let mut refs = Vec::new();
+	translate_program_with_refs(&parsed_ast,string_table.clone(),&mut refs).unwrap();
+
+	let at = |s:Span| &source_code[s.start().to_usize()..s.end().to_usize()];
+	let found :Vec<(&str,Option<&str>)> = refs.iter().map(|r| (at(r.span),r.def.map(at))).collect();
+	assert_eq!(found,vec![
+		("a",Some("helper(a)")),
+		("b",Some("b")),
+		("b",Some("b")),
+		("a",Some("helper(a)")),
+		("helper",None),
+	]);
+
+	//the use of b inside the lambda points at the assignment outside it
+	assert_eq!(refs[1].span,refs[2].def.unwrap());
+}
+
+#[test]
+fn tests_count_as_used() {
+	let source_code = "
+		def helper() { 1 }
+		def test_helper() { helper() == 1 }
+		def main(system) { 0 }
+	";
+	let (_code,warnings) = translate_source(source_code).unwrap();
+	assert!(warnings.is_empty());
+}
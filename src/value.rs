@@ -4,6 +4,8 @@ use crate::vm::{StaticFunc,DataFunc};
 use crate::vm::FuncData;
 use std::sync::Weak;
 use std::sync::Arc;
+use crate::fstring::FString;
+use crate::stream::StreamRef;
 
 #[derive(Clone,Debug)]
 #[repr(u32)] //optimized for 64bit architctures
@@ -14,11 +16,13 @@ pub enum Value<'code> {
     Int(i64)=3,
     Float(f64)=4,
     Atom(u32)=5,
-    String(Arc<String>)=6,
+    String(Arc<FString>)=6,
     Func(Arc<FuncData<'code>>)=7,
     WeakFunc(Weak<FuncData<'code>>)=8,
     StaticFunc(StaticFunc)=9,
     DataFunc(DataFunc)=10,
+    Bytes(Arc<[u8]>)=11,
+    Stream(StreamRef<'code>)=12,
 }
 
 impl PartialEq for Value<'_> {
@@ -30,13 +34,15 @@ impl PartialEq for Value<'_> {
             (Value::WeakFunc(weak), Value::Func(func)) | (Value::Func(func), Value::WeakFunc(weak)) => weak.as_ptr()==Arc::as_ptr(func),
             (Value::Func(a), Value::Func(b)) => Arc::as_ptr(a) == Arc::as_ptr(b),
             (Value::DataFunc(a), Value::DataFunc(b)) => a==b,
-            (Value::StaticFunc(a),Value::StaticFunc(b)) => a==b,
+            (Value::StaticFunc(a),Value::StaticFunc(b)) => std::ptr::fn_addr_eq(*a,*b),
             (Value::Nil, Value::Nil) => true,
             (Value::Bool(a), Value::Bool(b)) => a == b,
             (Value::Int(a), Value::Int(b)) => a == b,
             (Value::Float(a), Value::Float(b)) => a == b || a.is_nan() && b.is_nan(),
             (Value::Atom(a), Value::Atom(b)) => a == b,
             (Value::String(a), Value::String(b)) => Arc::ptr_eq(a, b) || *a == *b,
+            (Value::Bytes(a), Value::Bytes(b)) => a == b,
+            (Value::Stream(a), Value::Stream(b)) => Arc::ptr_eq(a, b),
             _ => false,
         }
     }
@@ -66,6 +72,7 @@ impl Hash for Value<'_> {
                     let string_content = &**arc;  // Dereference the `Arc` to get the string
                     string_content.hash(state);   // Hash the string itself
                 },
+            Value::Bytes(bytes) => bytes.hash(state),
             Value::Func(arc) => {
                 let ptr = Arc::as_ptr(arc);
                 state.write_usize(ptr as usize);
@@ -76,6 +83,7 @@ impl Hash for Value<'_> {
             }
             Value::StaticFunc(func) => func.hash(state),
             Value::DataFunc(func) => func.hash(state),
+            Value::Stream(s) => state.write_usize(Arc::as_ptr(s) as *const () as usize),
         }
     }
 }
@@ -93,7 +101,7 @@ fn test_value_partial_eq() {
     let value_int_42 = Value::Int(42);
     let value_float = Value::Float(6.9);
     let value_atom = Value::Atom(123);
-    let value_string = Value::String(Arc::new(String::from("Hello")));
+    let value_string = Value::String(Arc::new(FString::from("Hello")));
     let func = Arc::new(func_data);
     let value_func = Value::Func(func.clone());
     let value_weak_func = Value::WeakFunc(Arc::downgrade(&func));
@@ -103,7 +111,7 @@ fn test_value_partial_eq() {
     assert_eq!(value_int_42, Value::Int(42));
     assert_ne!(value_float, Value::Float(2.71));
     assert_eq!(value_atom, Value::Atom(123));
-    assert_eq!(value_string, Value::String(Arc::new(String::from("Hello"))));
+    assert_eq!(value_string, Value::String(Arc::new(FString::from("Hello"))));
     assert_eq!(value_func, Value::Func(func.clone()));
     assert_eq!(value_weak_func, Value::WeakFunc(Arc::downgrade(&func)));
     assert_eq!(value_weak_func, value_func);
@@ -3,8 +3,10 @@ use crate::runtime::FuncHolder;
 use core::hash::Hasher;
 use core::hash::Hash;
 use core::fmt;
-use crate::basic_ops::call_string;
+use crate::basic_ops::{call_string,call_bytes};
 use crate::reporting::stacked_error;
+use crate::reporting::is_pause;
+use crate::stream::{call_stream,Generators};
 use crate::reporting::match_error;
 use crate::basic_ops::non_callble_error;
 use crate::reporting::recursion_error;
@@ -17,6 +19,7 @@ use crate::reporting::InternalError;
 use codespan::Span;
 use std::collections::HashMap;
 use std::sync::Arc;
+use crate::fstring::FString;
 use crate::value::Value;
 use crate::value::VarTable;
 
@@ -24,7 +27,7 @@ use crate::basic_ops;
 
 
 use crate::reporting::{Error,ErrList};
-use crate::stack::ValueStack;
+use crate::stack::{ValueStack,ValueTag};
 // use crate::value::Scope;
 use ast::ast::StringTable;
 
@@ -36,13 +39,30 @@ use ast::id::ERR_ID;
 #[cfg(test)]
 use ast::get_id;
 
-pub type DynFunc =dyn for<'code> Fn(&mut ValueStack<'code>,&StringTable<'code>) -> Result<(),ErrList>;
+pub type DynFunc =dyn for<'code> Fn(&mut ValueStack<'code>,&StringTable<'code>) -> Result<(),ErrList> + Send + Sync;
 pub type StaticFunc = for<'code> fn(&mut ValueStack<'code>,&StringTable<'code>) -> Result<(),ErrList>;
 
+//what a data func holds besides its code. arrays and maps from make_array and make_map keep their
+//contents here so natives can read them back without calling the function (which could be any host code)
+#[derive(Clone,Debug)]
+pub enum DataKind {
+    Host,
+    Array(Arc<[Value<'static>]>),
+    Map(Arc<[(Value<'static>,Value<'static>)]>),
+    //the answer of a host call that is not ready yet, see Context::run_suspendable
+    Pending(u64),
+}
+
+//the kind lives in the same allocation as the closure so a DataFunc stays one fat pointer
+pub struct DataInner<F:?Sized> {
+    pub kind: DataKind,
+    pub func: F,
+}
+
 #[derive(Clone)]
 #[repr(transparent)]
 pub struct DataFunc {
-    pub inner: Arc<DynFunc>
+    pub inner: Arc<DataInner<DynFunc>>
 }
 
 // Implement Debug for DynFunc
@@ -72,9 +92,36 @@ impl Hash for DataFunc {
     }
 }
 
+impl DataFunc {
+    //the bound lets closures that forward to native functions infer a signature for every lifetime
+    pub fn new<F>(f: F) -> Self
+    where F: for<'code> Fn(&mut ValueStack<'code>,&StringTable<'code>) -> Result<(),ErrList> + Send + Sync + 'static {
+        DataFunc::with_kind(DataKind::Host,f)
+    }
+
+    pub fn with_kind<F>(kind: DataKind, f: F) -> Self
+    where F: for<'code> Fn(&mut ValueStack<'code>,&StringTable<'code>) -> Result<(),ErrList> + Send + Sync + 'static {
+        DataFunc { inner: Arc::new(DataInner{kind,func:f}) }
+    }
+
+    //what a host function returns instead of its answer when it has to wait,
+    //the answer comes later through Context::resume with the same token
+    pub fn pending(token:u64) -> Self {
+        DataFunc::with_kind(DataKind::Pending(token),|_stack: &mut ValueStack, _table: &StringTable| Err(sig_error()))
+    }
+
+    pub fn kind(&self) -> &DataKind {
+        &self.inner.kind
+    }
+
+    pub fn call<'code>(&self,stack:&mut ValueStack<'code>,table:&StringTable<'code>) -> Result<(),ErrList> {
+        (self.inner.func)(stack,table)
+    }
+}
+
 impl From<Arc<DynFunc>> for DataFunc {
 
-    fn from(val: Arc<(dyn for<'code> Fn(&mut ValueStack<'code>, &StringTable<'code>) -> Result<(),ErrList> + 'static)>) -> Self { DataFunc { inner: val }}
+    fn from(val: Arc<DynFunc>) -> Self { DataFunc::new(move |stack,table| val(stack,table)) }
 }
 
 
@@ -177,8 +224,19 @@ pub struct Context<'code> {
     pub stack: ValueStack<'code>,    
     pub table: &'code StringTable<'code>,//for errors only
 
+    //generator streams this context pulled from, each suspended in its own context
+    pub generators: Generators<'code>,
+    //the token of the host call run_suspendable stopped on
+    pending: Option<u64>,
+    //only set while run_suspendable drives the context
+    suspendable: bool,
+}
 
-    
+//where run_suspendable and resume stopped
+#[derive(Debug,PartialEq)]
+pub enum Run<'code> {
+    Done(Value<'code>),
+    Suspended(u64),
 }
 
 impl<'code> Context<'code> {
@@ -210,6 +268,9 @@ impl<'code> Context<'code> {
             stack:ValueStack::default(),
             func,global_vars,
             table,call_stack,
+            generators:Generators::new(),
+            pending:None,
+            suspendable:false,
         }
     }
 
@@ -314,14 +375,25 @@ impl<'code> Context<'code> {
                 call_string(s,&mut self.stack,self.table,span)?;
                 Ok(None)
             },
+            Value::Bytes(b) => {
+                call_bytes(b,&mut self.stack,self.table,span)?;
+                Ok(None)
+            },
+            Value::Stream(s) => {
+                call_stream(&s,self,span)
+                .map_err(|err| stacked_error("while using a stream",err,span))?;
+                Ok(None)
+            },
             Value::StaticFunc(extern_func) => {
                 #[cfg(feature = "debug_print_vm")] 
                 println!("calling extern func: {:?}",extern_func);
                 
                 extern_func(&mut self.stack,self.table)
-                .map_err(|err| 
-                    stacked_error("while calling external function",err,span)
-                 )?;
+                .map_err(|err| match is_pause(&err) {
+                    true => err,
+                    false => stacked_error("while calling external function",err,span),
+                })?;
+                self.take_pending()?;
                 
                 Ok(None)
             },
@@ -329,10 +401,12 @@ impl<'code> Context<'code> {
                 #[cfg(feature = "debug_print_vm")] 
                 println!("calling extern data func: {:?}",extern_func);
 
-                (extern_func.inner)(&mut self.stack,self.table)
-                .map_err(|err| 
-                    stacked_error("while calling external stateful function",err,span)
-                 )?;
+                extern_func.call(&mut self.stack,self.table)
+                .map_err(|err| match is_pause(&err) {
+                    true => err,
+                    false => stacked_error("while calling external stateful function",err,span),
+                })?;
+                self.take_pending()?;
                 
                 Ok(None)
             },
@@ -569,9 +643,32 @@ impl<'code> Context<'code> {
         }.map_err(|e| self.trace_error(e))
     }
 
+    //a host call that answered with a pending handle stops the context if run_suspendable drives it
+    //and is an error anywhere else, the handle itself never reaches the script
+    fn take_pending(&mut self) -> Result<(),ErrList> {
+        if self.stack.peak_tag() != Some(ValueTag::DataFunc) {
+            return Ok(());
+        }
+        let Some(Value::DataFunc(f)) = self.stack.pop_value() else {
+            return Err(bug_error("tag without its value"));
+        };
+        match *f.kind() {
+            DataKind::Pending(token) if self.suspendable => {
+                self.pending = Some(token);
+                Ok(())
+            },
+            DataKind::Pending(token) => Err(Error::Suspend(token).to_list()),
+            _ => self.stack.push_value(Value::DataFunc(f)).map_err(|_| overflow_error()),
+        }
+    }
+
     #[cold]
     fn trace_error(&self,mut err:ErrList) -> ErrList {
-        
+        //a yield is handled by the scheduler and the call resumes from here
+        if is_pause(&err) {
+            return err;
+        }
+
         for ret in self.call_stack.iter().rev() {
             for (span,tailed) in &ret.tail_debug.inner {
                 let err_in = InternalError{span: *span,err,message:"while calling function"};
@@ -602,8 +699,44 @@ impl<'code> Context<'code> {
         }
     }
 
+    //a new context running func over the same globals, func's args go on its stack before start
+    pub fn fork(&self,func:Arc<FuncData<'code>>) -> Context<'code> {
+        Context::new(func,self.global_vars,self.table)
+    }
+
+    //runs f(args) to the end in the middle of the current op and gives back what it returned.
+    //this is how natives the VM runs itself (streams) call back into the script
+    pub fn call_value(&mut self,f:Value<'code>,args:&[Value<'code>],span:Span) -> Result<Value<'code>,ErrList> {
+        //the native op waiting on the answer can not stop halfway so a pending host call in here is an error
+        let suspendable = std::mem::replace(&mut self.suspendable,false);
+        let ans = self.finish_call(f,args,span);
+        self.suspendable = suspendable;
+        ans
+    }
+
+    fn finish_call(&mut self,f:Value<'code>,args:&[Value<'code>],span:Span) -> Result<Value<'code>,ErrList> {
+        let depth = self.call_stack.len();
+        self.stack.push_terminator().map_err(|_| overflow_error())?;
+        for arg in args {
+            self.stack.push_value(arg.clone()).map_err(|_| overflow_error())?;
+        }
+        self.stack.push_value(f).map_err(|_| overflow_error())?;
+
+        self.call(span)?;
+        while self.call_stack.len() > depth {
+            self.next_op()?;
+        }
+        self.stack.pop_value().ok_or_else(|| bug_error("over poping"))
+    }
+
+    //pops the args pushed for func, call once before stepping with next_op
+    pub fn start(&mut self) -> Result<(),ErrList> {
+        self.set_args()
+    }
+
     pub fn run(&mut self) -> Result<Value<'code>,ErrList> {
-        self.set_args()?;
+        self.suspendable = false;
+        self.start()?;
 
         let mut keep_running = true;
         while keep_running {
@@ -614,10 +747,44 @@ impl<'code> Context<'code> {
             )
     }
 
+    //like run but a host function returning DataFunc::pending(token) stops it with Run::Suspended(token)
+    //instead of failing. the host answers with resume once it has the value so one thread can drive many contexts
+    pub fn run_suspendable(&mut self) -> Result<Run<'code>,ErrList> {
+        self.suspendable = true;
+        self.start()?;
+        self.drive()
+    }
+
+    //finishes the suspended host call with value and keeps going
+    pub fn resume(&mut self,token:u64,value:Value<'code>) -> Result<Run<'code>,ErrList> {
+        if self.pending != Some(token) {
+            return Err(Error::NotSuspended(token).to_list());
+        }
+        self.pending = None;
+        self.stack.push_value(value).map_err(|_| overflow_error())?;
+        self.drive()
+    }
+
+    fn drive(&mut self) -> Result<Run<'code>,ErrList> {
+        loop {
+            if !self.next_op()? {
+                break;
+            }
+            if let Some(token) = self.pending {
+                return Ok(Run::Suspended(token));
+            }
+        }
+        self.stack.pop_value()
+            .map(Run::Done)
+            .ok_or_else(||{bug_error("over poping")}
+            )
+    }
+
     pub fn reset(&mut self) {
         *self.mut_vars = self.func.mut_vars_template.clone();
         self.pos=0;
         self.stack = ValueStack::new();
+        self.pending = None;
     }
 }
 
@@ -657,7 +824,7 @@ pub enum Operation {
     PushNil,
     PushFloat(f64),
     PushInt(i64),
-    PushString(Arc<String>),
+    PushString(Arc<FString>),
 
     // BinOp{op:basic_ops::BinOp,span: Span},//too fat
     Add(Span),
@@ -819,8 +986,8 @@ fn test_string_match() {
     let mut string_table = StringTable::new();
 
     // Create two different `Arc` instances holding the same string content
-    let arc_str1 = Arc::new("match_string".to_string());
-    let arc_str2 = Arc::new("match_string".to_string());
+    let arc_str1: Arc<FString> = Arc::new("match_string".into());
+    let arc_str2: Arc<FString> = Arc::new("match_string".into());
 
     let mut match_map = HashMap::new();
     match_map.insert(Value::String(arc_str1.clone()), 4); // Map the string to some operation (4)
@@ -863,7 +1030,7 @@ fn test_string_match() {
 
    
     // Test generic
-    let unmatched_str = Arc::new("different_string".to_string());
+    let unmatched_str: Arc<FString> = Arc::new("different_string".into());
      // Push the second Arc (different address but same content)
     context.stack.push_value(Value::String(unmatched_str)).unwrap();
     let result = context.run().unwrap();